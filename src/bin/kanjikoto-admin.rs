@@ -0,0 +1,401 @@
+//! Server administration from the command line, without starting the web server.
+//!
+//! Reads the same `kanjikoto.toml` and `KANJIKOTO_*` settings as the server. Output is plain,
+//! tab-separated text and failures exit with status 1, so the commands can be used in scripts.
+
+use kanjikoto::core::api::{get_drills_url, parse_drills, write_sheet};
+use kanjikoto::core::backend::account::{DeleteAccount, ExportAccount, ImportAccount};
+use kanjikoto::core::backend::backup::{
+    backup_path, list_backups, BackupDatabase, RestoreDatabase, SnapshotDatabase,
+};
+use kanjikoto::core::backend::clock::SystemClock;
+use kanjikoto::core::backend::config::{Config, ConfigError};
+use kanjikoto::core::backend::export::QueryLessonSheet;
+use kanjikoto::core::backend::import::ImportLesson;
+use kanjikoto::core::backend::insert_lesson::InsertLesson;
+use kanjikoto::core::backend::lesson::QueryLessonStatus;
+use kanjikoto::core::backend::migrate::{self, LATEST_VERSION};
+use kanjikoto::core::backend::schedule::QueryUserDay;
+use kanjikoto::core::backend::settings::QueryPracticeLimits;
+use kanjikoto::core::backend::token::{CreateToken, QueryTokens};
+use kanjikoto::core::backend::{connect, get_users, read_user_lessons, CreateUser, StorageError};
+use kanjikoto::core::data::account::AccountArchive;
+use kanjikoto::core::data::db::init_config;
+use kanjikoto::core::data::import::DuplicatePolicy;
+use kanjikoto::core::data::new_phrases;
+use rusqlite::Connection;
+use thiserror::Error;
+
+const USAGE: &str = "\
+Usage: kanjikoto-admin <command>
+
+Commands:
+  user add <user> [--admin]       Create a user with the configured default settings
+  user list                       List users and whether they are admins
+  user export <user> [--output <file>]
+                                  Write a user's account archive as JSON, to stdout by default
+  user import <file> [--as <user>]
+                                  Recreate an account archive's lessons, history and settings
+  user delete <user>              Delete a user and everything they made
+  token create <user> [--name <name>]
+                                  Make an API token for the user and print its secret
+  token list <user>               List the user's API tokens
+  import <title> <file-or-url>    Import a CSV sheet into a new lesson
+      [--owner <user>] [--duplicates skip|merge|link|keep]
+  export <lesson-id> [--output <file>]
+                                  Write a lesson as a CSV sheet, to stdout by default
+  status [--owner <user>]         Print each lesson's ready and learned phrase counts
+  migrate                         Bring the database schema up to date
+  backups                         List the backups in the configured backup directory
+  backup [<file>]                 Copy the database to a file, or to a new rotated backup
+  restore <file-or-backup>        Replace the database's contents with a backup, backing
+                                  the replaced contents up first
+
+The owner defaults to the configured admin.";
+
+#[derive(Error, Debug)]
+enum AdminError {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+    #[error("{0}")]
+    Refused(String),
+    #[error("Invalid configuration: {0}")]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("{0}: {1}")]
+    Json(String, serde_json::Error),
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(err) = run(args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(mut args: Vec<String>) -> Result<(), AdminError> {
+    if args.is_empty() || take_flag(&mut args, "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let command = args.remove(0);
+    let config = init_config()?;
+    match command.as_str() {
+        "user" => user(config, args),
+        "token" => token(config, args),
+        "import" => import(config, args),
+        "export" => export(config, args),
+        "status" => status(config, args),
+        "migrate" => {
+            no_more(args)?;
+            let conn = connect(config)?;
+            println!(
+                "{} is at schema version {} of {LATEST_VERSION}",
+                config.database,
+                migrate::version(&conn)?
+            );
+            Ok(())
+        }
+        "backups" => {
+            no_more(args)?;
+            for backup in list_backups(&config.backup.directory)? {
+                println!(
+                    "{}\t{}\t{} bytes",
+                    backup.name, backup.taken_at, backup.bytes
+                );
+            }
+            Ok(())
+        }
+        "backup" => {
+            let path = match args.as_slice() {
+                [] => {
+                    let backup = SnapshotDatabase {
+                        directory: config.backup.directory.clone(),
+                        keep: config.backup.keep,
+                        clock: &SystemClock,
+                    }
+                    .apply(&connect(config)?)?;
+                    format!("{}/{}", config.backup.directory, backup.name)
+                }
+                _ => {
+                    let [path] = positional(args)?;
+                    BackupDatabase { path: path.clone() }.apply(&connect(config)?)?;
+                    path
+                }
+            };
+            println!("Backed up {} to {path}", config.database);
+            Ok(())
+        }
+        "restore" => {
+            let [source] = positional(args)?;
+            // A name from `backups`, or else a path to any backup file.
+            let path = backup_path(&config.backup.directory, &source).unwrap_or(source);
+            let replaced = RestoreDatabase {
+                path: path.clone(),
+                directory: config.backup.directory.clone(),
+                clock: &SystemClock,
+            }
+            .apply(&mut connect(config)?)?;
+            println!(
+                "Restored {} from {path}, backing up the data it replaced to {}/{}",
+                config.database, config.backup.directory, replaced.name
+            );
+            Ok(())
+        }
+        command => Err(AdminError::Usage(format!("Unknown command: {command}"))),
+    }
+}
+
+fn user(config: &Config, mut args: Vec<String>) -> Result<(), AdminError> {
+    if args.is_empty() {
+        return Err(AdminError::Usage("Missing user command".to_string()));
+    }
+    let mut conn = connect(config)?;
+    match args.remove(0).as_str() {
+        "add" => {
+            let is_admin = take_flag(&mut args, "--admin");
+            let [user_id] = positional(args)?;
+            if get_users(&conn)?.iter().any(|user| user.id == user_id) {
+                return Err(AdminError::Refused(format!(
+                    "User {user_id} already exists"
+                )));
+            }
+            CreateUser {
+                user_id: user_id.clone(),
+                is_admin,
+                settings: config.user_defaults(),
+            }
+            .apply(&conn)?;
+            println!("Created {user_id}");
+        }
+        "list" => {
+            no_more(args)?;
+            for user in get_users(&conn)? {
+                let role = if user.is_admin { "admin" } else { "user" };
+                println!("{}\t{role}", user.id);
+            }
+        }
+        "export" => {
+            let output = take_option(&mut args, "--output")?;
+            let [user_id] = positional(args)?;
+            let archive = ExportAccount {
+                user_id,
+                clock: &SystemClock,
+            }
+            .apply(&conn)?;
+            let json = serde_json::to_string_pretty(&archive)
+                .map_err(|err| AdminError::Json(archive.user_id.clone(), err))?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json).map_err(|err| AdminError::Io(path, err))?
+                }
+                None => println!("{json}"),
+            }
+        }
+        "import" => {
+            let user_id = take_option(&mut args, "--as")?;
+            let [path] = positional(args)?;
+            let json =
+                std::fs::read_to_string(&path).map_err(|err| AdminError::Io(path.clone(), err))?;
+            let archive: AccountArchive =
+                serde_json::from_str(&json).map_err(|err| AdminError::Json(path, err))?;
+            let user_id = user_id.unwrap_or_else(|| archive.user_id.clone());
+            let lessons = ImportAccount {
+                user_id: user_id.clone(),
+                archive,
+            }
+            .apply(&mut conn)?;
+            println!("Imported {} lessons for {user_id}", lessons.len());
+        }
+        "delete" => {
+            let [user_id] = positional(args)?;
+            DeleteAccount {
+                user_id: user_id.clone(),
+                admin: config.admin.clone(),
+            }
+            .apply(&conn)?;
+            println!("Deleted {user_id}");
+        }
+        command => {
+            return Err(AdminError::Usage(format!(
+                "Unknown user command: {command}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn token(config: &Config, mut args: Vec<String>) -> Result<(), AdminError> {
+    if args.is_empty() {
+        return Err(AdminError::Usage("Missing token command".to_string()));
+    }
+    let conn = connect(config)?;
+    match args.remove(0).as_str() {
+        "create" => {
+            let name = take_option(&mut args, "--name")?.unwrap_or_else(|| "CLI".to_string());
+            let [user_id] = positional(args)?;
+            if !get_users(&conn)?.iter().any(|user| user.id == user_id) {
+                return Err(AdminError::Refused(format!("No user {user_id}")));
+            }
+            let created = CreateToken { user_id, name }.apply(&conn)?;
+            println!("{}", created.secret);
+        }
+        "list" => {
+            let [user_id] = positional(args)?;
+            for token in (QueryTokens { user_id }).apply(&conn)? {
+                let last_used = token.last_used_at.as_deref().unwrap_or("never");
+                println!(
+                    "{}\t{}\t{}\t{last_used}",
+                    token.token_id, token.name, token.created_at
+                );
+            }
+        }
+        command => {
+            return Err(AdminError::Usage(format!(
+                "Unknown token command: {command}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn import(config: &Config, mut args: Vec<String>) -> Result<(), AdminError> {
+    let owner = take_option(&mut args, "--owner")?.unwrap_or_else(|| config.admin.clone());
+    let duplicates = match take_option(&mut args, "--duplicates")? {
+        Some(value) => DuplicatePolicy::parse(&value)
+            .ok_or_else(|| AdminError::Usage(format!("Unknown duplicate policy: {value}")))?,
+        None => DuplicatePolicy::default(),
+    };
+    let [title, source] = positional(args)?;
+    let drills = if source.starts_with("http://") || source.starts_with("https://") {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| AdminError::Io("Failed to start the runtime".to_string(), err))?;
+        runtime.block_on(get_drills_url(&source))?
+    } else {
+        let csv =
+            std::fs::read_to_string(&source).map_err(|err| AdminError::Io(source.clone(), err))?;
+        parse_drills(&csv)?
+    };
+    config.import.check_rows(drills.len())?;
+    let mut conn = connect(config)?;
+    let day = QueryUserDay {
+        user_id: owner.clone(),
+        clock: &SystemClock,
+    }
+    .apply(&conn)?;
+    let phrases = new_phrases(drills, Some(day.now))?;
+    let rows = phrases.len();
+    let lesson_id = ImportLesson {
+        lesson: InsertLesson {
+            title,
+            owner,
+            phrases,
+        },
+        duplicates,
+    }
+    .apply(&mut conn)?;
+    match lesson_id {
+        Some(lesson_id) => {
+            let imported = count_phrases(&conn, lesson_id)?;
+            println!("Imported {imported} of {rows} rows into lesson {lesson_id}");
+        }
+        None => println!("All {rows} rows were duplicates, so no lesson was made"),
+    }
+    Ok(())
+}
+
+fn export(config: &Config, mut args: Vec<String>) -> Result<(), AdminError> {
+    let output = take_option(&mut args, "--output")?;
+    let [lesson_id] = positional(args)?;
+    let lesson_id = lesson_id
+        .parse()
+        .map_err(|_| AdminError::Usage(format!("Not a lesson id: {lesson_id}")))?;
+    let conn = connect(config)?;
+    let (_, phrases) = QueryLessonSheet { lesson_id }.apply(&conn)?;
+    let sheet = write_sheet(&phrases)?;
+    match output {
+        Some(path) => std::fs::write(&path, sheet).map_err(|err| AdminError::Io(path, err))?,
+        None => print!("{sheet}"),
+    }
+    Ok(())
+}
+
+fn status(config: &Config, mut args: Vec<String>) -> Result<(), AdminError> {
+    let owner = take_option(&mut args, "--owner")?.unwrap_or_else(|| config.admin.clone());
+    no_more(args)?;
+    let conn = connect(config)?;
+    let day = QueryUserDay {
+        user_id: owner.clone(),
+        clock: &SystemClock,
+    }
+    .apply(&conn)?;
+    for lesson in read_user_lessons(&owner, &conn)? {
+        let limits = QueryPracticeLimits {
+            user_id: owner.clone(),
+            lesson_id: lesson.lesson_id,
+        }
+        .apply(&conn)?;
+        let status = QueryLessonStatus {
+            lesson_id: lesson.lesson_id,
+            day,
+            limits,
+            tag: None,
+        }
+        .apply(&conn)?;
+        println!(
+            "{}\t{}\t{} phrases\t{} ready\t{} learned",
+            lesson.lesson_id,
+            lesson.title,
+            count_phrases(&conn, lesson.lesson_id)?,
+            status.ready,
+            status.learned
+        );
+    }
+    Ok(())
+}
+
+fn count_phrases(conn: &Connection, lesson_id: i64) -> Result<usize, StorageError> {
+    const SQL: &str = "SELECT COUNT(*) FROM phrases WHERE lesson_id = ?1";
+    let count = conn.query_row(SQL, [lesson_id], |row| row.get(0))?;
+    Ok(count)
+}
+
+/// Removes `name` from the arguments, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let found = args.iter().any(|arg| arg == name);
+    args.retain(|arg| arg != name);
+    found
+}
+
+/// Removes `name` and the value after it from the arguments.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, AdminError> {
+    let Some(at) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if at + 1 == args.len() {
+        return Err(AdminError::Usage(format!("{name} needs a value")));
+    }
+    let value = args.remove(at + 1);
+    args.remove(at);
+    Ok(Some(value))
+}
+
+/// Exactly `N` arguments, with no options left over.
+fn positional<const N: usize>(args: Vec<String>) -> Result<[String; N], AdminError> {
+    if let Some(option) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(AdminError::Usage(format!("Unknown option: {option}")));
+    }
+    let count = args.len();
+    args.try_into()
+        .map_err(|_| AdminError::Usage(format!("Expected {N} arguments but got {count}")))
+}
+
+fn no_more(args: Vec<String>) -> Result<(), AdminError> {
+    positional::<0>(args).map(|_| ())
+}
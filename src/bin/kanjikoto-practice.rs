@@ -0,0 +1,340 @@
+//! Practice a lesson's due cards in a terminal, for studying over SSH.
+//!
+//! Uses the same configuration, database and deck as the web app, records each answer as it is
+//! given, and keeps a practice session as the web dialog does, so a session here counts the same
+//! as one in the browser. Quitting leaves the session open to resume there.
+
+use kanjikoto::core::backend::clock::SystemClock;
+use kanjikoto::core::backend::config::{Config, ConfigError};
+use kanjikoto::core::backend::insert_lesson::RevertLessonTime;
+use kanjikoto::core::backend::lesson::QueryPracticeCards;
+use kanjikoto::core::backend::phrase::{BuryPhrase, RecordAnswer, RevertLapse, SuspendPhrase};
+use kanjikoto::core::backend::practice_session::{FinishSession, SaveSession, StartSession};
+use kanjikoto::core::backend::schedule::{Day, QueryUserDay};
+use kanjikoto::core::backend::settings::{QueryPracticeLimits, QueryUserSettings};
+use kanjikoto::core::backend::{connect, read_user_lessons, StorageError};
+use kanjikoto::core::data::card::Card;
+use kanjikoto::core::data::db::init_config;
+use kanjikoto::core::data::deck::{Answer, Deck, Removal, Stats};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rusqlite::Connection;
+use std::io::{BufRead, Write};
+use thiserror::Error;
+
+const USAGE: &str = "\
+Usage: kanjikoto-practice [<lesson-id>] [--tag <tag>] [--user <user>]
+
+Practices the lesson's due cards, or the user's first lesson's. The user defaults to the
+configured admin. Type a key and press Enter to answer.";
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Error, Debug)]
+enum PracticeError {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+    #[error("Invalid configuration: {0}")]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Terminal: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Practice session: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Where the top card of the deck is in its turn.
+enum Turn {
+    Prompt,
+    Learn,
+    Check,
+}
+
+/// A lesson's session and the database its answers are written to.
+struct Session {
+    conn: Connection,
+    /// The `practice_sessions` row the deck is saved to, once started.
+    session_id: Option<i64>,
+    user_id: String,
+    day: Day,
+    leech_threshold: usize,
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(err) = run(args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(mut args: Vec<String>) -> Result<(), PracticeError> {
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let tag = take_option(&mut args, "--tag")?;
+    let user = take_option(&mut args, "--user")?;
+    let lesson_id = match args.as_slice() {
+        [] => None,
+        [lesson_id] => Some(
+            lesson_id
+                .parse::<i64>()
+                .map_err(|_| PracticeError::Usage(format!("Not a lesson id: {lesson_id}")))?,
+        ),
+        [_, extra, ..] => return Err(PracticeError::Usage(format!("Unexpected {extra}"))),
+    };
+    let config = init_config()?;
+    let user_id = user.unwrap_or_else(|| config.admin.clone());
+    let mut session = open(config, user_id)?;
+    let lessons = read_user_lessons(&session.user_id, &session.conn)?;
+    let lesson = match lesson_id {
+        Some(lesson_id) => lessons.into_iter().find(|l| l.lesson_id == lesson_id),
+        None => lessons.into_iter().next(),
+    }
+    .ok_or(StorageError::NotFound("Lesson"))?;
+    let limits = QueryPracticeLimits {
+        user_id: session.user_id.clone(),
+        lesson_id: lesson.lesson_id,
+    }
+    .apply(&session.conn)?;
+    let cards = QueryPracticeCards {
+        lesson_id: lesson.lesson_id,
+        day: session.day,
+        limits,
+        tag,
+    }
+    .apply(&session.conn)?;
+    if cards.is_empty() {
+        println!("Nothing to practice in {} today.", lesson.title);
+        return Ok(());
+    }
+    let deck = Deck::from_cards(cards, ChaCha8Rng::seed_from_u64(rand::random()));
+    session.start(lesson.lesson_id, &deck)?;
+    practice(session, &lesson.title, deck)
+}
+
+fn open(config: &Config, user_id: String) -> Result<Session, StorageError> {
+    let conn = connect(config)?;
+    let day = QueryUserDay {
+        user_id: user_id.clone(),
+        clock: &SystemClock,
+    }
+    .apply(&conn)?;
+    let settings = QueryUserSettings {
+        user_id: user_id.clone(),
+    }
+    .apply(&conn)?;
+    Ok(Session {
+        conn,
+        session_id: None,
+        user_id,
+        day,
+        leech_threshold: settings.leech_threshold,
+    })
+}
+
+/// Drives the deck like the web practice dialog until every card is passed or the user quits.
+fn practice(mut session: Session, title: &str, mut deck: Deck) -> Result<(), PracticeError> {
+    let mut input = std::io::stdin().lock();
+    let mut turn = Turn::Prompt;
+    loop {
+        let keys = match turn {
+            Turn::Prompt => "[Enter] check  [l] learn  [u] undo  [q] quit",
+            Turn::Learn => "[Enter] next  [q] quit",
+            Turn::Check => "[p] pass  [f] fail  [r] repeat  [b] bury  [s] suspend  [q] quit",
+        };
+        render(title, &deck, &turn, keys)?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let key = line.trim().to_lowercase();
+        (deck, turn) = match (turn, key.as_str()) {
+            (_, "q") => break,
+            (Turn::Prompt, "") => (deck, Turn::Check),
+            (Turn::Prompt, "l") => (deck, Turn::Learn),
+            (Turn::Prompt, "u") if deck.last_answer().is_some() => {
+                let answer = deck.last_answer().expect("An answer to undo");
+                let deck = deck.undo();
+                session.revert(answer, &deck.top)?;
+                let turn = match answer {
+                    Answer::Learn => Turn::Learn,
+                    _ => Turn::Check,
+                };
+                (deck, turn)
+            }
+            (Turn::Learn, "") => (deck.learn(), Turn::Prompt),
+            (Turn::Check, "p") => {
+                session.record(Answer::Pass, &deck.top)?;
+                (deck.pass(), Turn::Prompt)
+            }
+            (Turn::Check, "f") => {
+                session.record(Answer::Fail, &deck.top)?;
+                (deck.fail(), Turn::Prompt)
+            }
+            (Turn::Check, "r") => (deck.repeat(), Turn::Prompt),
+            (Turn::Check, "b" | "s") => {
+                let answer = if key == "b" {
+                    Answer::Bury
+                } else {
+                    Answer::Suspend
+                };
+                session.record(answer, &deck.top)?;
+                match deck.remove_top(answer) {
+                    Removal::Next(deck) => (deck, Turn::Prompt),
+                    Removal::Emptied(deck) => {
+                        session.save(&deck)?;
+                        session.finish(deck.stats)?;
+                        println!("{CLEAR}No cards left in {title}.");
+                        return Ok(());
+                    }
+                }
+            }
+            (turn, _) => (deck, turn),
+        };
+        if matches!(turn, Turn::Prompt) {
+            session.save(&deck)?;
+        }
+        if deck.is_all_passed() {
+            session.finish(deck.stats.clone())?;
+            let stats = &deck.stats;
+            println!(
+                "{CLEAR}{BOLD}{title}{RESET} done: {} passed, {} failed, {} repeated, {} learned.",
+                stats.passed, stats.failed, stats.repeated, stats.learned
+            );
+            return Ok(());
+        }
+    }
+    println!("{CLEAR}Stopped with {} turns left.", deck.turns_remaining());
+    Ok(())
+}
+
+fn render(title: &str, deck: &Deck, turn: &Turn, keys: &str) -> std::io::Result<()> {
+    let card = &deck.top;
+    let mut out = std::io::stdout().lock();
+    write!(
+        out,
+        "{CLEAR}{DIM}{title} · {} turns left{RESET}\n\n",
+        deck.turns_remaining()
+    )?;
+    writeln!(out, "    {BOLD}{}{RESET}\n", card.front.kanji)?;
+    if !matches!(turn, Turn::Prompt) {
+        writeln!(out, "    {}", card.back.yomi)?;
+        writeln!(out, "    {}\n", card.back.meaning)?;
+    }
+    write!(out, "{DIM}{keys}{RESET}\n> ")?;
+    out.flush()
+}
+
+impl Session {
+    /// Opens the lesson's practice session, abandoning one left unfinished, as the web dialog does.
+    fn start(&mut self, lesson_id: i64, deck: &Deck) -> Result<(), PracticeError> {
+        let now = self.now()?;
+        let session_id = StartSession {
+            user_id: self.user_id.clone(),
+            lesson_id: Some(lesson_id),
+            cram: false,
+            deck: serde_json::to_string(deck)?,
+            now,
+        }
+        .apply(&mut self.conn)?;
+        self.session_id = Some(session_id);
+        Ok(())
+    }
+
+    /// Stores the deck so the session can be resumed in the browser.
+    fn save(&self, deck: &Deck) -> Result<(), PracticeError> {
+        let Some(session_id) = self.session_id else {
+            return Ok(());
+        };
+        SaveSession {
+            user_id: self.user_id.clone(),
+            session_id,
+            deck: serde_json::to_string(deck)?,
+            now: self.now()?,
+        }
+        .apply(&self.conn)?;
+        Ok(())
+    }
+
+    /// Closes the session with its final stats for the history.
+    fn finish(&self, stats: Stats) -> Result<(), PracticeError> {
+        let Some(session_id) = self.session_id else {
+            return Ok(());
+        };
+        FinishSession {
+            user_id: self.user_id.clone(),
+            session_id,
+            learned: stats.learned,
+            failed: stats.failed,
+            repeated: stats.repeated,
+            passed: stats.passed,
+            now: self.now()?,
+        }
+        .apply(&self.conn)?;
+        Ok(())
+    }
+
+    /// The current time on the user's clock, which session times are measured by.
+    fn now(&self) -> Result<f64, StorageError> {
+        let day = QueryUserDay {
+            user_id: self.user_id.clone(),
+            clock: &SystemClock,
+        }
+        .apply(&self.conn)?;
+        Ok(day.now)
+    }
+
+    /// Writes an answer to the card's phrase, as the web dialog's handlers do.
+    fn record(&mut self, answer: Answer, card: &Card) -> Result<(), StorageError> {
+        RecordAnswer {
+            phrase_id: card.id,
+            answer,
+            day: self.day,
+            leech_threshold: self.leech_threshold,
+        }
+        .apply(&mut self.conn)
+    }
+
+    /// Takes back what `record` wrote for an undone answer.
+    fn revert(&mut self, answer: Answer, card: &Card) -> Result<(), StorageError> {
+        match answer {
+            Answer::Pass => RevertLessonTime { phrase_id: card.id }.apply(&mut self.conn),
+            Answer::Fail => RevertLapse {
+                phrase_id: card.id,
+                leech_threshold: self.leech_threshold,
+            }
+            .apply(&self.conn),
+            Answer::Bury => BuryPhrase {
+                phrase_id: card.id,
+                day: self.day,
+                buried: false,
+            }
+            .apply(&self.conn),
+            Answer::Suspend => SuspendPhrase {
+                phrase_id: card.id,
+                suspended: false,
+            }
+            .apply(&self.conn),
+            Answer::Learn | Answer::Repeat => Ok(()),
+        }
+    }
+}
+
+/// Removes `name` and the value after it from the arguments.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, PracticeError> {
+    let Some(at) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if at + 1 == args.len() {
+        return Err(PracticeError::Usage(format!("{name} needs a value")));
+    }
+    let value = args.remove(at + 1);
+    args.remove(at);
+    Ok(Some(value))
+}
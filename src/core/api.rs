@@ -1,11 +1,31 @@
-pub async fn get_drills_url(url: &str) -> Vec<DrillPoint> {
-    let csv = reqwest::get(url).await.unwrap().text().await.unwrap();
-    let rows = parse_rows(csv);
-    let drills = rows
-        .into_iter()
-        .map(|row| DrillPoint::new(row.chapter, row.word, row.meaning))
-        .collect::<Vec<_>>();
-    drills
+use crate::core::backend::StorageError;
+use crate::core::data::tag::split_tags;
+use crate::core::data::NewPhrase;
+
+pub async fn get_drills_url(url: &str) -> Result<Vec<DrillPoint>, StorageError> {
+    let fetch_failed = |err: reqwest::Error| StorageError::Import(err.to_string());
+    let csv = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(fetch_failed)?
+        .text()
+        .await
+        .map_err(fetch_failed)?;
+    parse_drills(&csv)
+}
+
+/// Reads a sheet of `Chapter`, `Word`, `Meaning` and optional `Tags` and `Sentence` columns.
+pub fn parse_drills(csv: &str) -> Result<Vec<DrillPoint>, StorageError> {
+    let rows = parse_rows(csv)?;
+    rows.into_iter()
+        .map(|row| {
+            Ok(DrillPoint {
+                tags: split_tags(&row.tags),
+                sentence: row.sentence,
+                ..DrillPoint::new(row.chapter, row.word, row.meaning)?
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -15,10 +35,18 @@ pub struct DrillPoint {
     pub meaning: String,
     pub kanji: String,
     pub yomi: String,
+    pub tags: Vec<String>,
+    pub sentence: String,
 }
 
 impl DrillPoint {
-    pub fn new(chapter: usize, furi: impl AsRef<str>, meaning: impl AsRef<str>) -> Self {
+    /// Splits `furi`, a word with readings in full-width brackets after its kanji, into the kanji
+    /// and the reading. Fails on brackets that don't pair up.
+    pub fn new(
+        chapter: usize,
+        furi: impl AsRef<str>,
+        meaning: impl AsRef<str>,
+    ) -> Result<Self, StorageError> {
         let furi = furi.as_ref().to_string();
         let meaning = meaning.as_ref().to_string();
         let mut kanji_segments = Vec::new();
@@ -40,33 +68,87 @@ impl DrillPoint {
                     kanji_segments.push(kana.clone());
                     yomi_segments.push(kana);
                 }
-                _ => panic!("Invalid furi segment: {}", segment),
+                _ => {
+                    return Err(StorageError::Import(format!(
+                        "Invalid furigana in {furi}: {segment}"
+                    )));
+                }
             }
         }
-        Self {
+        Ok(Self {
             chapter,
             furi,
             meaning,
             kanji: kanji_segments.join(""),
             yomi: yomi_segments.join(""),
-        }
+            tags: Vec::new(),
+            sentence: String::new(),
+        })
     }
 }
 
-fn parse_rows(csv: String) -> Vec<CsvRow> {
+fn parse_rows(csv: &str) -> Result<Vec<CsvRow>, StorageError> {
     let mut rows = vec![];
     let mut reader = csv::Reader::from_reader(csv.as_bytes());
     for result in reader.deserialize() {
-        let row: CsvRow = result.unwrap();
+        let row: CsvRow = result.map_err(|err| StorageError::Import(err.to_string()))?;
         rows.push(row);
     }
-    rows
+    Ok(rows)
+}
+
+/// Writes phrases as a sheet that `parse_drills` reads back.
+pub fn write_sheet(phrases: &[NewPhrase]) -> Result<String, StorageError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for phrase in phrases {
+        let word = if phrase.reading.is_empty() || phrase.reading == phrase.prompt {
+            phrase.prompt.clone()
+        } else {
+            format!("{}（{}）", phrase.prompt, phrase.reading)
+        };
+        writer
+            .serialize(CsvRow {
+                chapter: phrase.chapter.unwrap_or_default(),
+                word,
+                meaning: phrase.translation.clone(),
+                tags: phrase.tags.join(" "),
+                sentence: phrase.sentence.clone(),
+            })
+            .map_err(|err| StorageError::Import(err.to_string()))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| StorageError::Import(err.to_string()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct CsvRow {
     chapter: usize,
     word: String,
     meaning: String,
+    /// Optional tags, separated by commas or spaces.
+    #[serde(default)]
+    tags: String,
+    /// An optional example sentence.
+    #[serde(default)]
+    sentence: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_drills;
+    use crate::core::backend::StorageError;
+
+    #[test]
+    fn malformed_furigana_fails_the_import() {
+        let drills = parse_drills("Chapter,Word,Meaning\n1,意外（いがい）,unexpected\n").unwrap();
+        assert_eq!(
+            (drills[0].kanji.as_str(), drills[0].yomi.as_str()),
+            ("意外", "いがい")
+        );
+        let malformed = parse_drills("Chapter,Word,Meaning\n1,意外（い（がい）,unexpected\n");
+        assert!(matches!(malformed, Err(StorageError::Import(_))));
+    }
 }
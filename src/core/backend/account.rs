@@ -0,0 +1,469 @@
+use crate::core::backend::clock::Clock;
+use crate::core::backend::insert_lesson::{insert_lesson, insert_phrase};
+use crate::core::backend::schedule::is_time_zone;
+use crate::core::backend::settings::{
+    QueryLessonSettings, QueryUserSettings, SaveLessonSettings, SaveUserSettings,
+};
+use crate::core::backend::{get_users, read_user_lessons, CreateUser, StorageError};
+use crate::core::data::account::{
+    AccountArchive, ArchivedLesson, ArchivedPhrase, ArchivedReview, ReviewKind, ARCHIVE_FORMAT,
+    ARCHIVE_VERSION,
+};
+use crate::core::data::settings::LessonSettings;
+use crate::core::data::tag::split_tags;
+use crate::core::data::NewPhrase;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// Everything the user has made and practiced, as an archive `ImportAccount` can read.
+pub struct ExportAccount<'a> {
+    pub user_id: String,
+    pub clock: &'a dyn Clock,
+}
+
+impl ExportAccount<'_> {
+    pub fn apply(self, db: &Connection) -> Result<AccountArchive, StorageError> {
+        if !get_users(db)?.iter().any(|user| user.id == self.user_id) {
+            return Err(StorageError::NotFound("User"));
+        }
+        let settings = QueryUserSettings {
+            user_id: self.user_id.clone(),
+        }
+        .apply(db)?;
+        let lessons = read_user_lessons(&self.user_id, db)?
+            .into_iter()
+            .map(|lesson| {
+                let LessonSettings {
+                    session_size,
+                    new_per_day,
+                    reviews_per_day,
+                    review_weight,
+                    ..
+                } = QueryLessonSettings {
+                    lesson_id: lesson.lesson_id,
+                }
+                .apply(db)?;
+                Ok(ArchivedLesson {
+                    title: lesson.title,
+                    session_size,
+                    new_per_day,
+                    reviews_per_day,
+                    review_weight,
+                    phrases: archived_phrases(db, lesson.lesson_id)?,
+                })
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        Ok(AccountArchive {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            user_id: self.user_id,
+            exported_at: self.clock.now().to_string(),
+            settings,
+            lessons,
+        })
+    }
+}
+
+fn archived_phrases(db: &Connection, lesson_id: i64) -> Result<Vec<ArchivedPhrase>, StorageError> {
+    const SQL: &str = r#"
+SELECT id, prompt, reading, translation, chapter, sentence, source_url, content_changed_at,
+       learned_at, lapses, is_leech, suspended, buried_until, link_id,
+       (SELECT GROUP_CONCAT(name, ' ')
+        FROM (SELECT tags.name
+              FROM phrase_tags
+                       JOIN tags ON tags.id = phrase_tags.tag_id
+              WHERE phrase_tags.phrase_id = phrases.id
+              ORDER BY tags.name))
+FROM phrases
+WHERE lesson_id = ?1
+ORDER BY id
+"#;
+    const REVIEWS_SQL: &str = r#"
+SELECT reviewed_at, kind, previous_learned_at
+FROM reviews
+WHERE phrase_id = ?1
+ORDER BY id
+"#;
+    const LAPSES_SQL: &str = "SELECT lapsed_at FROM lapses WHERE phrase_id = ?1 ORDER BY id";
+    let mut phrases = db
+        .prepare(SQL)?
+        .query_map(params![lesson_id], |row| {
+            let lapses: i64 = row.get(9)?;
+            let tags: Option<String> = row.get(14)?;
+            Ok(ArchivedPhrase {
+                id: row.get(0)?,
+                prompt: row.get(1)?,
+                reading: row.get(2)?,
+                translation: row.get(3)?,
+                chapter: row.get(4)?,
+                sentence: row.get(5)?,
+                source_url: row.get(6)?,
+                tags: tags.map(|tags| split_tags(&tags)).unwrap_or_default(),
+                content_changed_at: row.get(7)?,
+                learned_at: row.get(8)?,
+                lapses: lapses as usize,
+                is_leech: row.get(10)?,
+                suspended: row.get(11)?,
+                buried_until: row.get(12)?,
+                link_id: row.get(13)?,
+                reviews: Vec::new(),
+                lapsed_at: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut reviews = db.prepare(REVIEWS_SQL)?;
+    let mut lapses = db.prepare(LAPSES_SQL)?;
+    for phrase in &mut phrases {
+        phrase.reviews = reviews
+            .query_map(params![phrase.id], |row| {
+                let kind: String = row.get(1)?;
+                Ok(ArchivedReview {
+                    reviewed_at: row.get(0)?,
+                    // The table's CHECK allows only the kinds `parse` knows.
+                    kind: ReviewKind::parse(&kind).unwrap_or(ReviewKind::Practice),
+                    previous_learned_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        phrase.lapsed_at = lapses
+            .query_map(params![phrase.id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(phrases)
+}
+
+/// Recreates an archive's lessons, review log and settings for the user, creating the user if
+/// they're new, and returns the new lessons' ids. Lessons are added beside any the user has.
+pub struct ImportAccount {
+    pub user_id: String,
+    pub archive: AccountArchive,
+}
+
+impl ImportAccount {
+    pub fn apply(self, db: &mut Connection) -> Result<Vec<i64>, StorageError> {
+        const STATE_SQL: &str = r#"
+UPDATE phrases
+SET learned_at   = ?2,
+    lapses       = ?3,
+    is_leech     = ?4,
+    suspended    = ?5,
+    buried_until = ?6
+WHERE id = ?1
+"#;
+        const REVIEW_SQL: &str =
+            "INSERT INTO reviews (phrase_id, reviewed_at, kind, previous_learned_at) VALUES (?1, ?2, ?3, ?4)";
+        const LAPSE_SQL: &str = "INSERT INTO lapses (phrase_id, lapsed_at) VALUES (?1, ?2)";
+        const LINK_SQL: &str = "UPDATE phrases SET link_id = ?2 WHERE id = ?1";
+        let archive = self.archive;
+        if archive.format != ARCHIVE_FORMAT {
+            return Err(StorageError::Archive(format!(
+                "expected format {ARCHIVE_FORMAT} but got {}",
+                archive.format
+            )));
+        }
+        if archive.version != ARCHIVE_VERSION {
+            return Err(StorageError::Archive(format!(
+                "version {} is not {ARCHIVE_VERSION}",
+                archive.version
+            )));
+        }
+        if let Some(time_zone) = &archive.settings.time_zone {
+            if !is_time_zone(time_zone) {
+                return Err(StorageError::TimeZone(time_zone.clone()));
+            }
+        }
+        if let Some(key) = archive.settings.keys.duplicate() {
+            return Err(StorageError::DuplicateKey(key.to_string()));
+        }
+
+        let tx = db.transaction()?;
+        CreateUser {
+            user_id: self.user_id.clone(),
+            is_admin: false,
+            settings: archive.settings.clone(),
+        }
+        .apply(&tx)?;
+        SaveUserSettings {
+            user_id: self.user_id.clone(),
+            settings: archive.settings,
+        }
+        .apply(&tx)?;
+        let mut lesson_ids = Vec::new();
+        // Archive phrase ids to the new ones, and the links to restore once every phrase exists.
+        let mut phrase_ids = HashMap::new();
+        let mut links = Vec::new();
+        for lesson in archive.lessons {
+            let lesson_id = insert_lesson(&tx, &lesson.title, &self.user_id)?;
+            SaveLessonSettings {
+                settings: LessonSettings {
+                    lesson_id,
+                    session_size: lesson.session_size,
+                    new_per_day: lesson.new_per_day,
+                    reviews_per_day: lesson.reviews_per_day,
+                    review_weight: lesson.review_weight,
+                },
+            }
+            .apply(&tx)?;
+            for phrase in lesson.phrases {
+                let phrase_id = insert_phrase(
+                    &tx,
+                    lesson_id,
+                    &self.user_id,
+                    &NewPhrase {
+                        prompt: phrase.prompt,
+                        reading: phrase.reading,
+                        translation: phrase.translation,
+                        chapter: phrase.chapter,
+                        sentence: phrase.sentence,
+                        content_changed_at: Some(phrase.content_changed_at),
+                        tags: phrase.tags,
+                        source_url: phrase.source_url,
+                    },
+                )?;
+                tx.prepare_cached(STATE_SQL)?.execute(params![
+                    phrase_id,
+                    phrase.learned_at,
+                    phrase.lapses,
+                    phrase.is_leech,
+                    phrase.suspended,
+                    phrase.buried_until
+                ])?;
+                for review in phrase.reviews {
+                    tx.prepare_cached(REVIEW_SQL)?.execute(params![
+                        phrase_id,
+                        review.reviewed_at,
+                        review.kind.as_str(),
+                        review.previous_learned_at
+                    ])?;
+                }
+                for lapsed_at in phrase.lapsed_at {
+                    tx.prepare_cached(LAPSE_SQL)?
+                        .execute(params![phrase_id, lapsed_at])?;
+                }
+                phrase_ids.insert(phrase.id, phrase_id);
+                if let Some(link_id) = phrase.link_id {
+                    links.push((phrase_id, link_id));
+                }
+            }
+            lesson_ids.push(lesson_id);
+        }
+        for (phrase_id, link_id) in links {
+            // A link to a phrase outside the archive can't be kept.
+            if let Some(link_id) = phrase_ids.get(&link_id) {
+                tx.prepare_cached(LINK_SQL)?
+                    .execute(params![phrase_id, link_id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(lesson_ids)
+    }
+}
+
+/// Deletes a user and, through the schema's `ON DELETE CASCADE` foreign keys, their lessons,
+/// phrases, tags, review log, sessions, settings and API tokens. The configured admin is kept,
+/// since the server would only recreate them.
+pub struct DeleteAccount {
+    pub user_id: String,
+    pub admin: String,
+}
+
+impl DeleteAccount {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        if self.user_id == self.admin {
+            return Err(StorageError::DeleteAdmin(self.user_id));
+        }
+        let deleted = db.execute("DELETE FROM users WHERE id = ?1", params![self.user_id])?;
+        if deleted == 0 {
+            return Err(StorageError::NotFound("User"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeleteAccount, ExportAccount, ImportAccount};
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::import::ImportLesson;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::phrase::RecordAnswer;
+    use crate::core::backend::schedule::Day;
+    use crate::core::backend::settings::SaveLessonSettings;
+    use crate::core::backend::token::CreateToken;
+    use crate::core::backend::{connect, read_phrases, CreateUser, StorageError};
+    use crate::core::data::account::AccountArchive;
+    use crate::core::data::deck::Answer;
+    use crate::core::data::import::DuplicatePolicy;
+    use crate::core::data::settings::{LessonSettings, UserSettings};
+    use crate::core::data::NewPhrase;
+    use rusqlite::Connection;
+    use std::collections::HashMap;
+
+    fn phrase(prompt: &str, tags: &[&str]) -> NewPhrase {
+        NewPhrase {
+            prompt: prompt.to_string(),
+            reading: "いがい".to_string(),
+            translation: "unexpected".to_string(),
+            chapter: Some(1),
+            sentence: format!("{prompt}だった。"),
+            content_changed_at: Some(2460000.5),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            source_url: Some("https://example.com".to_string()),
+        }
+    }
+
+    /// Numbers phrases in archive order, so archives from different databases compare equal.
+    fn renumbered(mut archive: AccountArchive) -> AccountArchive {
+        let positions = archive
+            .lessons
+            .iter()
+            .flat_map(|lesson| &lesson.phrases)
+            .enumerate()
+            .map(|(position, phrase)| (phrase.id, position as i64))
+            .collect::<HashMap<_, _>>();
+        for phrase in archive.lessons.iter_mut().flat_map(|l| &mut l.phrases) {
+            phrase.id = positions[&phrase.id];
+            phrase.link_id = phrase.link_id.map(|link_id| positions[&link_id]);
+        }
+        archive
+    }
+
+    fn count(conn: &Connection, table: &str) -> usize {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn accounts_move_and_delete() {
+        let clock = FixedClock {
+            at: "2024-01-01T10:00:00Z".parse().unwrap(),
+        };
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        CreateUser {
+            user_id: "kaito".to_string(),
+            is_admin: false,
+            settings: UserSettings {
+                session_size: 5,
+                ..Default::default()
+            },
+        }
+        .apply(&conn)
+        .unwrap();
+        let first = InsertLesson {
+            title: "Chapter 1".to_string(),
+            owner: "kaito".to_string(),
+            phrases: vec![phrase("意外", &["n3"]), phrase("以外", &[])],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        ImportLesson {
+            lesson: InsertLesson {
+                title: "Chapter 2".to_string(),
+                owner: "kaito".to_string(),
+                phrases: vec![phrase("意外", &["adj", "n3"])],
+            },
+            duplicates: DuplicatePolicy::Link,
+        }
+        .apply(&mut conn)
+        .unwrap();
+        SaveLessonSettings {
+            settings: LessonSettings {
+                lesson_id: first,
+                review_weight: Some(0),
+                ..Default::default()
+            },
+        }
+        .apply(&conn)
+        .unwrap();
+        let phrase_ids = read_phrases(first, &conn)
+            .unwrap()
+            .into_iter()
+            .map(|phrase| phrase.phrase_id)
+            .collect::<Vec<_>>();
+        for (phrase_id, answer) in [(phrase_ids[0], Answer::Pass), (phrase_ids[1], Answer::Fail)] {
+            RecordAnswer {
+                phrase_id,
+                answer,
+                day: Day::new(2460001.0, 3),
+                leech_threshold: 8,
+            }
+            .apply(&mut conn)
+            .unwrap();
+        }
+        CreateToken {
+            user_id: "kaito".to_string(),
+            name: "reader".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+
+        let archive = ExportAccount {
+            user_id: "kaito".to_string(),
+            clock: &clock,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(archive.settings.session_size, 5);
+        assert_eq!(archive.lessons[0].review_weight, Some(0));
+        let linked = &archive.lessons[1].phrases[0];
+        assert_eq!(linked.link_id, Some(archive.lessons[0].phrases[0].id));
+        assert_eq!(linked.tags, ["adj", "n3"]);
+        assert_eq!(archive.lessons[0].phrases[0].reviews.len(), 1);
+        assert_eq!(archive.lessons[0].phrases[1].lapsed_at.len(), 1);
+
+        // Through JSON, as an archive travels between servers.
+        let json = serde_json::to_string(&archive).unwrap();
+        let mut other = connect(&Config::in_memory()).unwrap();
+        let lesson_ids = ImportAccount {
+            user_id: "kaito".to_string(),
+            archive: serde_json::from_str(&json).unwrap(),
+        }
+        .apply(&mut other)
+        .unwrap();
+        assert_eq!(lesson_ids.len(), 2);
+        let imported = ExportAccount {
+            user_id: "kaito".to_string(),
+            clock: &clock,
+        }
+        .apply(&other)
+        .unwrap();
+        assert_eq!(renumbered(imported), renumbered(archive.clone()));
+        let unknown = ImportAccount {
+            user_id: "kaito".to_string(),
+            archive: AccountArchive {
+                version: 2,
+                ..archive
+            },
+        }
+        .apply(&mut other);
+        assert!(matches!(unknown, Err(StorageError::Archive(_))));
+
+        let delete = |user_id: &str| {
+            DeleteAccount {
+                user_id: user_id.to_string(),
+                admin: "admin".to_string(),
+            }
+            .apply(&conn)
+        };
+        assert!(matches!(delete("admin"), Err(StorageError::DeleteAdmin(_))));
+        delete("kaito").unwrap();
+        assert!(matches!(delete("kaito"), Err(StorageError::NotFound(_))));
+        for table in [
+            "lessons",
+            "lesson_settings",
+            "phrases",
+            "phrase_search",
+            "reviews",
+            "lapses",
+            "tags",
+            "phrase_tags",
+            "api_tokens",
+        ] {
+            assert_eq!(count(&conn, table), 0, "{table} kept rows");
+        }
+        assert_eq!(count(&conn, "user_settings"), 1);
+    }
+}
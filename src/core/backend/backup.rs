@@ -0,0 +1,266 @@
+use crate::core::backend::clock::Clock;
+use crate::core::backend::migrate::{self, LATEST_VERSION};
+use crate::core::backend::StorageError;
+use crate::core::data::backup::BackupFile;
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, OpenFlags, MAIN_DB};
+use std::path::Path;
+use std::time::Duration;
+
+/// Starts the names of backups taken by `SnapshotDatabase`, which are the only files it rotates.
+const SNAPSHOT_PREFIX: &str = "kanjikoto-";
+
+/// Copies the database to a new file at `path` while other connections keep using it.
+pub struct BackupDatabase {
+    pub path: String,
+}
+
+impl BackupDatabase {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        db.backup(MAIN_DB, &self.path, None)?;
+        // Out of WAL mode the backup is a single file, and checking it leaves no `-wal` beside it.
+        Connection::open(&self.path)?.pragma_update(None, "journal_mode", "DELETE")?;
+        check_backup(&self.path)
+    }
+}
+
+/// Replaces the database's contents with the backup at `path`, migrating it if it is older.
+/// The contents it replaces are backed up into `directory` first, so a mistaken restore can be
+/// undone by restoring that backup, which `apply` returns.
+pub struct RestoreDatabase<'a> {
+    pub path: String,
+    pub directory: String,
+    pub clock: &'a dyn Clock,
+}
+
+impl RestoreDatabase<'_> {
+    pub fn apply(self, db: &mut Connection) -> Result<BackupFile, StorageError> {
+        check_backup(&self.path)?;
+        // Rotating nothing out keeps the backup being restored, even the oldest.
+        let replaced = SnapshotDatabase {
+            directory: self.directory,
+            keep: usize::MAX,
+            clock: self.clock,
+        }
+        .apply(db)?;
+        db.restore(MAIN_DB, &self.path, None::<fn(Progress)>)?;
+        migrate::migrate(db)?;
+        Ok(replaced)
+    }
+}
+
+/// Fails unless `path` holds an intact database this build can open.
+pub fn check_backup(path: &str) -> Result<(), StorageError> {
+    let invalid = |reason: String| Err(StorageError::Backup(format!("{path} {reason}")));
+    if !Path::new(path).is_file() {
+        return invalid("doesn't exist".to_string());
+    }
+    let backup = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let integrity: String = backup.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if integrity != "ok" {
+        return invalid(format!("failed its integrity check: {integrity}"));
+    }
+    match migrate::version(&backup)? {
+        0 => invalid("is not a kanjikoto database".to_string()),
+        version if version > LATEST_VERSION => invalid(format!(
+            "is at schema version {version}, newer than this build's {LATEST_VERSION}"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Backs up into `directory` under a name taken from the time to the millisecond, then deletes
+/// all but the newest `keep` backups there. A backup that fails its check is deleted instead of
+/// kept, and one whose name is already taken is refused rather than written over.
+pub struct SnapshotDatabase<'a> {
+    pub directory: String,
+    pub keep: usize,
+    pub clock: &'a dyn Clock,
+}
+
+impl SnapshotDatabase<'_> {
+    pub fn apply(self, db: &Connection) -> Result<BackupFile, StorageError> {
+        let directory = Path::new(&self.directory);
+        std::fs::create_dir_all(directory)
+            .map_err(|err| StorageError::Backup(format!("directory {}: {err}", self.directory)))?;
+        let name = format!(
+            "{SNAPSHOT_PREFIX}{}.db",
+            self.clock.now().strftime("%Y%m%d-%H%M%S%3f")
+        );
+        let path = directory.join(&name).to_string_lossy().into_owned();
+        if Path::new(&path).exists() {
+            return Err(StorageError::Backup(format!("{name} already exists")));
+        }
+        if let Err(err) = (BackupDatabase { path: path.clone() }).apply(db) {
+            let _ = std::fs::remove_file(&path);
+            return Err(err);
+        }
+        for old in list_backups(&self.directory)?.iter().skip(self.keep) {
+            let old = directory.join(&old.name);
+            std::fs::remove_file(&old).map_err(|err| {
+                StorageError::Backup(format!("{} couldn't be deleted: {err}", old.display()))
+            })?;
+        }
+        list_backups(&self.directory)?
+            .into_iter()
+            .find(|backup| backup.name == name)
+            .ok_or(StorageError::NotFound("Backup"))
+    }
+}
+
+/// The backups `SnapshotDatabase` took in `directory`, newest first.
+pub fn list_backups(directory: &str) -> Result<Vec<BackupFile>, StorageError> {
+    let read_error =
+        |err: std::io::Error| StorageError::Backup(format!("directory {directory}: {err}"));
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(read_error(err)),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(read_error)?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(SNAPSHOT_PREFIX) || !name.ends_with(".db") {
+            continue;
+        }
+        let metadata = entry.metadata().map_err(read_error)?;
+        let taken_at = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| jiff::Timestamp::try_from(modified).ok())
+            .map(|taken_at| taken_at.strftime("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        backups.push(BackupFile {
+            name,
+            bytes: metadata.len(),
+            taken_at,
+        });
+    }
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// The path of the backup named `name` in `directory`, refusing names `list_backups` doesn't give.
+pub fn backup_path(directory: &str, name: &str) -> Result<String, StorageError> {
+    if !list_backups(directory)?
+        .iter()
+        .any(|backup| backup.name == name)
+    {
+        return Err(StorageError::NotFound("Backup"));
+    }
+    Ok(Path::new(directory)
+        .join(name)
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// How long ago the newest backup in `directory` was taken, if there is one.
+pub fn newest_backup_age(directory: &str) -> Option<Duration> {
+    let newest = list_backups(directory).ok()?.into_iter().next()?;
+    let modified = std::fs::metadata(Path::new(directory).join(newest.name))
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    modified.elapsed().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        backup_path, check_backup, list_backups, BackupDatabase, RestoreDatabase, SnapshotDatabase,
+    };
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::{connect, read_user_lessons, StorageError};
+    use jiff::SignedDuration;
+
+    #[test]
+    fn backups_restore_earlier_contents() {
+        let dir = std::env::temp_dir().join(format!("kanjikoto-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("backup.db").to_string_lossy().into_owned();
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let add_lesson = |conn: &rusqlite::Connection, title: &str| {
+            conn.execute(
+                "INSERT INTO lessons (title, creator_id) VALUES (?1, 'admin')",
+                [title],
+            )
+            .unwrap();
+        };
+        add_lesson(&conn, "Kept");
+        BackupDatabase { path: path.clone() }.apply(&conn).unwrap();
+        add_lesson(&conn, "Replaced");
+        let snapshots = dir.join("snapshots").to_string_lossy().into_owned();
+        let replaced = RestoreDatabase {
+            path: path.clone(),
+            directory: snapshots.clone(),
+            clock: &FixedClock {
+                at: "2026-01-01T03:00:00Z".parse().unwrap(),
+            },
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let titles = |conn: &rusqlite::Connection| {
+            read_user_lessons("admin", conn)
+                .unwrap()
+                .into_iter()
+                .map(|lesson| lesson.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles(&conn), ["Kept"]);
+        // The contents the restore replaced are still there to go back to.
+        let replaced = backup_path(&snapshots, &replaced.name).unwrap();
+        check_backup(&replaced).unwrap();
+        assert_eq!(
+            titles(&rusqlite::Connection::open(&replaced).unwrap()),
+            ["Kept", "Replaced"]
+        );
+
+        let empty = dir.join("empty.db").to_string_lossy().into_owned();
+        rusqlite::Connection::open(&empty).unwrap();
+        assert!(check_backup(&empty).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn snapshots_rotate() {
+        let dir = std::env::temp_dir().join(format!("kanjikoto-snapshots-{}", std::process::id()));
+        let directory = dir.to_string_lossy().into_owned();
+        let conn = connect(&Config::in_memory()).unwrap();
+        let mut clock = FixedClock {
+            at: "2026-01-01T03:00:00Z".parse().unwrap(),
+        };
+        let snapshot = |clock: &FixedClock| {
+            SnapshotDatabase {
+                directory: directory.clone(),
+                keep: 2,
+                clock,
+            }
+            .apply(&conn)
+        };
+        for _ in 0..3 {
+            snapshot(&clock).unwrap();
+            clock = clock.advance(SignedDuration::from_hours(24));
+        }
+        // A snapshot in the same millisecond as the last leaves it alone.
+        let taken = clock.advance(SignedDuration::from_hours(-24));
+        assert!(matches!(snapshot(&taken), Err(StorageError::Backup(_))));
+        std::fs::write(dir.join("notes.txt"), "kept").unwrap();
+        let names = list_backups(&directory)
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "kanjikoto-20260103-030000000.db",
+                "kanjikoto-20260102-030000000.db"
+            ]
+        );
+        assert!(check_backup(&backup_path(&directory, &names[0]).unwrap()).is_ok());
+        assert!(backup_path(&directory, "../notes.txt").is_err());
+        assert!(dir.join("notes.txt").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+use jiff::{SignedDuration, Timestamp};
+
+/// Source of the current time for server functions.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// The wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// A clock stopped at a moment, moved forward by hand in tests.
+#[cfg(test)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FixedClock {
+    pub at: Timestamp,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn advance(self, duration: SignedDuration) -> Self {
+        Self {
+            at: self.at + duration,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.at
+    }
+}
+
+/// Another clock shifted by a fixed amount, used to simulate other dates.
+pub struct OffsetClock<'a> {
+    pub inner: &'a dyn Clock,
+    pub offset: SignedDuration,
+}
+
+impl Clock for OffsetClock<'_> {
+    fn now(&self) -> Timestamp {
+        self.inner.now() + self.offset
+    }
+}
@@ -0,0 +1,317 @@
+use crate::core::backend::StorageError;
+use crate::core::data::settings::UserSettings;
+use dioxus::logger::tracing::Level;
+use serde::Deserialize;
+use std::path::Path;
+use thiserror::Error;
+
+/// The file read when `KANJIKOTO_CONFIG` doesn't name another.
+const CONFIG_FILE: &str = "kanjikoto.toml";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse {path}: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("{name}={value} is not valid: {reason}")]
+    Env {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+    #[error("{field} {reason}")]
+    Invalid {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+/// Server settings from `kanjikoto.toml`, overridden by `KANJIKOTO_*` environment variables.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The SQLite database file, created on first start.
+    pub database: String,
+    /// The admin created with a new database, and the user the server acts for.
+    pub admin: String,
+    /// Cards per session for users who haven't chosen their own.
+    pub session_size: usize,
+    /// Local hour a new day begins for users who haven't chosen their own.
+    pub day_start_hour: u8,
+    pub import: ImportLimits,
+    pub quick_add: QuickAdd,
+    pub backup: Backups,
+    /// `error`, `warn`, `info`, `debug` or `trace`.
+    pub log_level: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportLimits {
+    /// The most rows a CSV import may have.
+    pub max_rows: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuickAdd {
+    /// Title of the lesson quick-added words go to, made for each user on first use.
+    pub inbox: String,
+    /// A tab-separated file of `word`, `reading` and `meaning` lines to fill in quick-added words.
+    pub dictionary: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Backups {
+    /// The directory the server writes its backups to.
+    pub directory: String,
+    /// Hours between automatic backups, or 0 to take none.
+    pub interval_hours: u64,
+    /// Backups kept in the directory, the oldest deleted first.
+    pub keep: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database: "data/kanjikoto.db".to_string(),
+            admin: "admin".to_string(),
+            session_size: 20,
+            day_start_hour: 3,
+            import: ImportLimits::default(),
+            quick_add: QuickAdd::default(),
+            backup: Backups::default(),
+            log_level: "info".to_string(),
+        }
+    }
+}
+
+impl Default for ImportLimits {
+    fn default() -> Self {
+        Self { max_rows: 5000 }
+    }
+}
+
+impl ImportLimits {
+    /// Refuses a sheet of more than `max_rows` rows.
+    pub fn check_rows(&self, rows: usize) -> Result<(), StorageError> {
+        if rows > self.max_rows {
+            return Err(StorageError::TooManyRows {
+                rows,
+                limit: self.max_rows,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for QuickAdd {
+    fn default() -> Self {
+        Self {
+            inbox: "Inbox".to_string(),
+            dictionary: None,
+        }
+    }
+}
+
+impl Default for Backups {
+    fn default() -> Self {
+        Self {
+            directory: "data/backups".to_string(),
+            interval_hours: 24,
+            keep: 7,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, if present, applies environment overrides and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = std::env::var("KANJIKOTO_CONFIG").ok();
+        let config = match path.as_deref() {
+            Some(path) => Self::read(path)?,
+            None if Path::new(CONFIG_FILE).exists() => Self::read(CONFIG_FILE)?,
+            None => Self::default(),
+        };
+        let config = config.with_overrides(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// A config for an in-memory database, for tests.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            database: ":memory:".to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn read(path: &str) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_string(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    fn with_overrides(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        fn parse<T: std::str::FromStr>(name: &'static str, value: String) -> Result<T, ConfigError>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value.parse().map_err(|err: T::Err| ConfigError::Env {
+                name,
+                reason: err.to_string(),
+                value,
+            })
+        }
+        if let Some(value) = var("KANJIKOTO_DATABASE") {
+            self.database = value;
+        }
+        if let Some(value) = var("KANJIKOTO_ADMIN") {
+            self.admin = value;
+        }
+        if let Some(value) = var("KANJIKOTO_SESSION_SIZE") {
+            self.session_size = parse("KANJIKOTO_SESSION_SIZE", value)?;
+        }
+        if let Some(value) = var("KANJIKOTO_DAY_START_HOUR") {
+            self.day_start_hour = parse("KANJIKOTO_DAY_START_HOUR", value)?;
+        }
+        if let Some(value) = var("KANJIKOTO_IMPORT_MAX_ROWS") {
+            self.import.max_rows = parse("KANJIKOTO_IMPORT_MAX_ROWS", value)?;
+        }
+        if let Some(value) = var("KANJIKOTO_QUICK_ADD_INBOX") {
+            self.quick_add.inbox = value;
+        }
+        if let Some(value) = var("KANJIKOTO_QUICK_ADD_DICTIONARY") {
+            self.quick_add.dictionary = Some(value);
+        }
+        if let Some(value) = var("KANJIKOTO_BACKUP_DIRECTORY") {
+            self.backup.directory = value;
+        }
+        if let Some(value) = var("KANJIKOTO_BACKUP_INTERVAL_HOURS") {
+            self.backup.interval_hours = parse("KANJIKOTO_BACKUP_INTERVAL_HOURS", value)?;
+        }
+        if let Some(value) = var("KANJIKOTO_BACKUP_KEEP") {
+            self.backup.keep = parse("KANJIKOTO_BACKUP_KEEP", value)?;
+        }
+        if let Some(value) = var("KANJIKOTO_LOG_LEVEL") {
+            self.log_level = value;
+        }
+        Ok(self)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason| Err(ConfigError::Invalid { field, reason });
+        if self.database.trim().is_empty() {
+            return invalid("database", "must name a file");
+        }
+        if self.admin.trim().is_empty() {
+            return invalid("admin", "must name a user");
+        }
+        if self.session_size == 0 {
+            return invalid("session_size", "must be at least 1");
+        }
+        if self.day_start_hour > 23 {
+            return invalid("day_start_hour", "must be an hour from 0 to 23");
+        }
+        if self.import.max_rows == 0 {
+            return invalid("import.max_rows", "must be at least 1");
+        }
+        if self.quick_add.inbox.trim().is_empty() {
+            return invalid("quick_add.inbox", "must name a lesson");
+        }
+        if self.backup.directory.trim().is_empty() {
+            return invalid("backup.directory", "must name a directory");
+        }
+        if self.backup.keep == 0 {
+            return invalid("backup.keep", "must be at least 1");
+        }
+        if self.log_level.parse::<Level>().is_err() {
+            return invalid("log_level", "must be error, warn, info, debug or trace");
+        }
+        Ok(())
+    }
+
+    pub fn log_level(&self) -> Level {
+        self.log_level.parse().unwrap_or(Level::INFO)
+    }
+
+    /// The settings given to a user when they are created.
+    pub fn user_defaults(&self) -> UserSettings {
+        UserSettings {
+            session_size: self.session_size,
+            day_start_hour: self.day_start_hour,
+            ..UserSettings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigError};
+    use crate::core::backend::StorageError;
+    use std::collections::HashMap;
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let config: Config = toml::from_str(
+            r#"
+database = "study.db"
+session_size = 10
+
+[import]
+max_rows = 100
+"#,
+        )
+        .unwrap();
+        let env = HashMap::from([
+            ("KANJIKOTO_SESSION_SIZE", "15"),
+            ("KANJIKOTO_LOG_LEVEL", "debug"),
+        ]);
+        let config = config
+            .with_overrides(|name| env.get(name).map(|value| value.to_string()))
+            .unwrap();
+        assert_eq!(config.database, "study.db");
+        assert_eq!(config.session_size, 15);
+        assert_eq!(config.import.max_rows, 100);
+        assert!(config.import.check_rows(100).is_ok());
+        assert!(matches!(
+            config.import.check_rows(101),
+            Err(StorageError::TooManyRows {
+                rows: 101,
+                limit: 100
+            })
+        ));
+        assert_eq!(config.admin, "admin");
+        assert!(config.validate().is_ok());
+
+        let bad_hour = Config {
+            day_start_hour: 24,
+            ..config.clone()
+        };
+        assert!(matches!(
+            bad_hour.validate(),
+            Err(ConfigError::Invalid {
+                field: "day_start_hour",
+                ..
+            })
+        ));
+        let bad_env = config
+            .with_overrides(|name| (name == "KANJIKOTO_SESSION_SIZE").then(|| "many".to_string()));
+        assert!(matches!(bad_env, Err(ConfigError::Env { .. })));
+        assert!(toml::from_str::<Config>("sesion_size = 5").is_err());
+    }
+}
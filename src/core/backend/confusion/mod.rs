@@ -0,0 +1,323 @@
+use crate::core::backend::phrase::check_phrase_owner;
+use crate::core::backend::StorageError;
+use crate::core::data::confusion::{ConfusableSet, Confusion, ConfusionKind};
+use crate::core::data::PhraseView;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::LazyLock;
+
+static KANJI_PARTS: LazyLock<KanjiParts> =
+    LazyLock::new(|| KanjiParts::parse(include_str!("kanji_parts.txt")));
+
+/// Visual components of kanji, read from `kanji_parts.txt`.
+pub struct KanjiParts {
+    components: HashMap<char, BTreeSet<String>>,
+    groups: Vec<BTreeSet<char>>,
+}
+
+impl KanjiParts {
+    pub fn parse(text: &str) -> Self {
+        let mut components = HashMap::new();
+        let mut groups = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(head) = fields.next() else { continue };
+            if head == "~" {
+                groups.push(fields.filter_map(|s| s.chars().next()).collect());
+            } else if let Some(kanji) = head.chars().next() {
+                components.insert(kanji, fields.map(str::to_string).collect());
+            }
+        }
+        Self { components, groups }
+    }
+
+    pub fn look_alike(&self, a: char, b: char) -> bool {
+        if a == b {
+            return false;
+        }
+        if self
+            .groups
+            .iter()
+            .any(|group| group.contains(&a) && group.contains(&b))
+        {
+            return true;
+        }
+        match (self.components.get(&a), self.components.get(&b)) {
+            (Some(a), Some(b)) if a.len() == b.len() && a.len() > 1 => a.difference(b).count() == 1,
+            _ => false,
+        }
+    }
+}
+
+fn is_kanji(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c) || ('\u{3400}'..='\u{4DBF}').contains(&c)
+}
+
+/// Groups phrases that share a reading or contain look-alike kanji.
+pub fn find_confusables(phrases: &[PhraseView], parts: &KanjiParts) -> Vec<ConfusableSet> {
+    let mut sets = Vec::new();
+
+    let mut by_reading = BTreeMap::<&str, Vec<&PhraseView>>::new();
+    for phrase in phrases {
+        let group = by_reading.entry(phrase.reading.trim()).or_default();
+        if group.iter().all(|p| p.prompt != phrase.prompt) {
+            group.push(phrase);
+        }
+    }
+    for (reading, group) in by_reading {
+        if group.len() > 1 {
+            sets.push(ConfusableSet {
+                kind: ConfusionKind::Homophone,
+                key: reading.to_string(),
+                phrases: group.into_iter().cloned().collect(),
+            });
+        }
+    }
+
+    let kanji = phrases
+        .iter()
+        .flat_map(|p| p.prompt.chars())
+        .filter(|c| is_kanji(*c))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for (i, &a) in kanji.iter().enumerate() {
+        for &b in &kanji[i + 1..] {
+            if !parts.look_alike(a, b) {
+                continue;
+            }
+            let mut group = Vec::<&PhraseView>::new();
+            for phrase in phrases {
+                let has_a = phrase.prompt.contains(a);
+                let has_b = phrase.prompt.contains(b);
+                if has_a != has_b && group.iter().all(|p| p.prompt != phrase.prompt) {
+                    group.push(phrase);
+                }
+            }
+            let with_a = group.iter().any(|p| p.prompt.contains(a));
+            let with_b = group.iter().any(|p| p.prompt.contains(b));
+            if with_a && with_b {
+                sets.push(ConfusableSet {
+                    kind: ConfusionKind::LookAlike,
+                    key: format!("{a}・{b}"),
+                    phrases: group.into_iter().cloned().collect(),
+                });
+            }
+        }
+    }
+    sets
+}
+
+fn read_owner_phrases(db: &Connection, owner: &str) -> Result<Vec<PhraseView>, StorageError> {
+    const SQL: &str = r#"
+SELECT p.id, p.prompt, p.reading, p.translation
+FROM phrases p
+    JOIN lessons l ON l.id = p.lesson_id
+WHERE l.creator_id = ?1
+ORDER BY p.id
+"#;
+    let mut stmt = db.prepare(SQL)?;
+    let phrases = stmt
+        .query_map(params![owner], |row| {
+            Ok(PhraseView {
+                phrase_id: row.get(0)?,
+                prompt: row.get(1)?,
+                reading: row.get(2)?,
+                meaning: row.get(3)?,
+                ..Default::default()
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(phrases)
+}
+
+pub struct QueryConfusables {
+    pub owner: String,
+}
+
+impl QueryConfusables {
+    pub fn apply(self, db: &Connection) -> Result<Vec<ConfusableSet>, StorageError> {
+        let phrases = read_owner_phrases(db, &self.owner)?;
+        Ok(find_confusables(&phrases, &KANJI_PARTS))
+    }
+}
+
+/// Counts the owner choosing `chosen_id` when `expected_id` was asked for. Both phrases must be
+/// the owner's.
+pub struct RecordConfusion {
+    pub owner: String,
+    pub expected_id: i64,
+    pub chosen_id: i64,
+    pub now: f64,
+}
+
+impl RecordConfusion {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+INSERT INTO confusions (expected_id, chosen_id, count, last_at)
+VALUES (?1, ?2, 1, ?3)
+ON CONFLICT (expected_id, chosen_id) DO UPDATE SET count = count + 1, last_at = excluded.last_at
+"#;
+        check_phrase_owner(db, &self.owner, self.expected_id)?;
+        check_phrase_owner(db, &self.owner, self.chosen_id)?;
+        db.execute(SQL, params![self.expected_id, self.chosen_id, self.now])?;
+        Ok(())
+    }
+}
+
+pub struct QueryConfusions {
+    pub owner: String,
+}
+
+impl QueryConfusions {
+    pub fn apply(self, db: &Connection) -> Result<Vec<Confusion>, StorageError> {
+        const SQL: &str = r#"
+SELECT
+    e.id, e.prompt, e.reading, e.translation,
+    c.id, c.prompt, c.reading, c.translation,
+    confusions.count
+FROM confusions
+    JOIN phrases e ON e.id = confusions.expected_id
+    JOIN phrases c ON c.id = confusions.chosen_id
+    JOIN lessons l ON l.id = e.lesson_id
+WHERE l.creator_id = ?1
+ORDER BY confusions.count DESC, confusions.last_at DESC
+LIMIT 20
+"#;
+        let mut stmt = db.prepare(SQL)?;
+        let confusions = stmt
+            .query_map(params![self.owner], |row| {
+                let count: i64 = row.get(8)?;
+                Ok(Confusion {
+                    expected: PhraseView {
+                        phrase_id: row.get(0)?,
+                        prompt: row.get(1)?,
+                        reading: row.get(2)?,
+                        meaning: row.get(3)?,
+                        ..Default::default()
+                    },
+                    chosen: PhraseView {
+                        phrase_id: row.get(4)?,
+                        prompt: row.get(5)?,
+                        reading: row.get(6)?,
+                        meaning: row.get(7)?,
+                        ..Default::default()
+                    },
+                    count: count as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(confusions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_confusables, KanjiParts, QueryConfusions, RecordConfusion, KANJI_PARTS};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::{connect, read_phrases, CreateUser, StorageError};
+    use crate::core::data::confusion::ConfusionKind;
+    use crate::core::data::{NewPhrase, PhraseView};
+
+    fn phrase(phrase_id: i64, prompt: &str, reading: &str) -> PhraseView {
+        PhraseView {
+            phrase_id,
+            prompt: prompt.to_string(),
+            reading: reading.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn finds_homophones_and_look_alikes() {
+        let phrases = vec![
+            phrase(1, "意外", "いがい"),
+            phrase(2, "以外", "いがい"),
+            phrase(3, "待つ", "まつ"),
+            phrase(4, "持つ", "もつ"),
+            phrase(5, "未来", "みらい"),
+        ];
+        let sets = find_confusables(&phrases, &KANJI_PARTS);
+        let keys = sets
+            .iter()
+            .map(|s| (s.kind, s.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                (ConfusionKind::Homophone, "いがい"),
+                (ConfusionKind::LookAlike, "待・持"),
+            ]
+        );
+        assert_eq!(sets[1].phrases.len(), 2);
+    }
+
+    #[test]
+    fn look_alike_requires_one_differing_component() {
+        let parts = KanjiParts::parse("待 彳 寺\n持 扌 寺\n晴 日 青\n~ 未 末\n");
+        assert!(parts.look_alike('待', '持'));
+        assert!(parts.look_alike('末', '未'));
+        assert!(!parts.look_alike('待', '晴'));
+        assert!(!parts.look_alike('待', '待'));
+    }
+
+    #[test]
+    fn confusions_need_the_owners_phrases() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        CreateUser {
+            user_id: "kaito".to_string(),
+            is_admin: false,
+            settings: Default::default(),
+        }
+        .apply(&conn)
+        .unwrap();
+        let mut lesson = |owner: &str| {
+            let lesson_id = InsertLesson {
+                title: owner.to_string(),
+                owner: owner.to_string(),
+                phrases: ["待", "持"]
+                    .into_iter()
+                    .map(|prompt| NewPhrase {
+                        prompt: prompt.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            }
+            .apply(&mut conn)
+            .unwrap();
+            read_phrases(lesson_id, &conn)
+                .unwrap()
+                .into_iter()
+                .map(|phrase| phrase.phrase_id)
+                .collect::<Vec<_>>()
+        };
+        let mine = lesson("admin");
+        let theirs = lesson("kaito");
+        let record = |expected_id, chosen_id| {
+            RecordConfusion {
+                owner: "admin".to_string(),
+                expected_id,
+                chosen_id,
+                now: 2460310.5,
+            }
+            .apply(&conn)
+        };
+        for (expected_id, chosen_id) in [(mine[0], theirs[1]), (theirs[0], mine[1])] {
+            assert!(matches!(
+                record(expected_id, chosen_id),
+                Err(StorageError::NotFound("Phrase"))
+            ));
+        }
+        record(mine[0], mine[1]).unwrap();
+        let confusions = QueryConfusions {
+            owner: "admin".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(confusions.len(), 1);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::core::backend::StorageError;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub reading: String,
+    pub meaning: String,
+}
+
+/// Readings and meanings by word, read from tab-separated `word`, `reading` and `meaning` lines.
+/// Blank lines and lines starting with `#` are skipped.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    entries: HashMap<String, Vec<Entry>>,
+}
+
+impl Dictionary {
+    pub fn load(path: &str) -> Result<Self, StorageError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| StorageError::Dictionary(format!("{path}: {err}")))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut entries: HashMap<String, Vec<Entry>> = HashMap::new();
+        let lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        for line in lines {
+            let mut fields = line.split('\t').map(str::trim);
+            let (Some(word), reading, meaning) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            entries.entry(word.to_string()).or_default().push(Entry {
+                reading: reading.unwrap_or_default().to_string(),
+                meaning: meaning.unwrap_or_default().to_string(),
+            });
+        }
+        Self { entries }
+    }
+
+    /// The word's entry with `reading`, or its first entry when no reading is given.
+    pub fn lookup(&self, word: &str, reading: Option<&str>) -> Option<&Entry> {
+        let entries = self.entries.get(word)?;
+        match reading {
+            Some(reading) => entries.iter().find(|entry| entry.reading == reading),
+            None => entries.first(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::core::backend::StorageError;
+use crate::core::data::tag::split_tags;
+use crate::core::data::NewPhrase;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// A lesson's title and phrases, with everything needed to import them again.
+pub struct QueryLessonSheet {
+    pub lesson_id: i64,
+}
+
+impl QueryLessonSheet {
+    pub fn apply(self, db: &Connection) -> Result<(String, Vec<NewPhrase>), StorageError> {
+        const TITLE_SQL: &str = "SELECT title FROM lessons WHERE id = ?1";
+        const SQL: &str = r#"
+SELECT prompt, reading, translation, chapter, sentence, content_changed_at, source_url,
+       (SELECT GROUP_CONCAT(name, ' ')
+        FROM (SELECT tags.name
+              FROM phrase_tags
+                       JOIN tags ON tags.id = phrase_tags.tag_id
+              WHERE phrase_tags.phrase_id = phrases.id
+              ORDER BY tags.name))
+FROM phrases
+WHERE lesson_id = ?1
+ORDER BY chapter, id
+"#;
+        let title = db
+            .query_row(TITLE_SQL, params![self.lesson_id], |row| row.get(0))
+            .optional()?
+            .ok_or(StorageError::NotFound("Lesson"))?;
+        let phrases = db
+            .prepare(SQL)?
+            .query_map(params![self.lesson_id], |row| {
+                let tags: Option<String> = row.get(7)?;
+                Ok(NewPhrase {
+                    prompt: row.get(0)?,
+                    reading: row.get(1)?,
+                    translation: row.get(2)?,
+                    chapter: row.get(3)?,
+                    sentence: row.get(4)?,
+                    content_changed_at: row.get(5)?,
+                    tags: tags.map(|tags| split_tags(&tags)).unwrap_or_default(),
+                    source_url: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((title, phrases))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryLessonSheet;
+    use crate::core::api::{parse_drills, write_sheet};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::connect;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn sheet_round_trips() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Sheet".to_string(),
+            owner: "admin".to_string(),
+            phrases: vec![
+                NewPhrase {
+                    prompt: "意外".to_string(),
+                    reading: "いがい".to_string(),
+                    translation: "unexpected".to_string(),
+                    chapter: Some(2),
+                    sentence: "意外だった。".to_string(),
+                    tags: vec!["n3".to_string(), "adj".to_string()],
+                    ..Default::default()
+                },
+                NewPhrase {
+                    prompt: "ほら".to_string(),
+                    reading: "ほら".to_string(),
+                    translation: "look".to_string(),
+                    chapter: Some(1),
+                    ..Default::default()
+                },
+            ],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let (title, phrases) = QueryLessonSheet { lesson_id }.apply(&conn).unwrap();
+        assert_eq!(title, "Sheet");
+        let drills = parse_drills(&write_sheet(&phrases).unwrap()).unwrap();
+        let read = drills
+            .iter()
+            .map(|drill| (drill.chapter, drill.kanji.as_str(), drill.yomi.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(read, [(1, "ほら", "ほら"), (2, "意外", "いがい")]);
+        assert_eq!(drills[1].tags, ["adj", "n3"]);
+        assert_eq!(drills[1].sentence, "意外だった。");
+        assert!(QueryLessonSheet { lesson_id: -1 }.apply(&conn).is_err());
+    }
+}
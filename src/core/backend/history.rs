@@ -0,0 +1,236 @@
+use crate::core::backend::schedule::Day;
+use crate::core::backend::StorageError;
+use crate::core::data::history::{DailyStats, SessionSummary};
+use rusqlite::{named_params, Connection};
+
+/// Practice totals for each of the last `days` practice days, oldest first.
+pub struct QueryDailyStats {
+    pub user_id: String,
+    pub day: Day,
+    pub days: usize,
+}
+
+impl QueryDailyStats {
+    pub fn apply(self, db: &Connection) -> Result<Vec<DailyStats>, StorageError> {
+        const SQL: &str = r#"
+WITH RECURSIVE days (n, date) AS (
+    SELECT 0, date(:day_start - :offset)
+    UNION ALL
+    SELECT n + 1, date(:day_start - :offset - (n + 1)) FROM days WHERE n + 1 < :days
+),
+reviewed AS (
+    SELECT date(reviews.reviewed_at - :offset) AS date,
+           COUNT(*)                            AS reviews,
+           SUM(reviews.kind = 'new')           AS new_cards
+    FROM reviews
+             JOIN phrases ON phrases.id = reviews.phrase_id
+             JOIN lessons ON lessons.id = phrases.lesson_id
+    WHERE lessons.creator_id = :user_id
+      AND reviews.reviewed_at >= :from
+    GROUP BY 1
+),
+practiced AS (
+    SELECT date(finished_at - :offset) AS date,
+           COUNT(*)                    AS sessions,
+           SUM(learned)                AS learned,
+           SUM(failed)                 AS failed,
+           SUM(repeated)               AS repeated,
+           SUM(passed)                 AS passed,
+           SUM(active_seconds)         AS seconds
+    FROM practice_sessions
+    WHERE user_id = :user_id
+      AND finished_at >= :from
+    GROUP BY 1
+)
+SELECT days.date,
+       COALESCE(reviewed.reviews, 0),
+       COALESCE(reviewed.new_cards, 0),
+       COALESCE(practiced.sessions, 0),
+       COALESCE(practiced.learned, 0),
+       COALESCE(practiced.failed, 0),
+       COALESCE(practiced.repeated, 0),
+       COALESCE(practiced.passed, 0),
+       COALESCE(practiced.seconds, 0)
+FROM days
+         LEFT JOIN reviewed ON reviewed.date = days.date
+         LEFT JOIN practiced ON practiced.date = days.date
+ORDER BY days.date
+"#;
+        let days = self.days.max(1);
+        let mut stmt = db.prepare(SQL)?;
+        let rows = stmt.query_map(
+            named_params! {
+                ":user_id": self.user_id,
+                ":day_start": self.day.start,
+                ":offset": self.day.start_offset(),
+                ":days": days,
+                ":from": self.day.start - (days - 1) as f64,
+            },
+            |row| {
+                let count = |index: usize| -> rusqlite::Result<usize> {
+                    let value: i64 = row.get(index)?;
+                    Ok(value as usize)
+                };
+                let seconds: f64 = row.get(8)?;
+                Ok(DailyStats {
+                    date: row.get(0)?,
+                    reviews: count(1)?,
+                    new_cards: count(2)?,
+                    sessions: count(3)?,
+                    learned: count(4)?,
+                    failed: count(5)?,
+                    repeated: count(6)?,
+                    passed: count(7)?,
+                    seconds: seconds.round() as u64,
+                })
+            },
+        )?;
+        let stats = rows.collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+}
+
+/// The user's most recently finished sessions.
+pub struct QuerySessionHistory {
+    pub user_id: String,
+    pub limit: usize,
+}
+
+impl QuerySessionHistory {
+    pub fn apply(self, db: &Connection) -> Result<Vec<SessionSummary>, StorageError> {
+        const SQL: &str = r#"
+SELECT practice_sessions.id,
+       COALESCE(lessons.title, 'All lessons'),
+       strftime('%Y-%m-%d %H:%M', practice_sessions.finished_at),
+       practice_sessions.learned,
+       practice_sessions.failed,
+       practice_sessions.repeated,
+       practice_sessions.passed,
+       practice_sessions.active_seconds,
+       practice_sessions.cram
+FROM practice_sessions
+         LEFT JOIN lessons ON lessons.id = practice_sessions.lesson_id
+WHERE practice_sessions.user_id = :user_id
+  AND practice_sessions.finished_at IS NOT NULL
+ORDER BY practice_sessions.finished_at DESC
+LIMIT :limit
+"#;
+        let mut stmt = db.prepare(SQL)?;
+        let rows = stmt.query_map(
+            named_params! { ":user_id": self.user_id, ":limit": self.limit },
+            |row| {
+                let count = |index: usize| -> rusqlite::Result<usize> {
+                    let value: i64 = row.get(index)?;
+                    Ok(value as usize)
+                };
+                let seconds: f64 = row.get(7)?;
+                Ok(SessionSummary {
+                    session_id: row.get(0)?,
+                    lesson_title: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    learned: count(3)?,
+                    failed: count(4)?,
+                    repeated: count(5)?,
+                    passed: count(6)?,
+                    seconds: seconds.round() as u64,
+                    cram: row.get(8)?,
+                })
+            },
+        )?;
+        let sessions = rows.collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryDailyStats, QuerySessionHistory};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::{InsertLesson, UpdateLessonTimes};
+    use crate::core::backend::practice_session::{FinishSession, SaveSession, StartSession};
+    use crate::core::backend::schedule::Day;
+    use crate::core::backend::{connect, read_phrases};
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn stats_are_grouped_by_practice_day() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "History".to_string(),
+            owner: "admin".to_string(),
+            phrases: vec![NewPhrase {
+                prompt: "嫌".to_string(),
+                content_changed_at: Some(2460300.5),
+                ..Default::default()
+            }],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let phrase_id = read_phrases(lesson_id, &conn).unwrap()[0].phrase_id;
+        // 2024-01-01 10:00 local, with days starting at 03:00.
+        let today = Day::new(2460310.5 + 10.0 / 24.0, 3);
+        // 2024-01-01 01:00 local still belongs to the practice day of 2023-12-31.
+        let last_night = Day::new(2460310.5 + 1.0 / 24.0, 3);
+        for day in [last_night, today] {
+            UpdateLessonTimes {
+                phrase_ids: vec![phrase_id],
+                day,
+            }
+            .apply(&mut conn)
+            .unwrap();
+        }
+        let session_id = StartSession {
+            user_id: "admin".to_string(),
+            lesson_id: Some(lesson_id),
+            cram: false,
+            deck: "{}".to_string(),
+            now: today.now,
+        }
+        .apply(&mut conn)
+        .unwrap();
+        // Two minutes of practice, then a break longer than the cap.
+        SaveSession {
+            user_id: "admin".to_string(),
+            session_id,
+            deck: "{}".to_string(),
+            now: today.now + 120.0 / 86400.0,
+        }
+        .apply(&conn)
+        .unwrap();
+        FinishSession {
+            user_id: "admin".to_string(),
+            session_id,
+            learned: 1,
+            failed: 2,
+            repeated: 0,
+            passed: 1,
+            now: today.now + 0.5 / 24.0,
+        }
+        .apply(&conn)
+        .unwrap();
+
+        let stats = QueryDailyStats {
+            user_id: "admin".to_string(),
+            day: today,
+            days: 3,
+        }
+        .apply(&conn)
+        .unwrap();
+        let dates = stats.iter().map(|s| s.date.as_str()).collect::<Vec<_>>();
+        assert_eq!(dates, vec!["2023-12-30", "2023-12-31", "2024-01-01"]);
+        assert_eq!((stats[1].reviews, stats[1].new_cards), (1, 1));
+        assert_eq!((stats[2].reviews, stats[2].new_cards), (1, 0));
+        assert_eq!((stats[2].sessions, stats[2].failed), (1, 2));
+        assert_eq!(stats[2].seconds, 420);
+
+        let history = QuerySessionHistory {
+            user_id: "admin".to_string(),
+            limit: 10,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].lesson_title, "History");
+        assert_eq!(history[0].finished_at, "2024-01-01 10:30");
+    }
+}
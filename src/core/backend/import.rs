@@ -0,0 +1,347 @@
+use crate::core::backend::insert_lesson::{insert_lesson, insert_phrase, InsertLesson};
+use crate::core::backend::tag::tag_phrase;
+use crate::core::backend::StorageError;
+use crate::core::data::import::{Duplicate, DuplicatePolicy};
+use crate::core::data::NewPhrase;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// An earlier phrase with the same prompt and reading.
+struct Original {
+    phrase_id: i64,
+    /// `None` when the original comes from the same import.
+    lesson_title: Option<String>,
+}
+
+/// The user's first phrase with this prompt and reading, in an existing lesson or in `lesson_id`.
+fn find_original(
+    db: &Connection,
+    user_id: &str,
+    lesson_id: Option<i64>,
+    phrase: &NewPhrase,
+) -> Result<Option<Original>, StorageError> {
+    const SQL: &str = r#"
+SELECT phrases.id, lessons.id, lessons.title
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE lessons.creator_id = ?1
+  AND phrases.prompt = ?2
+  AND phrases.reading = ?3
+ORDER BY phrases.id
+LIMIT 1
+"#;
+    let original = db
+        .prepare_cached(SQL)?
+        .query_row(params![user_id, phrase.prompt, phrase.reading], |row| {
+            let phrase_id: i64 = row.get(0)?;
+            let original_lesson: i64 = row.get(1)?;
+            let title: String = row.get(2)?;
+            Ok(Original {
+                phrase_id,
+                lesson_title: (Some(original_lesson) != lesson_id).then_some(title),
+            })
+        })
+        .optional()?;
+    Ok(original)
+}
+
+/// The rows of an import that repeat an existing phrase or an earlier row, in import order.
+pub struct QueryDuplicates<'a> {
+    pub user_id: String,
+    pub phrases: &'a [NewPhrase],
+}
+
+impl QueryDuplicates<'_> {
+    pub fn apply(self, db: &Connection) -> Result<Vec<Duplicate>, StorageError> {
+        let mut duplicates = Vec::new();
+        for (index, phrase) in self.phrases.iter().enumerate() {
+            let repeats_row = self.phrases[..index].iter().any(|earlier| {
+                earlier.prompt == phrase.prompt && earlier.reading == phrase.reading
+            });
+            let lesson_title = find_original(db, &self.user_id, None, phrase)?
+                .and_then(|original| original.lesson_title);
+            if repeats_row || lesson_title.is_some() {
+                duplicates.push(Duplicate {
+                    prompt: phrase.prompt.clone(),
+                    reading: phrase.reading.clone(),
+                    chapter: phrase.chapter,
+                    lesson_title,
+                });
+            }
+        }
+        Ok(duplicates)
+    }
+}
+
+/// Inserts a lesson, resolving duplicates of existing phrases and of earlier rows by `duplicates`.
+/// The lesson is made only once a row is added to it, so an import whose rows are all skipped or
+/// merged returns `None` and leaves no empty lesson behind.
+pub struct ImportLesson {
+    pub lesson: InsertLesson,
+    pub duplicates: DuplicatePolicy,
+}
+
+impl ImportLesson {
+    pub fn apply(self, conn: &mut Connection) -> Result<Option<i64>, StorageError> {
+        let Self { lesson, duplicates } = self;
+        if duplicates == DuplicatePolicy::Keep {
+            return lesson.apply(conn).map(Some);
+        }
+        let tx = conn.transaction()?;
+        let mut lesson_id = None;
+        let insert = |tx: &Connection, lesson_id: &mut Option<i64>, phrase| {
+            let id = match *lesson_id {
+                Some(id) => id,
+                None => *lesson_id.insert(insert_lesson(tx, &lesson.title, &lesson.owner)?),
+            };
+            insert_phrase(tx, id, &lesson.owner, phrase)
+        };
+        for phrase in &lesson.phrases {
+            let Some(original) = find_original(&tx, &lesson.owner, lesson_id, phrase)? else {
+                insert(&tx, &mut lesson_id, phrase)?;
+                continue;
+            };
+            match duplicates {
+                DuplicatePolicy::Skip | DuplicatePolicy::Keep => {}
+                DuplicatePolicy::Merge => {
+                    merge_phrase(&tx, &lesson.owner, original.phrase_id, phrase)?;
+                }
+                DuplicatePolicy::Link => {
+                    let phrase_id = insert(&tx, &mut lesson_id, phrase)?;
+                    link_phrase(&tx, original.phrase_id, phrase_id)?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(lesson_id)
+    }
+}
+
+/// Folds a new row's meaning, sentence and tags into an existing phrase.
+fn merge_phrase(
+    db: &Connection,
+    owner: &str,
+    phrase_id: i64,
+    phrase: &NewPhrase,
+) -> Result<(), StorageError> {
+    const SQL: &str = r#"
+UPDATE phrases
+SET translation = CASE
+                      WHEN ?2 = '' OR instr(translation, ?2) > 0 THEN translation
+                      WHEN translation = '' THEN ?2
+                      ELSE translation || '; ' || ?2
+    END,
+    sentence    = CASE WHEN sentence = '' THEN ?3 ELSE sentence END
+WHERE id = ?1
+"#;
+    db.execute(SQL, params![phrase_id, phrase.translation, phrase.sentence])?;
+    for tag in &phrase.tags {
+        tag_phrase(db, owner, phrase_id, tag)?;
+    }
+    Ok(())
+}
+
+/// Joins `phrase_id` to the link group of `original_id`, taking on the group's review state.
+fn link_phrase(db: &Connection, original_id: i64, phrase_id: i64) -> Result<(), StorageError> {
+    const SQL: &str = r#"
+UPDATE phrases
+SET link_id = COALESCE(link_id, id)
+WHERE id = ?1
+"#;
+    const JOIN_SQL: &str = r#"
+UPDATE phrases
+SET link_id = original.link_id
+FROM (SELECT link_id FROM phrases WHERE id = ?1) AS original
+WHERE phrases.id = ?2
+"#;
+    db.execute(SQL, params![original_id])?;
+    db.execute(JOIN_SQL, params![original_id, phrase_id])?;
+    sync_linked(db, original_id)?;
+    Ok(())
+}
+
+/// Copies a phrase's review state, suspension and burial included, to the phrases linked to it.
+pub fn sync_linked(db: &Connection, phrase_id: i64) -> Result<(), StorageError> {
+    const SQL: &str = r#"
+UPDATE phrases
+SET learned_at   = source.learned_at,
+    lapses       = source.lapses,
+    is_leech     = source.is_leech,
+    suspended    = source.suspended,
+    buried_until = source.buried_until
+FROM (
+    SELECT link_id, learned_at, lapses, is_leech, suspended, buried_until
+    FROM phrases
+    WHERE id = ?1
+) AS source
+WHERE phrases.link_id = source.link_id
+  AND phrases.id != ?1
+"#;
+    db.prepare_cached(SQL)?.execute(params![phrase_id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportLesson, QueryDuplicates};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::{InsertLesson, UpdateLessonTimes};
+    use crate::core::backend::phrase::{BuryPhrase, SuspendPhrase};
+    use crate::core::backend::schedule::Day;
+    use crate::core::backend::{connect, read_phrases, read_user_lessons};
+    use crate::core::data::import::DuplicatePolicy;
+    use crate::core::data::NewPhrase;
+
+    fn sheet() -> Vec<NewPhrase> {
+        [
+            ("意外", "unexpected"),
+            ("以外", "except"),
+            ("意外", "surprising"),
+        ]
+        .into_iter()
+        .map(|(prompt, translation)| NewPhrase {
+            prompt: prompt.to_string(),
+            reading: "いがい".to_string(),
+            translation: translation.to_string(),
+            tags: vec![translation.to_string()],
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn duplicates_follow_the_policy() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let import = |conn: &mut rusqlite::Connection, duplicates| {
+            let lesson_id = ImportLesson {
+                lesson: InsertLesson {
+                    title: format!("{duplicates:?}"),
+                    owner: "admin".to_string(),
+                    phrases: sheet(),
+                },
+                duplicates,
+            }
+            .apply(conn)
+            .unwrap();
+            lesson_id
+                .map(|lesson_id| read_phrases(lesson_id, conn).unwrap())
+                .unwrap_or_default()
+        };
+        let first = import(&mut conn, DuplicatePolicy::Merge);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].translation, "unexpected; surprising");
+        assert_eq!(first[0].tags, ["surprising", "unexpected"]);
+
+        let duplicates = QueryDuplicates {
+            user_id: "admin".to_string(),
+            phrases: &sheet(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(duplicates.len(), 3);
+        assert_eq!(duplicates[0].lesson_title.as_deref(), Some("Merge"));
+
+        assert!(import(&mut conn, DuplicatePolicy::Skip).is_empty());
+        let titles = read_user_lessons("admin", &conn)
+            .unwrap()
+            .into_iter()
+            .map(|lesson| lesson.title)
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Merge"]);
+        assert_eq!(import(&mut conn, DuplicatePolicy::Keep).len(), 3);
+
+        let linked = import(&mut conn, DuplicatePolicy::Link);
+        assert_eq!(linked.len(), 3);
+        UpdateLessonTimes {
+            phrase_ids: vec![linked[1].phrase_id],
+            day: Day::new(2460310.6, 3),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let learned = conn
+            .query_row(
+                "SELECT COUNT(*) FROM phrases WHERE prompt = '以外' AND learned_at IS NOT NULL",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap();
+        // The Merge original and its Link copy, but not the independent Keep copy.
+        assert_eq!(learned, 2);
+
+        SuspendPhrase {
+            phrase_id: linked[1].phrase_id,
+            suspended: true,
+        }
+        .apply(&conn)
+        .unwrap();
+        BuryPhrase {
+            phrase_id: linked[1].phrase_id,
+            day: Day::new(2460310.6, 3),
+            buried: true,
+        }
+        .apply(&conn)
+        .unwrap();
+        let hidden = conn
+            .query_row(
+                "SELECT COUNT(*) FROM phrases
+                 WHERE prompt = '以外' AND suspended AND buried_until IS NOT NULL",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap();
+        assert_eq!(hidden, 2);
+    }
+
+    #[test]
+    fn deleting_the_original_keeps_the_links() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let mut import = |duplicates| {
+            ImportLesson {
+                lesson: InsertLesson {
+                    title: format!("{duplicates:?}"),
+                    owner: "admin".to_string(),
+                    phrases: sheet(),
+                },
+                duplicates,
+            }
+            .apply(&mut conn)
+            .unwrap()
+            .unwrap()
+        };
+        let original = import(DuplicatePolicy::Merge);
+        let copies = [import(DuplicatePolicy::Link), import(DuplicatePolicy::Link)];
+        let unlinked = import(DuplicatePolicy::Keep);
+        conn.execute("DELETE FROM lessons WHERE id = ?1", [original])
+            .unwrap();
+
+        let first = read_phrases(copies[0], &conn).unwrap();
+        SuspendPhrase {
+            phrase_id: first[1].phrase_id,
+            suspended: true,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert!(read_phrases(copies[1], &conn).unwrap()[1].suspended);
+        assert!(!read_phrases(unlinked, &conn).unwrap()[1].suspended);
+        let link_ids = conn
+            .prepare("SELECT link_id FROM phrases WHERE prompt = '以外' ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get::<_, Option<i64>>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        // Both copies re-keyed on the first, the Keep copy still on its own.
+        let first_id = Some(first[1].phrase_id);
+        assert_eq!(link_ids, [first_id, first_id, None]);
+
+        conn.execute("DELETE FROM lessons WHERE id = ?1", [copies[0]])
+            .unwrap();
+        let lone = conn
+            .query_row(
+                "SELECT link_id FROM phrases WHERE lesson_id = ?1 AND prompt = '以外'",
+                [copies[1]],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .unwrap();
+        assert_eq!(lone, None);
+    }
+}
@@ -1,19 +1,38 @@
+use crate::core::backend::import::sync_linked;
+use crate::core::backend::schedule::{Day, READY};
+use crate::core::backend::tag::tag_phrase;
 use crate::core::backend::StorageError;
 use crate::core::data::NewPhrase;
-use rusqlite::params;
+use rusqlite::{named_params, params, OptionalExtension};
 
 pub struct UpdateLessonTimes {
     pub phrase_ids: Vec<i64>,
-    pub now: f64,
+    pub day: Day,
 }
 impl UpdateLessonTimes {
     pub fn apply(self, conn: &mut rusqlite::Connection) -> Result<(), StorageError> {
         let tx = conn.transaction()?;
         {
+            let log = format!(
+                r#"
+INSERT INTO reviews (phrase_id, reviewed_at, kind, previous_learned_at)
+SELECT id, :now, CASE
+        WHEN learned_at IS NULL THEN 'new'
+        WHEN {READY} THEN 'review'
+        ELSE 'practice'
+    END, learned_at
+FROM phrases
+WHERE id = :id
+"#
+            );
             const SQL: &str = "UPDATE phrases SET learned_at = ?1 WHERE id = ?2";
+            let mut log = tx.prepare(&log)?;
             let mut stmt = tx.prepare(SQL)?;
+            let Day { now, start } = self.day;
             for id in self.phrase_ids {
-                stmt.execute(params![self.now, id])?;
+                log.execute(named_params! { ":now": now, ":day_start": start, ":id": id })?;
+                stmt.execute(params![now, id])?;
+                sync_linked(&tx, id)?;
             }
         }
         tx.commit()?;
@@ -21,6 +40,41 @@ impl UpdateLessonTimes {
     }
 }
 
+/// Takes back the latest review of a phrase, restoring its previous `learned_at`.
+pub struct RevertLessonTime {
+    pub phrase_id: i64,
+}
+
+impl RevertLessonTime {
+    pub fn apply(self, conn: &mut rusqlite::Connection) -> Result<(), StorageError> {
+        const LATEST: &str = r#"
+SELECT id, previous_learned_at
+FROM reviews
+WHERE phrase_id = ?1
+ORDER BY id DESC
+LIMIT 1
+"#;
+        let tx = conn.transaction()?;
+        let latest = tx
+            .query_row(LATEST, params![self.phrase_id], |row| {
+                let id: i64 = row.get(0)?;
+                let previous: Option<f64> = row.get(1)?;
+                Ok((id, previous))
+            })
+            .optional()?;
+        if let Some((review_id, previous)) = latest {
+            tx.execute(
+                "UPDATE phrases SET learned_at = ?1 WHERE id = ?2",
+                params![previous, self.phrase_id],
+            )?;
+            tx.execute("DELETE FROM reviews WHERE id = ?1", params![review_id])?;
+            sync_linked(&tx, self.phrase_id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
 pub struct InsertLesson {
     pub title: String,
     pub owner: String,
@@ -30,39 +84,115 @@ pub struct InsertLesson {
 impl InsertLesson {
     pub fn apply(self, conn: &mut rusqlite::Connection) -> Result<i64, StorageError> {
         let tx = conn.transaction()?;
-        let lesson_id: i64 = {
-            const SQL: &str =
-                "INSERT INTO lessons (title, creator_id) VALUES (?1, ?2) RETURNING id";
-            let mut stmt = tx.prepare(SQL)?;
-            stmt.query_row(params![self.title, self.owner], |row| row.get(0))?
-        };
-        {
-            const SQL_4: &str =
-                "INSERT INTO phrases (lesson_id, prompt, reading, translation) VALUES (?1, ?2, ?3, ?4)";
-            const SQL_5: &str =
-                "INSERT INTO phrases (lesson_id, prompt, reading, translation, content_changed_at) VALUES (?1, ?2, ?3, ?4, ?5)";
-            let mut stmt_4 = tx.prepare(SQL_4)?;
-            let mut stmt_5 = tx.prepare(SQL_5)?;
-            for phrase in self.phrases {
-                if let Some(content_changed_at) = phrase.content_changed_at {
-                    stmt_5.execute(params![
-                        lesson_id,
-                        phrase.prompt,
-                        phrase.reading,
-                        phrase.translation,
-                        content_changed_at
-                    ])?;
-                } else {
-                    stmt_4.execute(params![
-                        lesson_id,
-                        phrase.prompt,
-                        phrase.reading,
-                        phrase.translation
-                    ])?;
-                }
-            }
+        let lesson_id = insert_lesson(&tx, &self.title, &self.owner)?;
+        for phrase in &self.phrases {
+            insert_phrase(&tx, lesson_id, &self.owner, phrase)?;
         }
         tx.commit()?;
         Ok(lesson_id)
     }
 }
+
+pub fn insert_lesson(
+    db: &rusqlite::Connection,
+    title: &str,
+    owner: &str,
+) -> Result<i64, StorageError> {
+    const SQL: &str = "INSERT INTO lessons (title, creator_id) VALUES (?1, ?2) RETURNING id";
+    let lesson_id = db.query_row(SQL, params![title, owner], |row| row.get(0))?;
+    Ok(lesson_id)
+}
+
+/// Adds a phrase and its tags to a lesson, returning the phrase id.
+pub fn insert_phrase(
+    db: &rusqlite::Connection,
+    lesson_id: i64,
+    owner: &str,
+    phrase: &NewPhrase,
+) -> Result<i64, StorageError> {
+    const SQL_4: &str =
+        "INSERT INTO phrases (lesson_id, prompt, reading, translation, chapter, sentence, source_url) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+    const SQL_5: &str =
+        "INSERT INTO phrases (lesson_id, prompt, reading, translation, chapter, sentence, source_url, content_changed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+    if let Some(content_changed_at) = phrase.content_changed_at {
+        db.prepare_cached(SQL_5)?.execute(params![
+            lesson_id,
+            phrase.prompt,
+            phrase.reading,
+            phrase.translation,
+            phrase.chapter,
+            phrase.sentence,
+            phrase.source_url,
+            content_changed_at
+        ])?;
+    } else {
+        db.prepare_cached(SQL_4)?.execute(params![
+            lesson_id,
+            phrase.prompt,
+            phrase.reading,
+            phrase.translation,
+            phrase.chapter,
+            phrase.sentence,
+            phrase.source_url
+        ])?;
+    }
+    let phrase_id = db.last_insert_rowid();
+    for tag in &phrase.tags {
+        tag_phrase(db, owner, phrase_id, tag)?;
+    }
+    Ok(phrase_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InsertLesson, RevertLessonTime, UpdateLessonTimes};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::schedule::Day;
+    use crate::core::backend::{connect, read_phrases};
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn reverting_restores_the_previous_learned_at() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Undo".to_string(),
+            owner: "admin".to_string(),
+            phrases: vec![NewPhrase {
+                prompt: "嫌".to_string(),
+                content_changed_at: Some(2460300.5),
+                ..Default::default()
+            }],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let phrase_id = read_phrases(lesson_id, &conn).unwrap()[0].phrase_id;
+        let learned_at = |conn: &rusqlite::Connection| -> Option<f64> {
+            conn.query_row(
+                "SELECT learned_at FROM phrases WHERE id = ?1",
+                [phrase_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let reviews = |conn: &rusqlite::Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM reviews", [], |row| row.get(0))
+                .unwrap()
+        };
+        for now in [2460310.6, 2460311.6] {
+            UpdateLessonTimes {
+                phrase_ids: vec![phrase_id],
+                day: Day::new(now, 3),
+            }
+            .apply(&mut conn)
+            .unwrap();
+        }
+        assert_eq!(reviews(&conn), 2);
+
+        RevertLessonTime { phrase_id }.apply(&mut conn).unwrap();
+        assert_eq!(learned_at(&conn), Some(2460310.6));
+        assert_eq!(reviews(&conn), 1);
+        RevertLessonTime { phrase_id }.apply(&mut conn).unwrap();
+        assert_eq!(learned_at(&conn), None);
+        assert_eq!(reviews(&conn), 0);
+    }
+}
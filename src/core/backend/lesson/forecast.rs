@@ -0,0 +1,227 @@
+use crate::core::backend::lesson::CountDoneToday;
+use crate::core::backend::schedule::{julian_date, Day, ACTIVE, READY};
+use crate::core::backend::settings::QueryPracticeLimits;
+use crate::core::backend::StorageError;
+use crate::core::data::forecast::DueDay;
+use crate::core::data::settings::PracticeLimits;
+use rusqlite::{named_params, params, Connection};
+
+/// Projects the phrases due on each of the next `days` practice days, starting today,
+/// assuming each day's work gets done. Every learned phrase is ready again the next day,
+/// so the daily limits decide how much of the lesson comes due.
+pub struct QueryForecast {
+    pub lesson_id: i64,
+    pub day: Day,
+    pub limits: PracticeLimits,
+    pub days: usize,
+}
+
+impl QueryForecast {
+    pub fn apply(self, db: &Connection) -> Result<Vec<DueDay>, StorageError> {
+        let Self {
+            lesson_id,
+            day,
+            limits,
+            days,
+        } = self;
+        let sql = format!(
+            r#"
+SELECT
+    COUNT(CASE WHEN learned_at IS NULL AND {ACTIVE} THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN learned_at IS NOT NULL AND {READY} AND {ACTIVE} THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN learned_at IS NULL AND suspended = 0 THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN learned_at IS NOT NULL AND suspended = 0 THEN 1 ELSE NULL END)
+FROM phrases
+WHERE lesson_id = :lesson_id
+"#
+        );
+        let params = named_params! {
+            ":lesson_id": lesson_id,
+            ":now": day.now,
+            ":day_start": day.start,
+        };
+        let (new_ready, reviews_ready, mut new_left, mut learned) =
+            db.query_row(&sql, params, |row| {
+                let count = |index: usize| -> rusqlite::Result<usize> {
+                    let value: i64 = row.get(index)?;
+                    Ok(value as usize)
+                };
+                Ok((count(0)?, count(1)?, count(2)?, count(3)?))
+            })?;
+        let done = CountDoneToday { lesson_id, day }.apply(db)?;
+        let mut forecast = Vec::with_capacity(days);
+        for n in 0..days {
+            let (new, reviews) = if n == 0 {
+                (
+                    new_ready.min(limits.new_per_day.saturating_sub(done.new)),
+                    reviews_ready.min(limits.reviews_per_day.saturating_sub(done.reviews)),
+                )
+            } else {
+                (
+                    new_left.min(limits.new_per_day),
+                    learned.min(limits.reviews_per_day),
+                )
+            };
+            new_left = new_left.saturating_sub(new);
+            learned += new;
+            forecast.push(DueDay {
+                date: julian_date(day.start - day.start_offset() + n as f64).to_string(),
+                new,
+                reviews,
+            });
+        }
+        Ok(forecast)
+    }
+}
+
+/// Adds up the forecasts of all the user's lessons.
+pub struct QueryUserForecast {
+    pub user_id: String,
+    pub day: Day,
+    pub days: usize,
+}
+
+impl QueryUserForecast {
+    pub fn apply(self, db: &Connection) -> Result<Vec<DueDay>, StorageError> {
+        const SQL: &str = "SELECT id FROM lessons WHERE creator_id = ?1 ORDER BY id";
+        let lesson_ids = db
+            .prepare(SQL)?
+            .query_map(params![self.user_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        let mut total: Vec<DueDay> = Vec::new();
+        for lesson_id in lesson_ids {
+            let limits = QueryPracticeLimits {
+                user_id: self.user_id.clone(),
+                lesson_id,
+            }
+            .apply(db)?;
+            let forecast = QueryForecast {
+                lesson_id,
+                day: self.day,
+                limits,
+                days: self.days,
+            }
+            .apply(db)?;
+            if total.is_empty() {
+                total = forecast;
+            } else {
+                for (sum, due) in total.iter_mut().zip(forecast) {
+                    sum.new += due.new;
+                    sum.reviews += due.reviews;
+                }
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Renders the days with work as all-day iCalendar events.
+pub fn to_icalendar(days: &[DueDay]) -> String {
+    let stamp = days
+        .first()
+        .map(|day| day.date.replace('-', ""))
+        .unwrap_or_default();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//kanjikoto//forecast//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for day in days.iter().filter(|day| day.total() > 0) {
+        let date = day.date.replace('-', "");
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{date}-due@kanjikoto"),
+            format!("DTSTAMP:{stamp}T000000Z"),
+            format!("DTSTART;VALUE=DATE:{date}"),
+            format!("SUMMARY:{} kanjikoto phrases due", day.total()),
+            format!(
+                "DESCRIPTION:{} reviews and {} new phrases",
+                day.reviews, day.new
+            ),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| format!("{line}\r\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_icalendar, QueryForecast};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::connect;
+    use crate::core::backend::insert_lesson::{InsertLesson, UpdateLessonTimes};
+    use crate::core::backend::schedule::Day;
+    use crate::core::data::forecast::DueDay;
+    use crate::core::data::settings::PracticeLimits;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn new_phrases_join_the_reviews() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Forecast".to_string(),
+            owner: "admin".to_string(),
+            phrases: (0..5)
+                .map(|n| NewPhrase {
+                    prompt: n.to_string(),
+                    content_changed_at: Some(2460300.5),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        // 2024-01-01 10:00 local.
+        let day = Day::new(2460310.5 + 10.0 / 24.0, 3);
+        UpdateLessonTimes {
+            phrase_ids: vec![1],
+            day,
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let forecast = QueryForecast {
+            lesson_id,
+            day,
+            limits: PracticeLimits {
+                session_size: 20,
+                new_per_day: 2,
+                reviews_per_day: 4,
+            },
+            days: 4,
+        }
+        .apply(&conn)
+        .unwrap();
+        let due = |date: &str, new, reviews| DueDay {
+            date: date.to_string(),
+            new,
+            reviews,
+        };
+        assert_eq!(
+            forecast,
+            vec![
+                due("2024-01-01", 1, 0),
+                due("2024-01-02", 2, 2),
+                due("2024-01-03", 1, 4),
+                due("2024-01-04", 0, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_skips_free_days() {
+        let due = |date: &str, new, reviews| DueDay {
+            date: date.to_string(),
+            new,
+            reviews,
+        };
+        let calendar = to_icalendar(&[due("2024-01-01", 1, 2), due("2024-01-02", 0, 0)]);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20240101\r\n"));
+        assert!(calendar.contains("SUMMARY:3 kanjikoto phrases due\r\n"));
+    }
+}
@@ -1,118 +1,235 @@
+use crate::core::backend::schedule::{Day, ACTIVE, READY};
+use crate::core::backend::tag::TAGGED;
 use crate::core::backend::StorageError;
 use crate::core::data::card::{Card, CardBack, CardFront, Goal};
-use crate::core::data::lesson_status::{LessonStatus, SESSION_SIZE};
+use crate::core::data::lesson_status::LessonStatus;
+use crate::core::data::settings::PracticeLimits;
 use rand::prelude::SliceRandom;
-use rusqlite::{params, Connection};
+use rusqlite::{named_params, params, Connection};
 
+pub mod forecast;
+pub mod review;
+pub mod study;
+
+/// Counts a lesson's phrases, or only those tagged `tag`.
 pub struct QueryLessonStatus {
     pub lesson_id: i64,
-    pub now: f64,
+    pub day: Day,
+    pub limits: PracticeLimits,
+    pub tag: Option<String>,
 }
 
 impl QueryLessonStatus {
     pub fn apply(self, db: &Connection) -> Result<LessonStatus, StorageError> {
-        let lesson_id = self.lesson_id;
-        const SQL: &str = r#"
+        let Self {
+            lesson_id,
+            day,
+            limits,
+            tag,
+        } = self;
+        let sql = format!(
+            r#"
 SELECT
-	COUNT(CASE
-		WHEN learned_at IS NULL THEN 1
-		WHEN learned_at < julianday(?1, 'start of day', '+3 hours') THEN 1
-		WHEN content_changed_at > learned_at THEN 1
-		ELSE NULL
-	END) as count_before_3am,
+	COUNT(CASE WHEN learned_at IS NULL THEN 1 ELSE NULL END) as count_new,
+	COUNT(CASE WHEN learned_at IS NOT NULL AND {READY} THEN 1 ELSE NULL END) as count_review,
 	COUNT(*) as count_all
 FROM
 	 phrases
 WHERE
-	lesson_id = ?2;
+	lesson_id = :lesson_id
+	AND {ACTIVE}
+	AND {TAGGED};
+"#
+        );
+        let params = named_params! {
+            ":lesson_id": lesson_id,
+            ":now": day.now,
+            ":day_start": day.start,
+            ":tag": tag,
+        };
+        let (new_count, review_count, total_count) = db.query_row(&sql, params, |row| {
+            let new_count: i64 = row.get(0)?;
+            let review_count: i64 = row.get(1)?;
+            let total_count: i64 = row.get(2)?;
+            Ok((
+                new_count as usize,
+                review_count as usize,
+                total_count as usize,
+            ))
+        })?;
+        let done = CountDoneToday { lesson_id, day }.apply(db)?;
+        let ready = new_count.min(limits.new_per_day.saturating_sub(done.new))
+            + review_count.min(limits.reviews_per_day.saturating_sub(done.reviews));
+        Ok(LessonStatus {
+            lesson_id,
+            ready,
+            learned: total_count - new_count - review_count,
+            session_size: limits.session_size,
+        })
+    }
+}
+
+/// New phrases and reviews passed since the day started.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DoneToday {
+    pub new: usize,
+    pub reviews: usize,
+}
+
+pub struct CountDoneToday {
+    pub lesson_id: i64,
+    pub day: Day,
+}
+
+impl CountDoneToday {
+    pub fn apply(self, db: &Connection) -> Result<DoneToday, StorageError> {
+        const SQL: &str = r#"
+SELECT
+    COUNT(CASE WHEN r.kind = 'new' THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN r.kind = 'review' THEN 1 ELSE NULL END)
+FROM reviews r
+    JOIN phrases p ON p.id = r.phrase_id
+WHERE p.lesson_id = :lesson_id
+    AND r.reviewed_at >= :day_start
 "#;
-        let status = db.query_row(SQL, params![self.now, lesson_id], |row| {
-            let ready_count: i64 = row.get(0)?;
-            let total_count: i64 = row.get(1)?;
-            Ok(LessonStatus {
-                lesson_id,
-                ready: ready_count as usize,
-                learned: (total_count - ready_count) as usize,
+        let params = named_params! {
+            ":lesson_id": self.lesson_id,
+            ":day_start": self.day.start,
+        };
+        let done = db.query_row(SQL, params, |row| {
+            let new: i64 = row.get(0)?;
+            let reviews: i64 = row.get(1)?;
+            Ok(DoneToday {
+                new: new as usize,
+                reviews: reviews as usize,
             })
         })?;
-        Ok(status)
+        Ok(done)
     }
 }
 
+/// Cards for a lesson's practice session, or for its phrases tagged `tag`.
 pub struct QueryPracticeCards {
     pub lesson_id: i64,
-    pub now: f64,
+    pub day: Day,
+    pub limits: PracticeLimits,
+    pub tag: Option<String>,
 }
 
 impl QueryPracticeCards {
     pub fn apply(self, db: &Connection) -> Result<Vec<Card>, StorageError> {
-        let Self { lesson_id, now } = self;
-        let mut cards = select_ready(db, lesson_id, now)?;
-        if cards.len() < SESSION_SIZE {
-            let fill_count = SESSION_SIZE - cards.len();
-            let fill_cards = select_resting(db, lesson_id, now, fill_count)?;
+        let Self {
+            lesson_id,
+            day,
+            limits,
+            tag,
+        } = self;
+        let tag = tag.as_deref();
+        let session_size = limits.session_size;
+        let done = CountDoneToday { lesson_id, day }.apply(db)?;
+        let review_limit = limits
+            .reviews_per_day
+            .saturating_sub(done.reviews)
+            .min(session_size);
+        let mut cards = select_ready(db, lesson_id, day, tag, false, review_limit)?;
+        let new_limit = limits
+            .new_per_day
+            .saturating_sub(done.new)
+            .min(session_size - cards.len());
+        cards.extend(select_ready(db, lesson_id, day, tag, true, new_limit)?);
+        if cards.len() < session_size {
+            let fill_count = session_size - cards.len();
+            let fill_cards = select_resting(db, lesson_id, day, tag, fill_count)?;
             cards.extend(fill_cards);
-            cards.shuffle(&mut rand::rng());
         }
+        cards.shuffle(&mut rand::rng());
         Ok(cards)
     }
 }
 
-fn select_ready(db: &Connection, lesson_id: i64, now: f64) -> Result<Vec<Card>, StorageError> {
-    let mut select_ready = db.prepare(
+fn select_ready(
+    db: &Connection,
+    lesson_id: i64,
+    day: Day,
+    tag: Option<&str>,
+    new: bool,
+    limit: usize,
+) -> Result<Vec<Card>, StorageError> {
+    let sql = format!(
         r#"
 SELECT
     id, prompt, reading, translation
 FROM phrases
-WHERE lesson_id = ?1 AND
-    CASE
-        WHEN learned_at IS NULL THEN 1
-        WHEN learned_at < julianday(?2, 'start of day', '+3 hours') THEN 1
-        WHEN content_changed_at > learned_at THEN 1
-        ELSE 0
-    END
-    ORDER BY RANDOM() LIMIT ?3
+WHERE lesson_id = :lesson_id
+    AND {ACTIVE}
+    AND {READY}
+    AND {TAGGED}
+    AND (learned_at IS NULL) = :new
+    ORDER BY RANDOM() LIMIT :limit
 ;
-"#,
-    )?;
-    let cards = select_ready
-        .query_map(params![lesson_id, now, SESSION_SIZE], |row| {
-            Ok(Card {
-                id: row.get(0)?,
-                goal: Goal::Learn,
-                front: CardFront { kanji: row.get(1)? },
-                back: CardBack {
-                    yomi: row.get(2)?,
-                    meaning: row.get(3)?,
-                },
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(cards)
+"#
+    );
+    let params = named_params! {
+        ":lesson_id": lesson_id,
+        ":now": day.now,
+        ":day_start": day.start,
+        ":tag": tag,
+        ":new": new,
+        ":limit": limit,
+    };
+    select_cards(db, &sql, params)
 }
 
 fn select_resting(
     db: &Connection,
     lesson_id: i64,
-    now: f64,
+    day: Day,
+    tag: Option<&str>,
     limit: usize,
 ) -> Result<Vec<Card>, StorageError> {
-    let mut select_resting = db.prepare(
+    let sql = format!(
         r#"
 SELECT id, prompt, reading, translation
 FROM phrases
-WHERE lesson_id = ?1 AND
-    NOT CASE
-        WHEN learned_at IS NULL THEN 1
-        WHEN learned_at < julianday(?2, 'start of day', '+3 hours') THEN 1
-        WHEN content_changed_at > learned_at THEN 1
-        ELSE 0
-    END
-    ORDER BY RANDOM() LIMIT ?3
-"#,
-    )?;
-    let cards = select_resting
-        .query_map(params![lesson_id, now, limit], |row| {
+WHERE lesson_id = :lesson_id
+    AND {ACTIVE}
+    AND NOT {READY}
+    AND {TAGGED}
+    ORDER BY RANDOM() LIMIT :limit
+"#
+    );
+    let params = named_params! {
+        ":lesson_id": lesson_id,
+        ":now": day.now,
+        ":day_start": day.start,
+        ":tag": tag,
+        ":limit": limit,
+    };
+    select_cards(db, &sql, params)
+}
+
+/// Fails with `NotFound` unless the user made the lesson, so other users' lessons stay hidden.
+pub fn check_lesson_owner(
+    db: &Connection,
+    user_id: &str,
+    lesson_id: i64,
+) -> Result<(), StorageError> {
+    const SQL: &str = "SELECT COUNT(*) FROM lessons WHERE id = ?1 AND creator_id = ?2";
+    let count: usize = db.query_row(SQL, params![lesson_id, user_id], |row| row.get(0))?;
+    if count == 0 {
+        return Err(StorageError::NotFound("Lesson"));
+    }
+    Ok(())
+}
+
+fn select_cards(
+    db: &Connection,
+    sql: &str,
+    params: &[(&str, &dyn rusqlite::ToSql)],
+) -> Result<Vec<Card>, StorageError> {
+    let mut stmt = db.prepare(sql)?;
+    let cards = stmt
+        .query_map(params, |row| {
             Ok(Card {
                 id: row.get(0)?,
                 goal: Goal::Learn,
@@ -126,3 +243,87 @@ WHERE lesson_id = ?1 AND
         .collect::<Result<Vec<_>, _>>()?;
     Ok(cards)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_lesson_owner, QueryLessonStatus, QueryPracticeCards};
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::{InsertLesson, UpdateLessonTimes};
+    use crate::core::backend::schedule::{local_now, Day};
+    use crate::core::backend::{connect, StorageError};
+    use crate::core::data::settings::PracticeLimits;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn daily_limits_cap_new_cards() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let clock = FixedClock {
+            at: "2024-01-01T10:00:00Z".parse().unwrap(),
+        };
+        let day = Day::new(local_now(&clock, Some("UTC")).unwrap(), 3);
+        let lesson_id = InsertLesson {
+            title: "Limits".to_string(),
+            owner: "admin".to_string(),
+            phrases: (0..5)
+                .map(|n| NewPhrase {
+                    prompt: n.to_string(),
+                    content_changed_at: Some(day.start - 1.0),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let limits = PracticeLimits {
+            session_size: 3,
+            new_per_day: 2,
+            reviews_per_day: 10,
+        };
+        let cards = QueryPracticeCards {
+            lesson_id,
+            day,
+            limits,
+            tag: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(cards.len(), 2);
+
+        UpdateLessonTimes {
+            phrase_ids: vec![cards[0].id],
+            day,
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let status = QueryLessonStatus {
+            lesson_id,
+            day,
+            limits,
+            tag: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!((status.ready, status.learned), (1, 1));
+        assert_eq!(status.to_ready_learned(), (1, 1));
+    }
+
+    #[test]
+    fn only_the_creator_owns_a_lesson() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Mine".to_string(),
+            owner: "admin".to_string(),
+            phrases: Vec::new(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        check_lesson_owner(&conn, "admin", lesson_id).unwrap();
+        for (user_id, lesson_id) in [("kaito", lesson_id), ("admin", lesson_id + 1)] {
+            assert!(matches!(
+                check_lesson_owner(&conn, user_id, lesson_id),
+                Err(StorageError::NotFound("Lesson"))
+            ));
+        }
+    }
+}
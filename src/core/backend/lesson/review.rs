@@ -0,0 +1,121 @@
+use crate::core::backend::lesson::{select_ready, CountDoneToday};
+use crate::core::backend::schedule::Day;
+use crate::core::backend::settings::{QueryLessonSettings, QueryUserSettings};
+use crate::core::backend::StorageError;
+use crate::core::data::card::Card;
+use rusqlite::{params, Connection};
+
+/// Cards for a session that reviews every lesson: each lesson's ready phrases within its
+/// daily limits, interleaved by the lessons' review weights up to the user's session size.
+pub struct QueryReviewCards {
+    pub user_id: String,
+    pub day: Day,
+}
+
+impl QueryReviewCards {
+    pub fn apply(self, db: &Connection) -> Result<Vec<Card>, StorageError> {
+        let Self { user_id, day } = self;
+        let user = QueryUserSettings {
+            user_id: user_id.clone(),
+        }
+        .apply(db)?;
+        let session_size = user.session_size.max(1);
+        const SQL: &str = "SELECT id FROM lessons WHERE creator_id = ?1 ORDER BY id";
+        let lesson_ids = db
+            .prepare(SQL)?
+            .query_map(params![user_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        let mut piles = Vec::new();
+        for lesson_id in lesson_ids {
+            let lesson = QueryLessonSettings { lesson_id }.apply(db)?;
+            let weight = lesson.review_weight();
+            if weight == 0 {
+                continue;
+            }
+            let limits = user.limits_for(&lesson);
+            let done = CountDoneToday { lesson_id, day }.apply(db)?;
+            let review_limit = limits
+                .reviews_per_day
+                .saturating_sub(done.reviews)
+                .min(session_size);
+            let mut cards = select_ready(db, lesson_id, day, None, false, review_limit)?;
+            let new_limit = limits
+                .new_per_day
+                .saturating_sub(done.new)
+                .min(session_size - cards.len());
+            cards.extend(select_ready(db, lesson_id, day, None, true, new_limit)?);
+            piles.push((weight, cards));
+        }
+        Ok(interleave(piles, session_size))
+    }
+}
+
+/// Takes cards from the piles in proportion to their weights, spreading each pile's
+/// cards evenly, until `size` cards are taken or the piles run out.
+fn interleave(piles: Vec<(usize, Vec<Card>)>, size: usize) -> Vec<Card> {
+    let mut piles = piles
+        .into_iter()
+        .map(|(weight, cards)| (weight as i64, 0i64, cards.into_iter()))
+        .collect::<Vec<_>>();
+    let mut cards = Vec::with_capacity(size);
+    while cards.len() < size {
+        // Smooth weighted round robin over the piles that still have cards.
+        let mut total = 0;
+        for (weight, current, pile) in piles.iter_mut() {
+            if !pile.as_slice().is_empty() {
+                *current += *weight;
+                total += *weight;
+            }
+        }
+        let Some((_, current, pile)) = piles
+            .iter_mut()
+            .filter(|(_, _, pile)| !pile.as_slice().is_empty())
+            .max_by_key(|(_, current, _)| *current)
+        else {
+            break;
+        };
+        *current -= total;
+        cards.extend(pile.next());
+    }
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interleave;
+    use crate::core::data::card::{Card, CardBack, CardFront, Goal};
+
+    fn cards(kanji: &str, count: usize) -> Vec<Card> {
+        (0..count)
+            .map(|n| Card {
+                id: n as i64,
+                goal: Goal::Review,
+                front: CardFront {
+                    kanji: kanji.to_string(),
+                },
+                back: CardBack {
+                    yomi: String::new(),
+                    meaning: String::new(),
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn weights_set_each_lesson_share() {
+        let prompts = |piles, size| {
+            interleave(piles, size)
+                .into_iter()
+                .map(|card| card.front.kanji)
+                .collect::<String>()
+        };
+        assert_eq!(
+            prompts(vec![(2, cards("a", 10)), (1, cards("b", 10))], 6),
+            "abaaba"
+        );
+        assert_eq!(
+            prompts(vec![(3, cards("a", 1)), (1, cards("b", 3))], 6),
+            "abbb"
+        );
+    }
+}
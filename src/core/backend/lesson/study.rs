@@ -0,0 +1,147 @@
+use crate::core::backend::lesson::select_cards;
+use crate::core::backend::schedule::Day;
+use crate::core::backend::tag::TAGGED;
+use crate::core::backend::StorageError;
+use crate::core::data::card::Card;
+use crate::core::data::study::StudyFilter;
+use rusqlite::{named_params, Connection};
+
+/// Cards for a custom study session, whatever their schedule.
+pub struct QueryStudyCards {
+    pub user_id: String,
+    pub day: Day,
+    pub filter: StudyFilter,
+}
+
+impl QueryStudyCards {
+    pub fn apply(self, db: &Connection) -> Result<Vec<Card>, StorageError> {
+        let sql = format!(
+            r#"
+SELECT phrases.id, phrases.prompt, phrases.reading, phrases.translation
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE lessons.creator_id = :user_id
+  AND phrases.suspended = 0
+  AND (:lesson_id IS NULL OR phrases.lesson_id = :lesson_id)
+  AND (:chapter IS NULL OR phrases.chapter = :chapter)
+  AND (:leeches_only = 0 OR phrases.is_leech = 1)
+  AND (:failed_today = 0 OR EXISTS (SELECT 1
+                                    FROM lapses
+                                    WHERE lapses.phrase_id = phrases.id
+                                      AND lapses.lapsed_at >= :day_start))
+  AND (:added_since IS NULL OR phrases.content_changed_at >= :added_since)
+  AND {TAGGED}
+ORDER BY RANDOM()
+LIMIT :limit
+"#
+        );
+        let Self {
+            user_id,
+            day,
+            filter,
+        } = self;
+        let added_since = filter
+            .added_within_days
+            .map(|days| day.start - days.saturating_sub(1) as f64);
+        let params = named_params! {
+            ":user_id": user_id,
+            ":lesson_id": filter.lesson_id,
+            ":chapter": filter.chapter,
+            ":leeches_only": filter.leeches_only,
+            ":failed_today": filter.failed_today,
+            ":day_start": day.start,
+            ":added_since": added_since,
+            ":tag": filter.tag,
+            ":limit": filter.limit,
+        };
+        select_cards(db, &sql, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryStudyCards;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::connect;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::phrase::RecordLapse;
+    use crate::core::backend::schedule::Day;
+    use crate::core::data::study::StudyFilter;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn filters_ignore_the_schedule() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        // 2024-01-01 10:00 local.
+        let day = Day::new(2460310.5 + 10.0 / 24.0, 3);
+        let lesson_id = InsertLesson {
+            title: "Study".to_string(),
+            owner: "admin".to_string(),
+            phrases: ["一", "二", "三", "四"]
+                .into_iter()
+                .enumerate()
+                .map(|(n, prompt)| NewPhrase {
+                    prompt: prompt.to_string(),
+                    chapter: Some(n / 2 + 1),
+                    content_changed_at: Some(if n == 3 { day.now } else { day.start - 7.0 }),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        for now in [day.start - 1.0, day.now] {
+            RecordLapse {
+                phrase_id: 1,
+                leech_threshold: 8,
+                now,
+            }
+            .apply(&conn)
+            .unwrap();
+        }
+        RecordLapse {
+            phrase_id: 2,
+            leech_threshold: 8,
+            now: day.start - 1.0,
+        }
+        .apply(&conn)
+        .unwrap();
+
+        let study = |filter: StudyFilter| {
+            let mut prompts = QueryStudyCards {
+                user_id: "admin".to_string(),
+                day,
+                filter,
+            }
+            .apply(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|card| card.front.kanji)
+            .collect::<Vec<_>>();
+            prompts.sort();
+            prompts.concat()
+        };
+        assert_eq!(study(StudyFilter::default()), "一三二四");
+        let in_lesson = StudyFilter {
+            lesson_id: Some(lesson_id),
+            chapter: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(study(in_lesson), "三四");
+        let failed_today = StudyFilter {
+            failed_today: true,
+            ..Default::default()
+        };
+        assert_eq!(study(failed_today), "一");
+        let added_today = StudyFilter {
+            added_within_days: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(study(added_today), "四");
+        let limited = StudyFilter {
+            limit: 3,
+            ..Default::default()
+        };
+        assert_eq!(study(limited).chars().count(), 3);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::core::backend::StorageError;
+
+const MIGRATIONS: &[&str] = &[
+    include_str!("migrations/0001_confusions.sql"),
+    include_str!("migrations/0002_leeches.sql"),
+    include_str!("migrations/0003_practice_limits.sql"),
+    include_str!("migrations/0004_time_zone.sql"),
+    include_str!("migrations/0005_simulated_date.sql"),
+    include_str!("migrations/0006_practice_sessions.sql"),
+    include_str!("migrations/0007_review_undo.sql"),
+    include_str!("migrations/0008_key_bindings.sql"),
+    include_str!("migrations/0009_session_history.sql"),
+    include_str!("migrations/0010_goals.sql"),
+    include_str!("migrations/0011_review_sessions.sql"),
+    include_str!("migrations/0012_custom_study.sql"),
+    include_str!("migrations/0013_tags.sql"),
+    include_str!("migrations/0014_search.sql"),
+    include_str!("migrations/0015_linked_phrases.sql"),
+    include_str!("migrations/0016_api_tokens.sql"),
+    include_str!("migrations/0017_phrase_source.sql"),
+    include_str!("migrations/0018_link_survivors.sql"),
+];
+
+/// The schema version this build migrates databases to.
+pub const LATEST_VERSION: usize = MIGRATIONS.len();
+
+/// The number of migrations applied to the database.
+pub fn version(conn: &rusqlite::Connection) -> Result<usize, StorageError> {
+    let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Applies every migration newer than the database's `user_version`.
+pub fn migrate(conn: &mut rusqlite::Connection) -> Result<(), StorageError> {
+    let version = version(conn)?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+CREATE TABLE confusions
+(
+    expected_id INTEGER NOT NULL,
+    chosen_id   INTEGER NOT NULL,
+    count       INTEGER NOT NULL DEFAULT 0,
+    last_at     REAL    NOT NULL DEFAULT (julianday('now', 'localtime')),
+    PRIMARY KEY (expected_id, chosen_id),
+    FOREIGN KEY (expected_id) REFERENCES phrases (id) ON DELETE CASCADE,
+    FOREIGN KEY (chosen_id) REFERENCES phrases (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_confusion_chosen ON confusions (chosen_id);
@@ -0,0 +1,11 @@
+ALTER TABLE phrases ADD COLUMN lapses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE phrases ADD COLUMN is_leech INTEGER NOT NULL DEFAULT 0 CHECK (is_leech IN (0, 1));
+ALTER TABLE phrases ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0 CHECK (suspended IN (0, 1));
+ALTER TABLE phrases ADD COLUMN buried_until REAL DEFAULT NULL;
+
+CREATE TABLE user_settings
+(
+    user_id         TEXT PRIMARY KEY,
+    leech_threshold INTEGER NOT NULL DEFAULT 8 CHECK (leech_threshold > 0),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
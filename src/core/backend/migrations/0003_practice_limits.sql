@@ -0,0 +1,23 @@
+ALTER TABLE user_settings ADD COLUMN session_size INTEGER NOT NULL DEFAULT 20 CHECK (session_size > 0);
+ALTER TABLE user_settings ADD COLUMN new_per_day INTEGER NOT NULL DEFAULT 20 CHECK (new_per_day >= 0);
+ALTER TABLE user_settings ADD COLUMN reviews_per_day INTEGER NOT NULL DEFAULT 200 CHECK (reviews_per_day >= 0);
+
+CREATE TABLE lesson_settings
+(
+    lesson_id       INTEGER PRIMARY KEY,
+    session_size    INTEGER DEFAULT NULL CHECK (session_size > 0),
+    new_per_day     INTEGER DEFAULT NULL CHECK (new_per_day >= 0),
+    reviews_per_day INTEGER DEFAULT NULL CHECK (reviews_per_day >= 0),
+    FOREIGN KEY (lesson_id) REFERENCES lessons (id) ON DELETE CASCADE
+);
+
+CREATE TABLE reviews
+(
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    phrase_id   INTEGER NOT NULL,
+    reviewed_at REAL    NOT NULL,
+    kind        TEXT    NOT NULL CHECK (kind IN ('new', 'review', 'practice')),
+    FOREIGN KEY (phrase_id) REFERENCES phrases (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_review_phrase ON reviews (phrase_id);
+CREATE INDEX idx_review_time ON reviews (reviewed_at);
@@ -0,0 +1,2 @@
+ALTER TABLE user_settings ADD COLUMN time_zone TEXT DEFAULT NULL;
+ALTER TABLE user_settings ADD COLUMN day_start_hour INTEGER NOT NULL DEFAULT 3 CHECK (day_start_hour BETWEEN 0 AND 23);
@@ -0,0 +1 @@
+ALTER TABLE user_settings ADD COLUMN clock_offset_days INTEGER NOT NULL DEFAULT 0;
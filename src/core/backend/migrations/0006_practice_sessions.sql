@@ -0,0 +1,13 @@
+CREATE TABLE practice_sessions
+(
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id     TEXT    NOT NULL,
+    lesson_id   INTEGER NOT NULL,
+    deck        TEXT    NOT NULL,
+    started_at  REAL    NOT NULL,
+    updated_at  REAL    NOT NULL,
+    finished_at REAL             DEFAULT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (lesson_id) REFERENCES lessons (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_practice_session_user ON practice_sessions (user_id, lesson_id);
@@ -0,0 +1 @@
+ALTER TABLE reviews ADD COLUMN previous_learned_at REAL DEFAULT NULL;
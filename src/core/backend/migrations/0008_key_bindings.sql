@@ -0,0 +1,9 @@
+ALTER TABLE user_settings ADD COLUMN key_flip TEXT NOT NULL DEFAULT 'Space';
+ALTER TABLE user_settings ADD COLUMN key_learn TEXT NOT NULL DEFAULT 'l';
+ALTER TABLE user_settings ADD COLUMN key_fail TEXT NOT NULL DEFAULT '1';
+ALTER TABLE user_settings ADD COLUMN key_repeat TEXT NOT NULL DEFAULT '2';
+ALTER TABLE user_settings ADD COLUMN key_pass TEXT NOT NULL DEFAULT '3';
+ALTER TABLE user_settings ADD COLUMN key_bury TEXT NOT NULL DEFAULT 'b';
+ALTER TABLE user_settings ADD COLUMN key_suspend TEXT NOT NULL DEFAULT 's';
+ALTER TABLE user_settings ADD COLUMN key_undo TEXT NOT NULL DEFAULT 'u';
+ALTER TABLE user_settings ADD COLUMN key_close TEXT NOT NULL DEFAULT 'Escape';
@@ -0,0 +1,6 @@
+ALTER TABLE practice_sessions ADD COLUMN learned INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE practice_sessions ADD COLUMN failed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE practice_sessions ADD COLUMN repeated INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE practice_sessions ADD COLUMN passed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE practice_sessions ADD COLUMN active_seconds REAL NOT NULL DEFAULT 0;
+CREATE INDEX idx_practice_session_finished ON practice_sessions (user_id, finished_at);
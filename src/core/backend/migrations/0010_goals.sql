@@ -0,0 +1,3 @@
+ALTER TABLE user_settings ADD COLUMN goal_kind TEXT NOT NULL DEFAULT 'cards' CHECK (goal_kind IN ('cards', 'minutes'));
+ALTER TABLE user_settings ADD COLUMN goal_amount INTEGER NOT NULL DEFAULT 20 CHECK (goal_amount > 0);
+ALTER TABLE user_settings ADD COLUMN streak_freezes INTEGER NOT NULL DEFAULT 1 CHECK (streak_freezes >= 0);
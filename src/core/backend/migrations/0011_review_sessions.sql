@@ -0,0 +1,29 @@
+ALTER TABLE lesson_settings ADD COLUMN review_weight INTEGER DEFAULT NULL CHECK (review_weight >= 0);
+
+-- Sessions that review every lesson have no lesson_id.
+CREATE TABLE practice_sessions_new
+(
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id        TEXT    NOT NULL,
+    lesson_id      INTEGER          DEFAULT NULL,
+    deck           TEXT    NOT NULL,
+    started_at     REAL    NOT NULL,
+    updated_at     REAL    NOT NULL,
+    finished_at    REAL             DEFAULT NULL,
+    learned        INTEGER NOT NULL DEFAULT 0,
+    failed         INTEGER NOT NULL DEFAULT 0,
+    repeated       INTEGER NOT NULL DEFAULT 0,
+    passed         INTEGER NOT NULL DEFAULT 0,
+    active_seconds REAL    NOT NULL DEFAULT 0,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (lesson_id) REFERENCES lessons (id) ON DELETE CASCADE
+);
+INSERT INTO practice_sessions_new (id, user_id, lesson_id, deck, started_at, updated_at, finished_at,
+                                   learned, failed, repeated, passed, active_seconds)
+SELECT id, user_id, lesson_id, deck, started_at, updated_at, finished_at,
+       learned, failed, repeated, passed, active_seconds
+FROM practice_sessions;
+DROP TABLE practice_sessions;
+ALTER TABLE practice_sessions_new RENAME TO practice_sessions;
+CREATE INDEX idx_practice_session_user ON practice_sessions (user_id, lesson_id);
+CREATE INDEX idx_practice_session_finished ON practice_sessions (user_id, finished_at);
@@ -0,0 +1,13 @@
+ALTER TABLE phrases ADD COLUMN chapter INTEGER DEFAULT NULL;
+
+CREATE TABLE lapses
+(
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    phrase_id INTEGER NOT NULL,
+    lapsed_at REAL    NOT NULL,
+    FOREIGN KEY (phrase_id) REFERENCES phrases (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_lapse_phrase ON lapses (phrase_id, lapsed_at);
+
+-- Cram sessions drill phrases without touching their schedule.
+ALTER TABLE practice_sessions ADD COLUMN cram INTEGER NOT NULL DEFAULT 0 CHECK (cram IN (0, 1));
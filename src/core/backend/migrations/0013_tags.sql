@@ -0,0 +1,18 @@
+CREATE TABLE tags
+(
+    id      INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL,
+    name    TEXT NOT NULL,
+    UNIQUE (user_id, name),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE phrase_tags
+(
+    phrase_id INTEGER NOT NULL,
+    tag_id    INTEGER NOT NULL,
+    PRIMARY KEY (phrase_id, tag_id),
+    FOREIGN KEY (phrase_id) REFERENCES phrases (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_phrase_tag_tag ON phrase_tags (tag_id);
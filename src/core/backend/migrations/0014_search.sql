@@ -0,0 +1,41 @@
+ALTER TABLE phrases ADD COLUMN sentence TEXT NOT NULL DEFAULT '';
+
+-- Trigram tokens let kanji and kana match inside words, which have no spaces to split on.
+CREATE VIRTUAL TABLE phrase_search USING fts5
+(
+    prompt,
+    reading,
+    translation,
+    sentence,
+    content = 'phrases',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+CREATE TRIGGER phrase_search_insert
+    AFTER INSERT
+    ON phrases
+BEGIN
+    INSERT INTO phrase_search (rowid, prompt, reading, translation, sentence)
+    VALUES (new.id, new.prompt, new.reading, new.translation, new.sentence);
+END;
+
+CREATE TRIGGER phrase_search_delete
+    AFTER DELETE
+    ON phrases
+BEGIN
+    INSERT INTO phrase_search (phrase_search, rowid, prompt, reading, translation, sentence)
+    VALUES ('delete', old.id, old.prompt, old.reading, old.translation, old.sentence);
+END;
+
+CREATE TRIGGER phrase_search_update
+    AFTER UPDATE OF prompt, reading, translation, sentence
+    ON phrases
+BEGIN
+    INSERT INTO phrase_search (phrase_search, rowid, prompt, reading, translation, sentence)
+    VALUES ('delete', old.id, old.prompt, old.reading, old.translation, old.sentence);
+    INSERT INTO phrase_search (rowid, prompt, reading, translation, sentence)
+    VALUES (new.id, new.prompt, new.reading, new.translation, new.sentence);
+END;
+
+INSERT INTO phrase_search (phrase_search) VALUES ('rebuild');
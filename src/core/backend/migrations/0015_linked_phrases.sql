@@ -0,0 +1,4 @@
+-- Phrases sharing a link_id are the same word imported into several lessons and share review state.
+ALTER TABLE phrases ADD COLUMN link_id INTEGER DEFAULT NULL REFERENCES phrases (id) ON DELETE SET NULL;
+CREATE INDEX idx_phrase_link ON phrases (link_id);
+CREATE INDEX idx_phrase_prompt ON phrases (prompt, reading);
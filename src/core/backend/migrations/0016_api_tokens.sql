@@ -0,0 +1,11 @@
+-- Bearer tokens for the REST API. Only a SHA-256 hash of each token is kept.
+CREATE TABLE api_tokens
+(
+    id           INTEGER PRIMARY KEY,
+    user_id      TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name         TEXT NOT NULL,
+    token_hash   TEXT NOT NULL UNIQUE,
+    created_at   REAL NOT NULL DEFAULT (julianday('now')),
+    last_used_at REAL
+);
+CREATE INDEX idx_api_token_user ON api_tokens (user_id);
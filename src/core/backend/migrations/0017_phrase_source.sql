@@ -0,0 +1,2 @@
+-- The page a quick-added phrase was found on.
+ALTER TABLE phrases ADD COLUMN source_url TEXT DEFAULT NULL;
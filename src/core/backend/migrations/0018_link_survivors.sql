@@ -0,0 +1,15 @@
+-- Deleting the phrase a link group is keyed on re-keys the group on its lowest remaining member,
+-- before the link_id foreign key sets the survivors' link_id to NULL. A lone survivor is unlinked.
+CREATE TRIGGER phrase_link_survives
+    BEFORE DELETE
+    ON phrases
+    WHEN OLD.link_id = OLD.id
+BEGIN
+    UPDATE phrases
+    SET link_id = CASE
+                      WHEN (SELECT COUNT(*) FROM phrases WHERE link_id = OLD.id AND id != OLD.id) > 1
+                          THEN (SELECT MIN(id) FROM phrases WHERE link_id = OLD.id AND id != OLD.id)
+        END
+    WHERE link_id = OLD.id
+      AND id != OLD.id;
+END;
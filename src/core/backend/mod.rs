@@ -1,10 +1,32 @@
+use crate::core::backend::config::Config;
+use crate::core::data::settings::UserSettings;
+use crate::core::data::tag::split_tags;
 use crate::core::data::{Lesson, Phrase, User};
 use rusqlite::params;
 use std::path::Path;
 use thiserror::Error;
 
+pub mod account;
+pub mod backup;
+pub mod clock;
+pub mod config;
+pub mod confusion;
+pub mod dictionary;
+pub mod export;
+pub mod history;
+pub mod import;
 pub mod lesson;
-pub mod misc;
+pub mod migrate;
+pub mod phrase;
+pub mod pool;
+pub mod practice_session;
+pub mod progress;
+pub mod quick_add;
+pub mod schedule;
+pub mod search;
+pub mod settings;
+pub mod tag;
+pub mod token;
 
 pub mod insert_lesson;
 
@@ -12,50 +34,112 @@ pub mod insert_lesson;
 pub enum StorageError {
     #[error("Database error: {0}")]
     Sqlite(#[from] rusqlite::Error),
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("Unknown time zone: {0}")]
+    TimeZone(String),
+    #[error("Invalid date: {0}")]
+    Date(String),
+    #[error("Key {0} is bound to more than one command")]
+    DuplicateKey(String),
+    #[error("Only admins may {0}")]
+    AdminOnly(&'static str),
+    #[error("The sheet has {rows} rows, more than the {limit} an import may have")]
+    TooManyRows { rows: usize, limit: usize },
+    #[error("Failed to read the sheet: {0}")]
+    Import(String),
+    #[error("Backup {0}")]
+    Backup(String),
+    #[error("Failed to read the dictionary {0}")]
+    Dictionary(String),
+    #[error("Missing or unknown API token")]
+    Unauthorized,
+    #[error("Unsupported account archive: {0}")]
+    Archive(String),
+    #[error("{0} is the configured admin and can't be deleted")]
+    DeleteAdmin(String),
+    #[error("A database task failed: {0}")]
+    Task(String),
 }
 
-pub fn connect(filename: Option<&'static str>) -> rusqlite::Connection {
-    let (conn, existed) = if let Some(filename) = filename {
-        let db_existed = Path::new(filename).exists();
-        let conn = rusqlite::Connection::open(filename).expect("Failed to open the database");
-        (conn, db_existed)
-    } else {
-        let conn =
-            rusqlite::Connection::open_in_memory().expect("Failed to open an in-memory database");
-        (conn, false)
-    };
-    conn.pragma_update(None, "foreign_keys", "ON")
-        .expect("Failed to enable foreign keys");
+/// Opens the configured database, creating and migrating it as needed, with the admin in place.
+pub fn connect(config: &Config) -> Result<rusqlite::Connection, StorageError> {
+    let existed = Path::new(&config.database).exists();
+    let mut conn = rusqlite::Connection::open(&config.database)?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
 
-    let admin = User {
-        id: "admin".to_string(),
-        is_admin: true,
-    };
     if !existed {
         let up = include_str!("up.sql");
-        conn.execute_batch(up)
-            .expect("Failed to run database upgrade");
-        const SQL: &str = "INSERT INTO users (id, is_admin) VALUES (?1, ?2)";
-        conn.execute(SQL, (&admin.id, &admin.is_admin))
-            .expect("Failed to create admin user");
+        conn.execute_batch(up)?;
+    }
+    migrate::migrate(&mut conn)?;
+    CreateUser {
+        user_id: config.admin.clone(),
+        is_admin: true,
+        settings: config.user_defaults(),
+    }
+    .apply(&conn)?;
+    Ok(conn)
+}
+
+/// Adds a user with starting settings, leaving an existing user as they are.
+pub struct CreateUser {
+    pub user_id: String,
+    pub is_admin: bool,
+    pub settings: UserSettings,
+}
+
+impl CreateUser {
+    pub fn apply(self, conn: &rusqlite::Connection) -> Result<(), StorageError> {
+        const SQL: &str = "INSERT OR IGNORE INTO users (id, is_admin) VALUES (?1, ?2)";
+        const SETTINGS_SQL: &str = r#"
+INSERT OR IGNORE INTO user_settings (user_id, session_size, day_start_hour)
+VALUES (?1, ?2, ?3)
+"#;
+        conn.execute(SQL, params![self.user_id, self.is_admin])?;
+        conn.execute(
+            SETTINGS_SQL,
+            params![
+                self.user_id,
+                self.settings.session_size,
+                self.settings.day_start_hour
+            ],
+        )?;
+        Ok(())
     }
-    conn
 }
 
 pub fn read_phrases(
     lesson_id: i64,
     conn: &rusqlite::Connection,
 ) -> Result<Vec<Phrase>, StorageError> {
-    const SQL: &str = "SELECT id, prompt, reading, translation FROM phrases WHERE lesson_id = ?1";
+    const SQL: &str = r#"
+SELECT id, prompt, reading, translation, lapses, is_leech, suspended, buried_until,
+       (SELECT GROUP_CONCAT(name, ' ')
+        FROM (SELECT tags.name
+              FROM phrase_tags
+                       JOIN tags ON tags.id = phrase_tags.tag_id
+              WHERE phrase_tags.phrase_id = phrases.id
+              ORDER BY tags.name))
+FROM phrases
+WHERE lesson_id = ?1
+"#;
     let mut stmt = conn.prepare(SQL)?;
     let phrases = stmt
         .query_map(params![lesson_id], |row| {
+            let lapses: i64 = row.get(4)?;
+            let tags: Option<String> = row.get(8)?;
             Ok(Phrase {
                 phrase_id: row.get(0)?,
                 lesson_id,
                 prompt: row.get(1)?,
                 reading: row.get(2)?,
                 translation: row.get(3)?,
+                lapses: lapses as usize,
+                is_leech: row.get(5)?,
+                suspended: row.get(6)?,
+                buried_until: row.get(7)?,
+                tags: tags.map(|tags| split_tags(&tags)).unwrap_or_default(),
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -66,9 +150,17 @@ pub fn read_user_lesson(
     user: &str,
     conn: &rusqlite::Connection,
 ) -> Result<Option<Lesson>, StorageError> {
-    const SQL: &str = "SELECT id, title, creator_id FROM lessons WHERE creator_id = ?1";
+    let lesson = read_user_lessons(user, conn)?.into_iter().next();
+    Ok(lesson)
+}
+
+pub fn read_user_lessons(
+    user: &str,
+    conn: &rusqlite::Connection,
+) -> Result<Vec<Lesson>, StorageError> {
+    const SQL: &str = "SELECT id, title, creator_id FROM lessons WHERE creator_id = ?1 ORDER BY id";
     let mut stmt = conn.prepare(SQL)?;
-    let lesson = stmt
+    let lessons = stmt
         .query_map(params![user], |row| {
             let lesson_id = row.get(0)?;
             let title = row.get(1)?;
@@ -79,50 +171,64 @@ pub fn read_user_lesson(
                 creator_id,
             })
         })?
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .next();
-    Ok(lesson)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lessons)
 }
 
-pub fn get_users(conn: &rusqlite::Connection) -> Vec<User> {
-    let mut stmt = conn.prepare("SELECT id, is_admin FROM users").unwrap();
-    let user_iter = stmt
+pub fn get_users(conn: &rusqlite::Connection) -> Result<Vec<User>, StorageError> {
+    let mut stmt = conn.prepare("SELECT id, is_admin FROM users")?;
+    let users = stmt
         .query_map([], |row| {
-            let id = row.get(0).unwrap();
-            let is_admin = row.get(1).unwrap();
+            let id = row.get(0)?;
+            let is_admin = row.get(1)?;
             Ok(User { id, is_admin })
-        })
-        .expect("Failed to fetch users");
-    user_iter.collect::<Result<Vec<_>, _>>().unwrap()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(users)
+}
+
+/// Fails with `AdminOnly` unless the user is an admin.
+pub fn require_admin(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    action: &'static str,
+) -> Result<(), StorageError> {
+    let is_admin = get_users(conn)?
+        .iter()
+        .any(|user| user.id == user_id && user.is_admin);
+    if !is_admin {
+        return Err(StorageError::AdminOnly(action));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
     use crate::core::backend::insert_lesson::{InsertLesson, UpdateLessonTimes};
     use crate::core::backend::lesson::{QueryLessonStatus, QueryPracticeCards};
-    use crate::core::backend::{connect, get_users, read_phrases, read_user_lesson};
+    use crate::core::backend::schedule::{local_now, Day};
+    use crate::core::backend::{
+        connect, get_users, read_phrases, read_user_lesson, require_admin, CreateUser, StorageError,
+    };
     use crate::core::data::lesson_status::LessonStatus;
+    use crate::core::data::settings::PracticeLimits;
     use crate::core::data::NewPhrase;
 
-    pub fn today_at_3am(db: &rusqlite::Connection) -> Result<f64, rusqlite::Error> {
-        db.query_row(
-            "SELECT julianday('now','localtime', 'start of day', '+3 hours')",
-            [],
-            |row| Ok(row.get(0)?),
-        )
-    }
-
     #[test]
     fn it_works() {
-        let mut conn = connect(None);
+        let mut conn = connect(&Config::in_memory()).unwrap();
         let admin = {
-            let users = get_users(&conn);
+            let users = get_users(&conn).unwrap();
             assert_eq!(users.len(), 1);
             users.into_iter().next().unwrap()
         };
 
-        let today_at_3am = today_at_3am(&conn).unwrap();
+        let clock = FixedClock {
+            at: "2024-01-01T10:00:00Z".parse().unwrap(),
+        };
+        let today_at_3am = Day::new(local_now(&clock, Some("UTC")).unwrap(), 3).start;
         InsertLesson {
             title: "Aggrieved Ch1".to_string(),
             owner: admin.id.clone(),
@@ -131,13 +237,21 @@ mod tests {
                     prompt: "嫌".to_string(),
                     reading: "いや".to_string(),
                     translation: "unpleasant".to_string(),
+                    chapter: Some(1),
+                    sentence: String::new(),
                     content_changed_at: Some(today_at_3am - 0.1),
+                    tags: Vec::new(),
+                    source_url: None,
                 },
                 NewPhrase {
                     prompt: "必要".to_string(),
                     reading: "ひつよう".to_string(),
                     translation: "necessary".to_string(),
+                    chapter: Some(1),
+                    sentence: String::new(),
                     content_changed_at: Some(today_at_3am - 0.1),
+                    tags: Vec::new(),
+                    source_url: None,
                 },
             ],
         }
@@ -155,7 +269,9 @@ mod tests {
         {
             let lesson_status = QueryLessonStatus {
                 lesson_id: lesson.lesson_id,
-                now,
+                day: Day::new(now, 3),
+                limits: PracticeLimits::default(),
+                tag: None,
             }
             .apply(&conn)
             .expect("Failed to fetch lesson status");
@@ -164,12 +280,15 @@ mod tests {
                 LessonStatus {
                     lesson_id: lesson.lesson_id,
                     ready: 2,
-                    learned: 0
+                    learned: 0,
+                    session_size: 20,
                 }
             );
             let practice_cards = QueryPracticeCards {
                 lesson_id: lesson.lesson_id,
-                now,
+                day: Day::new(now, 3),
+                limits: PracticeLimits::default(),
+                tag: None,
             }
             .apply(&conn)
             .expect("Failed to fetch practice cards");
@@ -179,21 +298,24 @@ mod tests {
         {
             UpdateLessonTimes {
                 phrase_ids: vec![phrase_ids[0]],
-                now,
+                day: Day::new(now, 3),
             }
             .apply(&mut conn)
             .expect("Failed to update lesson time");
             assert_eq!(
                 QueryLessonStatus {
                     lesson_id: lesson.lesson_id,
-                    now,
+                    day: Day::new(now, 3),
+                    limits: PracticeLimits::default(),
+                    tag: None,
                 }
                 .apply(&conn)
                 .expect("Failed to fetch lesson status"),
                 LessonStatus {
                     lesson_id: lesson.lesson_id,
                     ready: 1,
-                    learned: 1
+                    learned: 1,
+                    session_size: 20,
                 }
             );
         }
@@ -201,21 +323,24 @@ mod tests {
         {
             UpdateLessonTimes {
                 phrase_ids: vec![phrase_ids[1]],
-                now,
+                day: Day::new(now, 3),
             }
             .apply(&mut conn)
             .expect("Failed to update lesson time");
             assert_eq!(
                 QueryLessonStatus {
                     lesson_id: lesson.lesson_id,
-                    now,
+                    day: Day::new(now, 3),
+                    limits: PracticeLimits::default(),
+                    tag: None,
                 }
                 .apply(&conn)
                 .expect("Failed to fetch lesson status"),
                 LessonStatus {
                     lesson_id: lesson.lesson_id,
                     ready: 0,
-                    learned: 2
+                    learned: 2,
+                    session_size: 20,
                 }
             );
         }
@@ -224,7 +349,9 @@ mod tests {
             assert_eq!(
                 QueryLessonStatus {
                     lesson_id: lesson.lesson_id,
-                    now,
+                    day: Day::new(now, 3),
+                    limits: PracticeLimits::default(),
+                    tag: None,
                 }
                 .apply(&conn)
                 .expect("Failed to fetch lesson status"),
@@ -232,8 +359,28 @@ mod tests {
                     lesson_id: lesson.lesson_id,
                     ready: 2,
                     learned: 0,
+                    session_size: 20,
                 }
             );
         }
     }
+
+    #[test]
+    fn only_admins_pass() {
+        let conn = connect(&Config::in_memory()).unwrap();
+        CreateUser {
+            user_id: "kaito".to_string(),
+            is_admin: false,
+            settings: Default::default(),
+        }
+        .apply(&conn)
+        .unwrap();
+        require_admin(&conn, "admin", "restore backups").unwrap();
+        for user_id in ["kaito", "nobody"] {
+            assert!(matches!(
+                require_admin(&conn, user_id, "restore backups"),
+                Err(StorageError::AdminOnly(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,267 @@
+use crate::core::backend::import::sync_linked;
+use crate::core::backend::insert_lesson::UpdateLessonTimes;
+use crate::core::backend::schedule::Day;
+use crate::core::backend::StorageError;
+use crate::core::data::deck::Answer;
+use rusqlite::{params, Connection};
+
+pub struct RecordLapse {
+    pub phrase_id: i64,
+    pub leech_threshold: usize,
+    pub now: f64,
+}
+
+impl RecordLapse {
+    /// Counts a failed answer and returns whether the phrase is now a leech.
+    pub fn apply(self, db: &Connection) -> Result<bool, StorageError> {
+        db.execute(
+            "INSERT INTO lapses (phrase_id, lapsed_at) VALUES (?1, ?2)",
+            params![self.phrase_id, self.now],
+        )?;
+        const SQL: &str = r#"
+UPDATE phrases
+SET lapses   = lapses + 1,
+    is_leech = CASE WHEN lapses + 1 >= ?2 THEN 1 ELSE is_leech END
+WHERE id = ?1
+RETURNING is_leech
+"#;
+        let is_leech = db.query_row(SQL, params![self.phrase_id, self.leech_threshold], |row| {
+            row.get(0)
+        })?;
+        sync_linked(db, self.phrase_id)?;
+        Ok(is_leech)
+    }
+}
+
+/// Takes back a lapse recorded by `RecordLapse`.
+pub struct RevertLapse {
+    pub phrase_id: i64,
+    pub leech_threshold: usize,
+}
+
+impl RevertLapse {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+UPDATE phrases
+SET lapses   = MAX(lapses - 1, 0),
+    is_leech = CASE WHEN lapses = ?2 THEN 0 ELSE is_leech END
+WHERE id = ?1
+"#;
+        db.execute(SQL, params![self.phrase_id, self.leech_threshold])?;
+        sync_linked(db, self.phrase_id)?;
+        const LOG_SQL: &str = r#"
+DELETE FROM lapses
+WHERE id = (SELECT id FROM lapses WHERE phrase_id = ?1 ORDER BY lapsed_at DESC, id DESC LIMIT 1)
+"#;
+        db.execute(LOG_SQL, params![self.phrase_id])?;
+        Ok(())
+    }
+}
+
+pub struct SuspendPhrase {
+    pub phrase_id: i64,
+    pub suspended: bool,
+}
+
+impl SuspendPhrase {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = "UPDATE phrases SET suspended = ?2 WHERE id = ?1";
+        if db.execute(SQL, params![self.phrase_id, self.suspended])? == 0 {
+            return Err(StorageError::NotFound("Phrase"));
+        }
+        sync_linked(db, self.phrase_id)
+    }
+}
+
+pub struct BuryPhrase {
+    pub phrase_id: i64,
+    pub day: Day,
+    pub buried: bool,
+}
+
+impl BuryPhrase {
+    /// Hides the phrase until the next day starts, or brings it back right away.
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const BURY: &str = "UPDATE phrases SET buried_until = ?2 WHERE id = ?1";
+        const UNBURY: &str = "UPDATE phrases SET buried_until = NULL WHERE id = ?1";
+        let updated = if self.buried {
+            db.execute(BURY, params![self.phrase_id, self.day.next_start()])?
+        } else {
+            db.execute(UNBURY, params![self.phrase_id])?
+        };
+        if updated == 0 {
+            return Err(StorageError::NotFound("Phrase"));
+        }
+        sync_linked(db, self.phrase_id)
+    }
+}
+
+/// Writes an answer to a phrase's card as practice does. Learning and repeating write nothing.
+pub struct RecordAnswer {
+    pub phrase_id: i64,
+    pub answer: Answer,
+    pub day: Day,
+    pub leech_threshold: usize,
+}
+
+impl RecordAnswer {
+    pub fn apply(self, db: &mut Connection) -> Result<(), StorageError> {
+        match self.answer {
+            Answer::Pass => UpdateLessonTimes {
+                phrase_ids: vec![self.phrase_id],
+                day: self.day,
+            }
+            .apply(db),
+            Answer::Fail => RecordLapse {
+                phrase_id: self.phrase_id,
+                leech_threshold: self.leech_threshold,
+                now: self.day.now,
+            }
+            .apply(db)
+            .map(|_| ()),
+            Answer::Bury => BuryPhrase {
+                phrase_id: self.phrase_id,
+                day: self.day,
+                buried: true,
+            }
+            .apply(db),
+            Answer::Suspend => SuspendPhrase {
+                phrase_id: self.phrase_id,
+                suspended: true,
+            }
+            .apply(db),
+            Answer::Learn | Answer::Repeat => Ok(()),
+        }
+    }
+}
+
+/// Fails with `NotFound` unless the phrase is in one of the user's lessons.
+pub fn check_phrase_owner(
+    db: &Connection,
+    user_id: &str,
+    phrase_id: i64,
+) -> Result<(), StorageError> {
+    const SQL: &str = r#"
+SELECT COUNT(*)
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE phrases.id = ?1
+  AND lessons.creator_id = ?2
+"#;
+    let count: usize = db.query_row(SQL, params![phrase_id, user_id], |row| row.get(0))?;
+    if count == 0 {
+        return Err(StorageError::NotFound("Phrase"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuryPhrase, RecordLapse, RevertLapse, SuspendPhrase};
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::lesson::{QueryLessonStatus, QueryPracticeCards};
+    use crate::core::backend::schedule::{local_now, Day};
+    use crate::core::backend::{connect, read_phrases, StorageError};
+    use crate::core::data::settings::PracticeLimits;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn leeches_suspended_and_buried_phrases() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Leeches".to_string(),
+            owner: "admin".to_string(),
+            phrases: ["嫌", "必要", "意外"]
+                .into_iter()
+                .map(|prompt| NewPhrase {
+                    prompt: prompt.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let ids = read_phrases(lesson_id, &conn)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.phrase_id)
+            .collect::<Vec<_>>();
+        let clock = FixedClock {
+            at: "2024-01-01T10:00:00Z".parse().unwrap(),
+        };
+        let day = Day::new(local_now(&clock, Some("UTC")).unwrap(), 3);
+
+        let lapse = |conn: &rusqlite::Connection| {
+            RecordLapse {
+                phrase_id: ids[0],
+                leech_threshold: 2,
+                now: day.now,
+            }
+            .apply(conn)
+            .unwrap()
+        };
+        assert!(!lapse(&conn));
+        assert!(lapse(&conn));
+        RevertLapse {
+            phrase_id: ids[0],
+            leech_threshold: 2,
+        }
+        .apply(&conn)
+        .unwrap();
+        let phrase = &read_phrases(lesson_id, &conn).unwrap()[0];
+        assert_eq!((phrase.lapses, phrase.is_leech), (1, false));
+        assert!(lapse(&conn));
+
+        SuspendPhrase {
+            phrase_id: ids[1],
+            suspended: true,
+        }
+        .apply(&conn)
+        .unwrap();
+        BuryPhrase {
+            phrase_id: ids[2],
+            day,
+            buried: true,
+        }
+        .apply(&conn)
+        .unwrap();
+        let cards = QueryPracticeCards {
+            lesson_id,
+            day,
+            limits: PracticeLimits::default(),
+            tag: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(cards.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[0]]);
+        let status = QueryLessonStatus {
+            lesson_id,
+            day,
+            limits: PracticeLimits::default(),
+            tag: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(status.ready, 1);
+
+        let tomorrow = Day::new(day.now + 1.0, 3);
+        let cards = QueryPracticeCards {
+            lesson_id,
+            day: tomorrow,
+            limits: PracticeLimits::default(),
+            tag: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(cards.len(), 2);
+
+        let missing = SuspendPhrase {
+            phrase_id: -1,
+            suspended: true,
+        }
+        .apply(&conn);
+        assert!(matches!(missing, Err(StorageError::NotFound("Phrase"))));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::core::backend::config::Config;
+use crate::core::backend::{connect, StorageError};
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// How long a connection waits on another connection's lock before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One writer and a set of read-only connections to a database in WAL mode, so reads run
+/// alongside each other and alongside the writer instead of queueing behind it.
+pub struct Pool {
+    writer: Mutex<Connection>,
+    readers: Mutex<Vec<Connection>>,
+    returned: Condvar,
+}
+
+impl Pool {
+    /// Opens, creates or migrates the configured database with `readers` read-only connections.
+    pub fn open(config: &Config, readers: usize) -> Result<Self, StorageError> {
+        let writer = connect(config)?;
+        writer.pragma_update(None, "journal_mode", "WAL")?;
+        writer.busy_timeout(BUSY_TIMEOUT)?;
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let readers = (0..readers.max(1))
+            .map(|_| {
+                let reader = Connection::open_with_flags(&config.database, flags)?;
+                reader.busy_timeout(BUSY_TIMEOUT)?;
+                Ok(reader)
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        Ok(Self {
+            writer: Mutex::new(writer),
+            readers: Mutex::new(readers),
+            returned: Condvar::new(),
+        })
+    }
+
+    pub fn path(&self) -> Option<String> {
+        self.writer().path().map(str::to_string)
+    }
+
+    /// Runs read-only queries on the blocking thread pool with the next free reader.
+    pub async fn read<T, F>(&'static self, query: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, StorageError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let reader = self.take_reader();
+            query(&reader)
+        })
+        .await
+        .map_err(|err| StorageError::Task(err.to_string()))?
+    }
+
+    /// Runs commands on the blocking thread pool with the one writer.
+    pub async fn write<T, F>(&'static self, command: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, StorageError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let mut writer = self.writer();
+            command(&mut writer)
+        })
+        .await
+        .map_err(|err| StorageError::Task(err.to_string()))?
+    }
+
+    /// Locks the writer, taking it back after a command that panicked: SQLite rolls back the
+    /// transaction it left open, so the connection is still sound.
+    fn writer(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take_reader(&self) -> Reader<'_> {
+        let readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut readers = self
+            .returned
+            .wait_while(readers, |readers| readers.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+        let conn = readers.pop().expect("A reader was returned");
+        Reader {
+            pool: self,
+            conn: Some(conn),
+        }
+    }
+}
+
+/// A reader taken from the pool, given back when dropped even if its query panics.
+struct Reader<'a> {
+    pool: &'a Pool,
+    conn: Option<Connection>,
+}
+
+impl Deref for Reader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("The reader is still taken")
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut readers = self
+                .pool
+                .readers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            readers.push(conn);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pool;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::{read_user_lessons, StorageError};
+
+    #[test]
+    fn reads_see_committed_writes() {
+        let dir = std::env::temp_dir().join(format!("kanjikoto-pool-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = Config {
+            database: dir.join("pool.db").to_string_lossy().into_owned(),
+            ..Config::default()
+        };
+        let pool: &'static Pool = Box::leak(Box::new(Pool::open(&config, 2).unwrap()));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            pool.write(|db| {
+                db.execute(
+                    "INSERT INTO lessons (title, creator_id) VALUES ('Pooled', 'admin')",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+            for _ in 0..3 {
+                let lessons = pool.read(|db| read_user_lessons("admin", db)).await;
+                assert_eq!(lessons.unwrap()[0].title, "Pooled");
+            }
+            let denied = pool
+                .read(|db| {
+                    db.execute("DELETE FROM lessons", [])?;
+                    Ok(())
+                })
+                .await;
+            assert!(denied.is_err());
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_panicking_task_leaves_the_pool_usable() {
+        let dir = std::env::temp_dir().join(format!("kanjikoto-panic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = Config {
+            database: dir.join("pool.db").to_string_lossy().into_owned(),
+            ..Config::default()
+        };
+        let pool: &'static Pool = Box::leak(Box::new(Pool::open(&config, 1).unwrap()));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let panicked = pool
+                .write(|db| -> Result<(), StorageError> {
+                    let tx = db.transaction()?;
+                    tx.execute(
+                        "INSERT INTO lessons (title, creator_id) VALUES ('Lost', 'admin')",
+                        [],
+                    )?;
+                    panic!("the command failed halfway");
+                })
+                .await;
+            assert!(matches!(panicked, Err(StorageError::Task(_))));
+            let panicked = pool
+                .read(|_| -> Result<(), StorageError> { panic!("the query failed") })
+                .await;
+            assert!(matches!(panicked, Err(StorageError::Task(_))));
+            pool.write(|db| {
+                db.execute(
+                    "INSERT INTO lessons (title, creator_id) VALUES ('Kept', 'admin')",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+            let lessons = pool.read(|db| read_user_lessons("admin", db)).await;
+            let titles: Vec<_> = lessons.unwrap().into_iter().map(|l| l.title).collect();
+            assert_eq!(titles, ["Kept"]);
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+use crate::core::backend::StorageError;
+use rusqlite::{named_params, params, Connection, OptionalExtension};
+
+/// Gaps between answers count as time spent up to this many seconds.
+const MAX_ANSWER_SECONDS: f64 = 300.0;
+
+/// A practice session that has not been finished. The deck is stored as JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenSession {
+    pub session_id: i64,
+    pub deck: String,
+}
+
+/// Opens a session for a lesson, or for all lessons when `lesson_id` is `None`,
+/// abandoning any the user left unfinished for the same.
+pub struct StartSession {
+    pub user_id: String,
+    pub lesson_id: Option<i64>,
+    /// Whether the session drills phrases without scheduling them.
+    pub cram: bool,
+    pub deck: String,
+    pub now: f64,
+}
+
+impl StartSession {
+    pub fn apply(self, db: &mut Connection) -> Result<i64, StorageError> {
+        let tx = db.transaction()?;
+        tx.execute(
+            "DELETE FROM practice_sessions WHERE user_id = ?1 AND lesson_id IS ?2 AND cram = ?3 AND finished_at IS NULL",
+            params![self.user_id, self.lesson_id, self.cram],
+        )?;
+        tx.execute(
+            "INSERT INTO practice_sessions (user_id, lesson_id, cram, deck, started_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![self.user_id, self.lesson_id, self.cram, self.deck, self.now],
+        )?;
+        let session_id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(session_id)
+    }
+}
+
+/// Stores the user's session's deck, reopening the session if it was finished.
+pub struct SaveSession {
+    pub user_id: String,
+    pub session_id: i64,
+    pub deck: String,
+    pub now: f64,
+}
+
+impl SaveSession {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+UPDATE practice_sessions
+SET deck           = :deck,
+    active_seconds = active_seconds + MIN(MAX((:now - updated_at) * 86400, 0), :max_seconds),
+    updated_at     = :now,
+    finished_at    = NULL
+WHERE id = :id AND user_id = :user_id
+"#;
+        let updated = db.execute(
+            SQL,
+            named_params! {
+                ":id": self.session_id,
+                ":user_id": self.user_id,
+                ":deck": self.deck,
+                ":now": self.now,
+                ":max_seconds": MAX_ANSWER_SECONDS,
+            },
+        )?;
+        if updated == 0 {
+            return Err(StorageError::NotFound("Practice session"));
+        }
+        Ok(())
+    }
+}
+
+/// Closes the user's session, keeping its final stats for the history. Finishing a finished
+/// session changes nothing.
+pub struct FinishSession {
+    pub user_id: String,
+    pub session_id: i64,
+    pub learned: usize,
+    pub failed: usize,
+    pub repeated: usize,
+    pub passed: usize,
+    pub now: f64,
+}
+
+impl FinishSession {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+UPDATE practice_sessions
+SET learned        = :learned,
+    failed         = :failed,
+    repeated       = :repeated,
+    passed         = :passed,
+    active_seconds = active_seconds + MIN(MAX((:now - updated_at) * 86400, 0), :max_seconds),
+    updated_at     = :now,
+    finished_at    = :now
+WHERE id = :id AND user_id = :user_id AND finished_at IS NULL
+"#;
+        let updated = db.execute(
+            SQL,
+            named_params! {
+                ":id": self.session_id,
+                ":user_id": self.user_id,
+                ":learned": self.learned,
+                ":failed": self.failed,
+                ":repeated": self.repeated,
+                ":passed": self.passed,
+                ":now": self.now,
+                ":max_seconds": MAX_ANSWER_SECONDS,
+            },
+        )?;
+        if updated == 0 {
+            QuerySessionDeck {
+                user_id: self.user_id,
+                session_id: self.session_id,
+            }
+            .apply(db)?;
+        }
+        Ok(())
+    }
+}
+
+/// The stored deck of the user's session, finished or not.
+pub struct QuerySessionDeck {
+    pub user_id: String,
+    pub session_id: i64,
+}
+
+impl QuerySessionDeck {
+    pub fn apply(self, db: &Connection) -> Result<String, StorageError> {
+        const SQL: &str = "SELECT deck FROM practice_sessions WHERE id = ?1 AND user_id = ?2";
+        db.query_row(SQL, params![self.session_id, self.user_id], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .ok_or(StorageError::NotFound("Practice session"))
+    }
+}
+
+/// The user's most recently used unfinished session for a lesson, or for all lessons.
+/// Cram sessions are not resumed.
+pub struct QueryOpenSession {
+    pub user_id: String,
+    pub lesson_id: Option<i64>,
+}
+
+impl QueryOpenSession {
+    pub fn apply(self, db: &Connection) -> Result<Option<OpenSession>, StorageError> {
+        const SQL: &str = r#"
+SELECT id, deck
+FROM practice_sessions
+WHERE user_id = ?1 AND lesson_id IS ?2 AND cram = 0 AND finished_at IS NULL
+ORDER BY updated_at DESC
+LIMIT 1
+"#;
+        let session = db
+            .query_row(SQL, params![self.user_id, self.lesson_id], |row| {
+                Ok(OpenSession {
+                    session_id: row.get(0)?,
+                    deck: row.get(1)?,
+                })
+            })
+            .optional()?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FinishSession, OpenSession, QueryOpenSession, SaveSession, StartSession};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::{connect, StorageError};
+
+    #[test]
+    fn sessions_resume_until_finished() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let lesson_id = InsertLesson {
+            title: "Sessions".to_string(),
+            owner: "admin".to_string(),
+            phrases: vec![],
+        }
+        .apply(&mut conn)
+        .map(Some)
+        .unwrap();
+        let open = |conn: &rusqlite::Connection| {
+            QueryOpenSession {
+                user_id: "admin".to_string(),
+                lesson_id,
+            }
+            .apply(conn)
+            .unwrap()
+        };
+        let start = |conn: &mut rusqlite::Connection, deck: &str| {
+            StartSession {
+                user_id: "admin".to_string(),
+                lesson_id,
+                cram: false,
+                deck: deck.to_string(),
+                now: 2460310.5,
+            }
+            .apply(conn)
+            .unwrap()
+        };
+        assert_eq!(open(&conn), None);
+
+        let abandoned = start(&mut conn, "[1]");
+        let session_id = start(&mut conn, "[2]");
+        assert_ne!(abandoned, session_id);
+        let stranger = SaveSession {
+            user_id: "someone".to_string(),
+            session_id,
+            deck: "[0]".to_string(),
+            now: 2460310.6,
+        }
+        .apply(&conn);
+        assert!(matches!(stranger, Err(StorageError::NotFound(_))));
+        SaveSession {
+            user_id: "admin".to_string(),
+            session_id,
+            deck: "[3]".to_string(),
+            now: 2460310.6,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(
+            open(&conn),
+            Some(OpenSession {
+                session_id,
+                deck: "[3]".to_string(),
+            })
+        );
+
+        let finish = |user_id: &str| {
+            FinishSession {
+                user_id: user_id.to_string(),
+                session_id,
+                learned: 1,
+                failed: 0,
+                repeated: 0,
+                passed: 1,
+                now: 2460310.7,
+            }
+            .apply(&conn)
+        };
+        assert!(matches!(finish("someone"), Err(StorageError::NotFound(_))));
+        assert!(open(&conn).is_some());
+        finish("admin").unwrap();
+        assert_eq!(open(&conn), None);
+        // Finishing again is harmless.
+        finish("admin").unwrap();
+
+        // Sessions across all lessons are kept apart from lesson sessions.
+        let lesson_session = start(&mut conn, "[4]");
+        let review_session = StartSession {
+            user_id: "admin".to_string(),
+            lesson_id: None,
+            cram: false,
+            deck: "[5]".to_string(),
+            now: 2460310.8,
+        }
+        .apply(&mut conn)
+        .unwrap();
+        assert_eq!(
+            open(&conn).map(|open| open.session_id),
+            Some(lesson_session)
+        );
+        let review = QueryOpenSession {
+            user_id: "admin".to_string(),
+            lesson_id: None,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(review.map(|open| open.session_id), Some(review_session));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::core::backend::history::QueryDailyStats;
+use crate::core::backend::schedule::{julian_date, Day};
+use crate::core::backend::settings::QueryUserSettings;
+use crate::core::backend::StorageError;
+use crate::core::data::progress::{Achievement, Progress};
+use crate::core::data::settings::GoalKind;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashSet};
+
+/// Phrase counts that earn a milestone.
+const WORD_MILESTONES: [usize; 5] = [10, 100, 500, 1000, 2000];
+/// Distinct kanji counts that earn a milestone.
+const KANJI_MILESTONES: [usize; 5] = [10, 100, 500, 1000, 2000];
+/// The longest history the streak looks back through.
+const MAX_STREAK_DAYS: usize = 3660;
+
+/// The user's daily goal, streaks and achievements, computed from their review history.
+pub struct QueryProgress {
+    pub user_id: String,
+    pub day: Day,
+}
+
+impl QueryProgress {
+    pub fn apply(self, db: &Connection) -> Result<Progress, StorageError> {
+        let settings = QueryUserSettings {
+            user_id: self.user_id.clone(),
+        }
+        .apply(db)?;
+        let history = QueryPhraseHistory {
+            user_id: self.user_id.clone(),
+        }
+        .apply(db)?;
+        let first_day = history
+            .iter()
+            .filter_map(|phrase| phrase.first_reviewed_at)
+            .reduce(f64::min)
+            .unwrap_or(self.day.start);
+        let days = ((self.day.start - first_day).ceil().max(0.0) as usize + 1).min(MAX_STREAK_DAYS);
+        let stats = QueryDailyStats {
+            user_id: self.user_id,
+            day: self.day,
+            days,
+        }
+        .apply(db)?;
+        let done = stats
+            .iter()
+            .map(|day| match settings.goal.kind {
+                GoalKind::Cards => day.reviews,
+                GoalKind::Minutes => (day.seconds / 60) as usize,
+            })
+            .collect::<Vec<_>>();
+        let done_today = done.last().copied().unwrap_or(0);
+        let met = done
+            .iter()
+            .map(|&amount| amount >= settings.goal.amount)
+            .collect::<Vec<_>>();
+        // Today only counts against the streak once it is over.
+        let counted = match met.last() {
+            Some(false) => &met[..met.len() - 1],
+            _ => &met[..],
+        };
+        let (streak, best_streak) = streaks(counted, settings.streak_freezes);
+        let offset = self.day.start_offset();
+        Ok(Progress {
+            goal: settings.goal,
+            done_today,
+            streak,
+            best_streak,
+            achievements: achievements(&history, |at| julian_date(at - offset).to_string()),
+        })
+    }
+}
+
+/// A phrase and when it was first reviewed and first passed on a later day.
+struct PhraseHistory {
+    lesson_title: String,
+    lesson_id: i64,
+    prompt: String,
+    first_reviewed_at: Option<f64>,
+    first_recalled_at: Option<f64>,
+}
+
+struct QueryPhraseHistory {
+    user_id: String,
+}
+
+impl QueryPhraseHistory {
+    fn apply(self, db: &Connection) -> Result<Vec<PhraseHistory>, StorageError> {
+        const SQL: &str = r#"
+SELECT lessons.id,
+       lessons.title,
+       phrases.prompt,
+       MIN(reviews.reviewed_at),
+       MIN(CASE WHEN reviews.kind = 'review' THEN reviews.reviewed_at END)
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+         LEFT JOIN reviews ON reviews.phrase_id = phrases.id
+WHERE lessons.creator_id = ?1
+GROUP BY phrases.id
+ORDER BY 4
+"#;
+        let mut stmt = db.prepare(SQL)?;
+        let rows = stmt.query_map(params![self.user_id], |row| {
+            Ok(PhraseHistory {
+                lesson_id: row.get(0)?,
+                lesson_title: row.get(1)?,
+                prompt: row.get(2)?,
+                first_reviewed_at: row.get(3)?,
+                first_recalled_at: row.get(4)?,
+            })
+        })?;
+        let history = rows.collect::<Result<Vec<_>, _>>()?;
+        Ok(history)
+    }
+}
+
+/// The current and best streak of met days, oldest first, allowing up to `freezes`
+/// missed days in a row.
+fn streaks(met: &[bool], freezes: usize) -> (usize, usize) {
+    let (mut current, mut best, mut missed) = (0, 0, 0);
+    for &met in met {
+        if met {
+            current += 1;
+            missed = 0;
+            best = best.max(current);
+        } else {
+            missed += 1;
+            if missed > freezes {
+                current = 0;
+            }
+        }
+    }
+    (current, best)
+}
+
+/// Milestones for words learned, kanji known and lessons mastered. A lesson is mastered
+/// once every phrase in it has been passed on a day after it was learned.
+fn achievements(history: &[PhraseHistory], date: impl Fn(f64) -> String) -> Vec<Achievement> {
+    let mut earned = Vec::new();
+    let mut upcoming = Vec::new();
+    let mut milestone = |title: String, progress: usize, target: usize, at: Option<f64>| {
+        let achievement = Achievement {
+            title,
+            earned_on: at.map(&date),
+            progress: progress.min(target),
+            target,
+        };
+        match at {
+            Some(at) => earned.push((at, achievement)),
+            None => upcoming.push(achievement),
+        }
+    };
+
+    let reviewed = history
+        .iter()
+        .filter_map(|phrase| Some((phrase.first_reviewed_at?, phrase.prompt.as_str())))
+        .collect::<Vec<_>>();
+    for target in WORD_MILESTONES {
+        let at = reviewed.get(target - 1).map(|&(at, _)| at);
+        milestone(
+            format!("{target} words learned"),
+            reviewed.len(),
+            target,
+            at,
+        );
+        if at.is_none() {
+            break;
+        }
+    }
+
+    let mut kanji = HashSet::new();
+    let mut kanji_reached = Vec::new();
+    for &(at, prompt) in &reviewed {
+        for c in prompt.chars().filter(|&c| is_kanji(c)) {
+            if kanji.insert(c) {
+                kanji_reached.push(at);
+            }
+        }
+    }
+    for target in KANJI_MILESTONES {
+        let at = kanji_reached.get(target - 1).copied();
+        milestone(format!("{target} kanji known"), kanji.len(), target, at);
+        if at.is_none() {
+            break;
+        }
+    }
+
+    let mut lessons = BTreeMap::new();
+    for phrase in history {
+        let lesson = lessons
+            .entry(phrase.lesson_id)
+            .or_insert((phrase.lesson_title.as_str(), Some(f64::MIN)));
+        lesson.1 = match (lesson.1, phrase.first_recalled_at) {
+            (Some(latest), Some(at)) => Some(latest.max(at)),
+            _ => None,
+        };
+    }
+    for (title, mastered_at) in lessons.into_values() {
+        if let Some(at) = mastered_at {
+            milestone(format!("Mastered {title}"), 1, 1, Some(at));
+        }
+    }
+
+    earned.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    earned
+        .into_iter()
+        .map(|(_, achievement)| achievement)
+        .chain(upcoming)
+        .collect()
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{achievements, streaks, PhraseHistory};
+
+    #[test]
+    fn freezes_bridge_missed_days() {
+        let (t, f) = (true, false);
+        assert_eq!(streaks(&[t, t, f, t], 0), (1, 2));
+        assert_eq!(streaks(&[t, t, f, t], 1), (3, 3));
+        assert_eq!(streaks(&[t, f, f, t], 1), (1, 1));
+        assert_eq!(streaks(&[t, t, f], 1), (2, 2));
+        assert_eq!(streaks(&[t, t, f, f], 1), (0, 2));
+    }
+
+    #[test]
+    fn milestones_come_from_first_reviews() {
+        let phrase =
+            |lesson_id, prompt: &str, reviewed: Option<f64>, recalled: Option<f64>| PhraseHistory {
+                lesson_title: format!("Lesson {lesson_id}"),
+                lesson_id,
+                prompt: prompt.to_string(),
+                first_reviewed_at: reviewed,
+                first_recalled_at: recalled,
+            };
+        let mut history = (0..10)
+            .map(|n| phrase(1, "日本", Some(n as f64), Some(20.0 + n as f64)))
+            .collect::<Vec<_>>();
+        history.push(phrase(2, "学校", Some(15.0), None));
+        let achievements = achievements(&history, |at| at.to_string());
+        let summary = achievements
+            .iter()
+            .map(|a| (a.title.as_str(), a.earned_on.as_deref(), a.progress))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("10 words learned", Some("9"), 10),
+                ("Mastered Lesson 1", Some("29"), 1),
+                ("100 words learned", None, 11),
+                ("10 kanji known", None, 4),
+            ]
+        );
+    }
+}
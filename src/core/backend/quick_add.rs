@@ -0,0 +1,156 @@
+use crate::core::backend::dictionary::Dictionary;
+use crate::core::backend::insert_lesson::{insert_lesson, insert_phrase};
+use crate::core::backend::{read_user_lessons, StorageError};
+use crate::core::data::NewPhrase;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Adds a phrase to the user's inbox lesson, making the lesson on first use, and returns the
+/// lesson and phrase ids. A missing reading or meaning is filled in from the user's other
+/// phrases for the word, then from the dictionary. A phrase already in the inbox isn't repeated.
+pub struct QuickAddPhrase<'a> {
+    pub user_id: String,
+    pub inbox: String,
+    pub phrase: NewPhrase,
+    pub dictionary: Option<&'a Dictionary>,
+}
+
+impl QuickAddPhrase<'_> {
+    pub fn apply(self, db: &mut Connection) -> Result<(i64, i64), StorageError> {
+        const EXISTING_SQL: &str =
+            "SELECT id FROM phrases WHERE lesson_id = ?1 AND prompt = ?2 AND reading = ?3";
+        let mut phrase = self.phrase;
+        let reading = (!phrase.reading.is_empty()).then(|| phrase.reading.clone());
+        if phrase.reading.is_empty() || phrase.translation.is_empty() {
+            if let Some((known_reading, meaning)) =
+                known_word(db, &self.user_id, &phrase.prompt, reading.as_deref())?
+            {
+                fill(&mut phrase, known_reading, meaning);
+            }
+        }
+        if let Some(entry) = self
+            .dictionary
+            .and_then(|dictionary| dictionary.lookup(&phrase.prompt, reading.as_deref()))
+        {
+            fill(&mut phrase, entry.reading.clone(), entry.meaning.clone());
+        }
+
+        let tx = db.transaction()?;
+        let inbox = read_user_lessons(&self.user_id, &tx)?
+            .into_iter()
+            .find(|lesson| lesson.title == self.inbox);
+        let lesson_id = match inbox {
+            Some(lesson) => lesson.lesson_id,
+            None => insert_lesson(&tx, &self.inbox, &self.user_id)?,
+        };
+        let existing = tx
+            .query_row(
+                EXISTING_SQL,
+                params![lesson_id, phrase.prompt, phrase.reading],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let phrase_id = match existing {
+            Some(phrase_id) => phrase_id,
+            None => insert_phrase(&tx, lesson_id, &self.user_id, &phrase)?,
+        };
+        tx.commit()?;
+        Ok((lesson_id, phrase_id))
+    }
+}
+
+/// The reading and meaning the user already gave the word in a lesson, matching `reading` if given.
+fn known_word(
+    db: &Connection,
+    user_id: &str,
+    prompt: &str,
+    reading: Option<&str>,
+) -> Result<Option<(String, String)>, StorageError> {
+    const SQL: &str = r#"
+SELECT phrases.reading, phrases.translation
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE lessons.creator_id = ?1
+  AND phrases.prompt = ?2
+  AND (?3 IS NULL OR phrases.reading = ?3)
+  AND phrases.translation != ''
+ORDER BY phrases.id
+LIMIT 1
+"#;
+    let known = db
+        .query_row(SQL, params![user_id, prompt, reading], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()?;
+    Ok(known)
+}
+
+/// Fills in the phrase's reading and meaning where they're empty.
+fn fill(phrase: &mut NewPhrase, reading: String, meaning: String) {
+    if phrase.reading.is_empty() {
+        phrase.reading = reading;
+    }
+    if phrase.translation.is_empty() {
+        phrase.translation = meaning;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuickAddPhrase;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::dictionary::Dictionary;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::{connect, read_phrases, read_user_lessons};
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn fills_words_into_the_inbox() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        InsertLesson {
+            title: "Chapter 1".to_string(),
+            owner: "admin".to_string(),
+            phrases: vec![NewPhrase {
+                prompt: "冒険".to_string(),
+                reading: "ぼうけん".to_string(),
+                translation: "adventure".to_string(),
+                ..Default::default()
+            }],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let dictionary = Dictionary::parse(
+            "# word\treading\tmeaning\n引退\tいんたい\tretirement\n冒険\tぼうけん\trisk\n",
+        );
+        let mut add = |prompt: &str| {
+            QuickAddPhrase {
+                user_id: "admin".to_string(),
+                inbox: "Inbox".to_string(),
+                phrase: NewPhrase {
+                    prompt: prompt.to_string(),
+                    sentence: format!("{prompt}したい"),
+                    source_url: Some("https://example.com/novel/1".to_string()),
+                    ..Default::default()
+                },
+                dictionary: Some(&dictionary),
+            }
+            .apply(&mut conn)
+            .unwrap()
+        };
+        let (inbox_id, retired) = add("引退");
+        let (_, adventure) = add("冒険");
+        let (_, unknown) = add("嘆き");
+        assert_eq!(add("引退"), (inbox_id, retired));
+
+        let lessons = read_user_lessons("admin", &conn).unwrap();
+        assert_eq!(lessons.len(), 2);
+        let phrases = read_phrases(inbox_id, &conn).unwrap();
+        let phrase = |phrase_id: i64| {
+            let phrase = phrases.iter().find(|p| p.phrase_id == phrase_id).unwrap();
+            (phrase.reading.as_str(), phrase.translation.as_str())
+        };
+        assert_eq!(phrases.len(), 3);
+        assert_eq!(phrase(retired), ("いんたい", "retirement"));
+        assert_eq!(phrase(adventure), ("ぼうけん", "adventure"));
+        assert_eq!(phrase(unknown), ("", ""));
+    }
+}
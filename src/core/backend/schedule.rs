@@ -0,0 +1,252 @@
+use crate::core::backend::clock::{Clock, OffsetClock};
+use crate::core::backend::settings::QueryUserSettings;
+use crate::core::backend::{require_admin, StorageError};
+use jiff::tz::TimeZone;
+use jiff::{civil, SignedDuration};
+use rusqlite::Connection;
+
+/// Condition for a phrase needing practice: never learned, learned before the
+/// current day started, or changed since it was learned. Binds `:day_start`.
+pub const READY: &str =
+    "(learned_at IS NULL OR learned_at < :day_start OR content_changed_at > learned_at)";
+
+/// Condition for a phrase that is neither suspended nor buried. Binds `:now`.
+pub const ACTIVE: &str = "(suspended = 0 AND (buried_until IS NULL OR buried_until <= :now))";
+
+/// A moment in the user's local time and the start of the user's day, both as Julian days.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Day {
+    pub now: f64,
+    pub start: f64,
+}
+
+impl Day {
+    pub fn new(now: f64, start_hour: u8) -> Self {
+        let offset = f64::from(start_hour) / 24.0;
+        // Julian days begin at noon, so midnight falls on the half day.
+        let start = (now - 0.5 - offset).floor() + 0.5 + offset;
+        Self { now, start }
+    }
+
+    pub fn next_start(&self) -> f64 {
+        self.start + 1.0
+    }
+
+    /// How long after midnight the day starts, as a fraction of a day. Subtracting it from
+    /// a time gives a moment whose calendar date is the practice day it belongs to.
+    pub fn start_offset(&self) -> f64 {
+        (self.start - 0.5).rem_euclid(1.0)
+    }
+}
+
+/// The time zone with the given name. `None` is the server's zone.
+pub fn time_zone(name: Option<&str>) -> Result<TimeZone, StorageError> {
+    match name {
+        Some(name) => TimeZone::get(name).map_err(|_| StorageError::TimeZone(name.to_string())),
+        None => Ok(TimeZone::system()),
+    }
+}
+
+/// The clock's time in a time zone as a Julian day. `None` uses the server's zone.
+pub fn local_now(clock: &dyn Clock, time_zone_name: Option<&str>) -> Result<f64, StorageError> {
+    let zoned = clock.now().to_zoned(time_zone(time_zone_name)?);
+    let seconds = zoned.timestamp().as_millisecond() as f64 / 1000.0;
+    let offset = f64::from(zoned.offset().seconds());
+    Ok((seconds + offset) / 86400.0 + 2440587.5)
+}
+
+/// The calendar date of a local Julian day.
+pub fn julian_date(julian_day: f64) -> civil::Date {
+    // Local Julian days count wall-clock time, so read them back as UTC.
+    let seconds = ((julian_day - 2440587.5) * 86400.0).floor() as i64;
+    jiff::Timestamp::from_second(seconds)
+        .unwrap_or_default()
+        .to_zoned(TimeZone::UTC)
+        .date()
+}
+
+pub fn is_time_zone(name: &str) -> bool {
+    TimeZone::get(name).is_ok()
+}
+
+/// The user's clock: `clock` moved ahead by the user's simulated date offset.
+fn user_clock(clock: &dyn Clock, offset_days: i64) -> OffsetClock<'_> {
+    OffsetClock {
+        inner: clock,
+        offset: SignedDuration::from_hours(24 * offset_days),
+    }
+}
+
+/// The current day in the user's time zone.
+pub struct QueryUserDay<'a> {
+    pub user_id: String,
+    pub clock: &'a dyn Clock,
+}
+
+impl QueryUserDay<'_> {
+    pub fn apply(self, db: &Connection) -> Result<Day, StorageError> {
+        let settings = QueryUserSettings {
+            user_id: self.user_id,
+        }
+        .apply(db)?;
+        let clock = user_clock(self.clock, settings.clock_offset_days);
+        let now = local_now(&clock, settings.time_zone.as_deref())?;
+        Ok(Day::new(now, settings.day_start_hour))
+    }
+}
+
+/// The calendar date in the user's time zone, after any simulated offset.
+pub struct QueryUserDate<'a> {
+    pub user_id: String,
+    pub clock: &'a dyn Clock,
+}
+
+impl QueryUserDate<'_> {
+    pub fn apply(self, db: &Connection) -> Result<civil::Date, StorageError> {
+        let settings = QueryUserSettings {
+            user_id: self.user_id,
+        }
+        .apply(db)?;
+        let clock = user_clock(self.clock, settings.clock_offset_days);
+        let zone = time_zone(settings.time_zone.as_deref())?;
+        Ok(clock.now().to_zoned(zone).date())
+    }
+}
+
+/// Moves the user's clock so that today is `date`, or back to the real date for `None`.
+/// Only admins may, so `requested_by` must be one.
+pub struct SimulateDate<'a> {
+    pub user_id: String,
+    pub requested_by: String,
+    pub clock: &'a dyn Clock,
+    pub date: Option<civil::Date>,
+}
+
+impl SimulateDate<'_> {
+    pub fn apply(self, db: &Connection) -> Result<i64, StorageError> {
+        const SQL: &str = r#"
+INSERT INTO user_settings (user_id, clock_offset_days)
+VALUES (?1, ?2)
+ON CONFLICT (user_id) DO UPDATE SET clock_offset_days = excluded.clock_offset_days
+"#;
+        require_admin(db, &self.requested_by, "simulate a date")?;
+        let settings = QueryUserSettings {
+            user_id: self.user_id.clone(),
+        }
+        .apply(db)?;
+        let offset_days = match self.date {
+            Some(date) => {
+                let zone = time_zone(settings.time_zone.as_deref())?;
+                let today = self.clock.now().to_zoned(zone).date();
+                i64::from((date - today).get_days())
+            }
+            None => 0,
+        };
+        db.execute(SQL, rusqlite::params![self.user_id, offset_days])?;
+        Ok(offset_days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Day, QueryUserDate, QueryUserDay, SimulateDate};
+    use crate::core::backend::clock::FixedClock;
+    use crate::core::backend::config::Config;
+    use crate::core::backend::settings::SaveUserSettings;
+    use crate::core::backend::{connect, CreateUser, StorageError};
+    use crate::core::data::settings::UserSettings;
+
+    #[test]
+    fn day_starts_at_the_start_hour() {
+        // 2024-01-01 00:00 local is Julian day 2460310.5.
+        let midnight = 2460310.5;
+        let day = Day::new(midnight + 10.0 / 24.0, 3);
+        assert!((day.start - (midnight + 3.0 / 24.0)).abs() < 1e-9);
+        let day = Day::new(midnight + 1.0 / 24.0, 3);
+        assert!((day.start - (midnight - 21.0 / 24.0)).abs() < 1e-9);
+        assert!((day.next_start() - (midnight + 3.0 / 24.0)).abs() < 1e-9);
+        assert!((day.start_offset() - 3.0 / 24.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn simulated_date_moves_the_user_day() {
+        let conn = connect(&Config::in_memory()).unwrap();
+        SaveUserSettings {
+            user_id: "admin".to_string(),
+            settings: UserSettings {
+                time_zone: Some("Asia/Tokyo".to_string()),
+                ..Default::default()
+            },
+        }
+        .apply(&conn)
+        .unwrap();
+        // 2024-01-01 10:00 in Tokyo.
+        let clock = FixedClock {
+            at: "2024-01-01T01:00:00Z".parse().unwrap(),
+        };
+        let today = QueryUserDay {
+            user_id: "admin".to_string(),
+            clock: &clock,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert!((today.start - (2460310.5 + 3.0 / 24.0)).abs() < 1e-9);
+
+        let offset = SimulateDate {
+            user_id: "admin".to_string(),
+            requested_by: "admin".to_string(),
+            clock: &clock,
+            date: Some("2024-01-08".parse().unwrap()),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(offset, 7);
+        let clock = clock.advance(jiff::SignedDuration::from_hours(20));
+        let date = QueryUserDate {
+            user_id: "admin".to_string(),
+            clock: &clock,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(date, "2024-01-09".parse().unwrap());
+        let day = QueryUserDay {
+            user_id: "admin".to_string(),
+            clock: &clock,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert!((day.start - (today.start + 8.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn only_admins_simulate_dates() {
+        let conn = connect(&Config::in_memory()).unwrap();
+        CreateUser {
+            user_id: "kaito".to_string(),
+            is_admin: false,
+            settings: UserSettings::default(),
+        }
+        .apply(&conn)
+        .unwrap();
+        let clock = FixedClock {
+            at: "2024-01-01T01:00:00Z".parse().unwrap(),
+        };
+        for user_id in ["admin", "kaito"] {
+            let refused = SimulateDate {
+                user_id: user_id.to_string(),
+                requested_by: "kaito".to_string(),
+                clock: &clock,
+                date: Some("2024-01-08".parse().unwrap()),
+            }
+            .apply(&conn);
+            assert!(matches!(refused, Err(StorageError::AdminOnly(_))));
+        }
+        let date = QueryUserDate {
+            user_id: "admin".to_string(),
+            clock: &clock,
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(date, "2024-01-01".parse().unwrap());
+    }
+}
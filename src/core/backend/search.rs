@@ -0,0 +1,276 @@
+use crate::core::backend::StorageError;
+use crate::core::data::search::SearchHit;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Row};
+
+/// Romaji syllables and their hiragana, covering Hepburn and Kunrei spellings.
+#[rustfmt::skip]
+const ROMAJI: &[(&str, &str)] = &[
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+    ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"), ("she", "しぇ"), ("shi", "し"),
+    ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+    ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"), ("che", "ちぇ"), ("chi", "ち"),
+    ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"),
+    ("dya", "ぢゃ"), ("dyu", "ぢゅ"), ("dyo", "ぢょ"),
+    ("tsu", "つ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+    ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+    ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+    ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"), ("je", "じぇ"), ("ji", "じ"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("sa", "さ"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("za", "ざ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ta", "た"), ("ti", "ち"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("hu", "ふ"), ("fu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("fa", "ふぁ"), ("fi", "ふぃ"), ("fe", "ふぇ"), ("fo", "ふぉ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"),
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+];
+
+/// Hiragana spelled by `romaji`, or `None` when it isn't romaji.
+pub fn romaji_to_hiragana(romaji: &str) -> Option<String> {
+    let romaji = romaji
+        .to_lowercase()
+        .replace('ā', "aa")
+        .replace('ī', "ii")
+        .replace('ū', "uu")
+        .replace('ē', "ee")
+        .replace('ō', "ou")
+        .replace('-', "ー");
+    let chars = romaji.chars().collect::<Vec<_>>();
+    let is_vowel = |c: char| "aiueo".contains(c);
+    let mut kana = String::new();
+    let mut at = 0;
+    while at < chars.len() {
+        let c = chars[at];
+        let next = chars.get(at + 1).copied();
+        if c == 'ー' || c == '\'' {
+            if c == 'ー' {
+                kana.push(c);
+            }
+            at += 1;
+            continue;
+        }
+        let ends_syllable = |next: Option<char>| next.is_none_or(|c| !is_vowel(c) && c != 'y');
+        if c == 'n' && ends_syllable(next) {
+            // "nn" spells one ん unless the second n starts the next syllable.
+            kana.push('ん');
+            at += if next == Some('n') && ends_syllable(chars.get(at + 2).copied()) {
+                2
+            } else {
+                1
+            };
+            continue;
+        }
+        let doubled = next == Some(c) || (c == 't' && next == Some('c'));
+        if c.is_ascii_alphabetic() && !is_vowel(c) && doubled {
+            kana.push('っ');
+            at += 1;
+            continue;
+        }
+        let rest = chars[at..].iter().take(3).collect::<String>();
+        let (spelled, syllable) = ROMAJI
+            .iter()
+            .find(|(spelled, _)| rest.starts_with(spelled))?;
+        kana.push_str(syllable);
+        at += spelled.len();
+    }
+    Some(kana)
+}
+
+/// The text with katakana turned into hiragana.
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// The text with hiragana turned into katakana.
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// The query as typed plus its spellings in hiragana and katakana.
+pub fn query_spellings(query: &str) -> Vec<String> {
+    let query = query.trim();
+    let kana = romaji_to_hiragana(query).unwrap_or_else(|| to_hiragana(query));
+    let mut spellings: Vec<String> = Vec::new();
+    for spelling in [query.to_string(), to_hiragana(&kana), to_katakana(&kana)] {
+        if !spelling.is_empty() && !spellings.contains(&spelling) {
+            spellings.push(spelling);
+        }
+    }
+    spellings
+}
+
+/// Phrases in the user's lessons whose prompt, reading, translation or sentence contain the query,
+/// spelled in kanji, kana or romaji. Best matches come first.
+pub struct QuerySearch {
+    pub user_id: String,
+    pub query: String,
+    pub limit: usize,
+}
+
+impl QuerySearch {
+    pub fn apply(self, db: &Connection) -> Result<Vec<SearchHit>, StorageError> {
+        // Trigrams only index text of three characters or more, so shorter spellings scan instead.
+        const MATCH_SQL: &str = r#"
+SELECT phrases.id, phrases.lesson_id, lessons.title, phrases.prompt, phrases.reading, phrases.translation,
+       phrases.sentence
+FROM phrase_search
+         JOIN phrases ON phrases.id = phrase_search.rowid
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE lessons.creator_id = ?1
+  AND phrase_search MATCH ?3
+ORDER BY bm25(phrase_search)
+LIMIT ?2
+"#;
+        let spellings = query_spellings(&self.query);
+        if spellings.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut values = vec![Value::Text(self.user_id), Value::Integer(self.limit as i64)];
+        let sql = if spellings
+            .iter()
+            .all(|spelling| spelling.chars().count() >= 3)
+        {
+            let query = spellings
+                .iter()
+                .map(|spelling| format!("\"{}\"", spelling.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            values.push(Value::Text(query));
+            MATCH_SQL.to_string()
+        } else {
+            let mut like = Vec::new();
+            for spelling in &spellings {
+                let escaped = spelling
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                values.push(Value::Text(format!("%{escaped}%")));
+                let pattern = format!("?{} ESCAPE '\\'", values.len());
+                like.extend(
+                    ["prompt", "reading", "translation", "sentence"]
+                        .map(|column| format!("phrases.{column} LIKE {pattern}")),
+                );
+            }
+            let like = like.join(" OR ");
+            format!(
+                r#"
+SELECT phrases.id, phrases.lesson_id, lessons.title, phrases.prompt, phrases.reading, phrases.translation,
+       phrases.sentence
+FROM phrases
+         JOIN lessons ON lessons.id = phrases.lesson_id
+WHERE lessons.creator_id = ?1
+  AND ({like})
+ORDER BY length(phrases.prompt), phrases.id
+LIMIT ?2
+"#
+            )
+        };
+        let hits = db
+            .prepare(&sql)?
+            .query_map(params_from_iter(values), read_hit)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(hits)
+    }
+}
+
+fn read_hit(row: &Row) -> rusqlite::Result<SearchHit> {
+    Ok(SearchHit {
+        phrase_id: row.get(0)?,
+        lesson_id: row.get(1)?,
+        lesson_title: row.get(2)?,
+        prompt: row.get(3)?,
+        reading: row.get(4)?,
+        meaning: row.get(5)?,
+        sentence: row.get(6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{query_spellings, romaji_to_hiragana, QuerySearch};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::connect;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn romaji_spells_hiragana() {
+        let kana = |romaji: &str| romaji_to_hiragana(romaji).unwrap();
+        assert_eq!(kana("igai"), "いがい");
+        assert_eq!(kana("konnichiwa"), "こんにちわ");
+        assert_eq!(kana("kippu"), "きっぷ");
+        assert_eq!(kana("matcha"), "まっちゃ");
+        assert_eq!(kana("shin'you"), "しんよう");
+        assert_eq!(kana("tōkyō"), "とうきょう");
+        assert_eq!(romaji_to_hiragana("意外"), None);
+        assert_eq!(query_spellings("イガイ"), ["イガイ", "いがい"]);
+    }
+
+    #[test]
+    fn search_matches_any_script() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        InsertLesson {
+            title: "Search".to_string(),
+            owner: "admin".to_string(),
+            phrases: [
+                ("意外", "いがい", "unexpected"),
+                ("以外", "いがい", "except"),
+            ]
+            .into_iter()
+            .map(|(prompt, reading, translation)| NewPhrase {
+                prompt: prompt.to_string(),
+                reading: reading.to_string(),
+                translation: translation.to_string(),
+                sentence: format!("それは{prompt}だった。"),
+                ..Default::default()
+            })
+            .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let search = |query: &str| {
+            QuerySearch {
+                user_id: "admin".to_string(),
+                query: query.to_string(),
+                limit: 10,
+            }
+            .apply(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.prompt)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(search("igai").len(), 2);
+        assert_eq!(search("イガイ").len(), 2);
+        assert_eq!(search("意外"), ["意外"]);
+        assert_eq!(search("unexpect"), ["意外"]);
+        assert_eq!(search("以外だった"), ["以外"]);
+        assert!(search("犬").is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::core::backend::StorageError;
+use crate::core::data::settings::{
+    DailyGoal, GoalKind, KeyBindings, LessonSettings, PracticeLimits, UserSettings,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub struct QueryUserSettings {
+    pub user_id: String,
+}
+
+impl QueryUserSettings {
+    pub fn apply(self, db: &Connection) -> Result<UserSettings, StorageError> {
+        const SQL: &str = r#"
+SELECT leech_threshold, session_size, new_per_day, reviews_per_day, time_zone, day_start_hour,
+       clock_offset_days, key_flip, key_learn, key_fail, key_repeat, key_pass, key_bury,
+       key_suspend, key_undo, key_close, goal_kind, goal_amount, streak_freezes
+FROM user_settings
+WHERE user_id = ?1
+"#;
+        let settings = db
+            .query_row(SQL, params![self.user_id], |row| {
+                let leech_threshold: i64 = row.get(0)?;
+                let session_size: i64 = row.get(1)?;
+                let new_per_day: i64 = row.get(2)?;
+                let reviews_per_day: i64 = row.get(3)?;
+                let goal_kind: String = row.get(16)?;
+                let goal_amount: i64 = row.get(17)?;
+                let streak_freezes: i64 = row.get(18)?;
+                Ok(UserSettings {
+                    leech_threshold: leech_threshold as usize,
+                    session_size: session_size as usize,
+                    new_per_day: new_per_day as usize,
+                    reviews_per_day: reviews_per_day as usize,
+                    time_zone: row.get(4)?,
+                    day_start_hour: row.get(5)?,
+                    clock_offset_days: row.get(6)?,
+                    keys: KeyBindings {
+                        flip: row.get(7)?,
+                        learn: row.get(8)?,
+                        fail: row.get(9)?,
+                        repeat: row.get(10)?,
+                        pass: row.get(11)?,
+                        bury: row.get(12)?,
+                        suspend: row.get(13)?,
+                        undo: row.get(14)?,
+                        close: row.get(15)?,
+                    },
+                    goal: DailyGoal {
+                        kind: GoalKind::parse(&goal_kind).unwrap_or(GoalKind::Cards),
+                        amount: goal_amount as usize,
+                    },
+                    streak_freezes: streak_freezes as usize,
+                })
+            })
+            .optional()?;
+        Ok(settings.unwrap_or_default())
+    }
+}
+
+pub struct SaveUserSettings {
+    pub user_id: String,
+    pub settings: UserSettings,
+}
+
+impl SaveUserSettings {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+INSERT INTO user_settings (user_id, leech_threshold, session_size, new_per_day, reviews_per_day,
+                           time_zone, day_start_hour, key_flip, key_learn, key_fail, key_repeat,
+                           key_pass, key_bury, key_suspend, key_undo, key_close, goal_kind,
+                           goal_amount, streak_freezes)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+ON CONFLICT (user_id) DO UPDATE SET
+    leech_threshold = excluded.leech_threshold,
+    session_size    = excluded.session_size,
+    new_per_day     = excluded.new_per_day,
+    reviews_per_day = excluded.reviews_per_day,
+    time_zone       = excluded.time_zone,
+    day_start_hour  = excluded.day_start_hour,
+    key_flip        = excluded.key_flip,
+    key_learn       = excluded.key_learn,
+    key_fail        = excluded.key_fail,
+    key_repeat      = excluded.key_repeat,
+    key_pass        = excluded.key_pass,
+    key_bury        = excluded.key_bury,
+    key_suspend     = excluded.key_suspend,
+    key_undo        = excluded.key_undo,
+    key_close       = excluded.key_close,
+    goal_kind       = excluded.goal_kind,
+    goal_amount     = excluded.goal_amount,
+    streak_freezes  = excluded.streak_freezes
+"#;
+        let UserSettings {
+            leech_threshold,
+            session_size,
+            new_per_day,
+            reviews_per_day,
+            time_zone,
+            day_start_hour,
+            clock_offset_days: _,
+            keys,
+            goal,
+            streak_freezes,
+        } = self.settings;
+        db.execute(
+            SQL,
+            params![
+                self.user_id,
+                leech_threshold.max(1),
+                session_size.max(1),
+                new_per_day,
+                reviews_per_day,
+                time_zone,
+                day_start_hour.min(23),
+                keys.flip,
+                keys.learn,
+                keys.fail,
+                keys.repeat,
+                keys.pass,
+                keys.bury,
+                keys.suspend,
+                keys.undo,
+                keys.close,
+                goal.kind.as_str(),
+                goal.amount.max(1),
+                streak_freezes
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct QueryLessonSettings {
+    pub lesson_id: i64,
+}
+
+impl QueryLessonSettings {
+    pub fn apply(self, db: &Connection) -> Result<LessonSettings, StorageError> {
+        const SQL: &str = r#"
+SELECT session_size, new_per_day, reviews_per_day, review_weight
+FROM lesson_settings
+WHERE lesson_id = ?1
+"#;
+        let lesson_id = self.lesson_id;
+        let settings = db
+            .query_row(SQL, params![lesson_id], |row| {
+                let session_size: Option<i64> = row.get(0)?;
+                let new_per_day: Option<i64> = row.get(1)?;
+                let reviews_per_day: Option<i64> = row.get(2)?;
+                let review_weight: Option<i64> = row.get(3)?;
+                Ok(LessonSettings {
+                    lesson_id,
+                    session_size: session_size.map(|n| n as usize),
+                    new_per_day: new_per_day.map(|n| n as usize),
+                    reviews_per_day: reviews_per_day.map(|n| n as usize),
+                    review_weight: review_weight.map(|n| n as usize),
+                })
+            })
+            .optional()?;
+        Ok(settings.unwrap_or(LessonSettings {
+            lesson_id,
+            ..Default::default()
+        }))
+    }
+}
+
+pub struct SaveLessonSettings {
+    pub settings: LessonSettings,
+}
+
+impl SaveLessonSettings {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+INSERT INTO lesson_settings (lesson_id, session_size, new_per_day, reviews_per_day, review_weight)
+VALUES (?1, ?2, ?3, ?4, ?5)
+ON CONFLICT (lesson_id) DO UPDATE SET
+    session_size    = excluded.session_size,
+    new_per_day     = excluded.new_per_day,
+    reviews_per_day = excluded.reviews_per_day,
+    review_weight   = excluded.review_weight
+"#;
+        let LessonSettings {
+            lesson_id,
+            session_size,
+            new_per_day,
+            reviews_per_day,
+            review_weight,
+        } = self.settings;
+        db.execute(
+            SQL,
+            params![
+                lesson_id,
+                session_size.map(|n| n.max(1)),
+                new_per_day,
+                reviews_per_day,
+                review_weight
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Combines the user's settings with the lesson's overrides.
+pub struct QueryPracticeLimits {
+    pub user_id: String,
+    pub lesson_id: i64,
+}
+
+impl QueryPracticeLimits {
+    pub fn apply(self, db: &Connection) -> Result<PracticeLimits, StorageError> {
+        let user = QueryUserSettings {
+            user_id: self.user_id,
+        }
+        .apply(db)?;
+        let lesson = QueryLessonSettings {
+            lesson_id: self.lesson_id,
+        }
+        .apply(db)?;
+        Ok(user.limits_for(&lesson))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::core::backend::StorageError;
+use rusqlite::{params, Connection};
+
+/// Condition for a phrase carrying the tag named `:tag`, or for any phrase when `:tag` is NULL.
+pub const TAGGED: &str = r#"(:tag IS NULL OR EXISTS (SELECT 1
+                                         FROM phrase_tags
+                                                  JOIN tags ON tags.id = phrase_tags.tag_id
+                                         WHERE phrase_tags.phrase_id = phrases.id
+                                           AND tags.name = :tag))"#;
+
+/// Tags a phrase, creating the user's tag if needed. Phrases in other users' lessons are skipped.
+pub fn tag_phrase(
+    db: &Connection,
+    user_id: &str,
+    phrase_id: i64,
+    tag: &str,
+) -> Result<(), StorageError> {
+    const OWNED_SQL: &str = r#"
+SELECT EXISTS (SELECT 1
+               FROM phrases
+                        JOIN lessons ON lessons.id = phrases.lesson_id
+               WHERE phrases.id = ?1
+                 AND lessons.creator_id = ?2)
+"#;
+    const TAG_SQL: &str = r#"
+INSERT INTO tags (user_id, name) VALUES (?1, ?2)
+ON CONFLICT (user_id, name) DO UPDATE SET name = excluded.name
+RETURNING id
+"#;
+    const PHRASE_SQL: &str =
+        "INSERT OR IGNORE INTO phrase_tags (phrase_id, tag_id) VALUES (?1, ?2)";
+    let owned: bool = db.query_row(OWNED_SQL, params![phrase_id, user_id], |row| row.get(0))?;
+    if !owned {
+        return Ok(());
+    }
+    let tag_id: i64 = db.query_row(TAG_SQL, params![user_id, tag], |row| row.get(0))?;
+    db.execute(PHRASE_SQL, params![phrase_id, tag_id])?;
+    Ok(())
+}
+
+pub struct TagPhrases {
+    pub user_id: String,
+    pub phrase_ids: Vec<i64>,
+    pub tags: Vec<String>,
+}
+
+impl TagPhrases {
+    pub fn apply(self, db: &mut Connection) -> Result<(), StorageError> {
+        let tx = db.transaction()?;
+        for tag in &self.tags {
+            for &phrase_id in &self.phrase_ids {
+                tag_phrase(&tx, &self.user_id, phrase_id, tag)?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Removes tags from phrases, dropping tags left on no phrase.
+pub struct UntagPhrases {
+    pub user_id: String,
+    pub phrase_ids: Vec<i64>,
+    pub tags: Vec<String>,
+}
+
+impl UntagPhrases {
+    pub fn apply(self, db: &mut Connection) -> Result<(), StorageError> {
+        const SQL: &str = r#"
+DELETE FROM phrase_tags
+WHERE phrase_id = ?1
+  AND tag_id = (SELECT id FROM tags WHERE user_id = ?2 AND name = ?3)
+"#;
+        const UNUSED_SQL: &str = r#"
+DELETE FROM tags
+WHERE user_id = ?1
+  AND NOT EXISTS (SELECT 1 FROM phrase_tags WHERE phrase_tags.tag_id = tags.id)
+"#;
+        let tx = db.transaction()?;
+        for tag in &self.tags {
+            for &phrase_id in &self.phrase_ids {
+                tx.execute(SQL, params![phrase_id, self.user_id, tag])?;
+            }
+        }
+        tx.execute(UNUSED_SQL, params![self.user_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+pub struct QueryTags {
+    pub user_id: String,
+}
+
+impl QueryTags {
+    pub fn apply(self, db: &Connection) -> Result<Vec<String>, StorageError> {
+        const SQL: &str = "SELECT name FROM tags WHERE user_id = ?1 ORDER BY name";
+        let tags = db
+            .prepare(SQL)?
+            .query_map(params![self.user_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryTags, TagPhrases, UntagPhrases};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::lesson::QueryLessonStatus;
+    use crate::core::backend::schedule::Day;
+    use crate::core::backend::{connect, CreateUser};
+    use crate::core::data::settings::{PracticeLimits, UserSettings};
+    use crate::core::data::NewPhrase;
+
+    #[test]
+    fn tags_filter_lesson_status() {
+        let mut conn = connect(&Config::in_memory()).unwrap();
+        let day = Day::new(2460310.5 + 10.0 / 24.0, 3);
+        let lesson_id = InsertLesson {
+            title: "Tags".to_string(),
+            owner: "admin".to_string(),
+            phrases: ["一", "二", "三"]
+                .into_iter()
+                .map(|prompt| NewPhrase {
+                    prompt: prompt.to_string(),
+                    tags: if prompt == "一" {
+                        vec!["numbers".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+        }
+        .apply(&mut conn)
+        .unwrap();
+        TagPhrases {
+            user_id: "admin".to_string(),
+            phrase_ids: vec![2],
+            tags: vec!["club".to_string(), "numbers".to_string()],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        UntagPhrases {
+            user_id: "admin".to_string(),
+            phrase_ids: vec![2, 3],
+            tags: vec!["club".to_string()],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let tags = QueryTags {
+            user_id: "admin".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(tags, vec!["numbers".to_string()]);
+
+        let count = |tag: Option<&str>| {
+            QueryLessonStatus {
+                lesson_id,
+                day,
+                limits: PracticeLimits::default(),
+                tag: tag.map(str::to_string),
+            }
+            .apply(&conn)
+            .unwrap()
+            .ready
+        };
+        assert_eq!(count(None), 3);
+        assert_eq!(count(Some("numbers")), 2);
+        assert_eq!(count(Some("club")), 0);
+
+        // Tagging another user's phrase doesn't leave the tag behind with no phrase.
+        CreateUser {
+            user_id: "kaito".to_string(),
+            is_admin: false,
+            settings: UserSettings::default(),
+        }
+        .apply(&conn)
+        .unwrap();
+        TagPhrases {
+            user_id: "kaito".to_string(),
+            phrase_ids: vec![1],
+            tags: vec!["borrowed".to_string()],
+        }
+        .apply(&mut conn)
+        .unwrap();
+        let tags = QueryTags {
+            user_id: "kaito".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert!(tags.is_empty());
+    }
+}
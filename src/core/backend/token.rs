@@ -0,0 +1,157 @@
+use crate::core::backend::StorageError;
+use crate::core::data::token::{ApiToken, NewApiToken};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sha2::{Digest, Sha256};
+
+/// Starts every token so that it's recognizable in scripts and shell history.
+const PREFIX: &str = "kk_";
+
+/// The columns `read_token` reads, with the UTC Julian day times formatted for display.
+const TOKEN_COLUMNS: &str = r#"
+id, name, strftime('%Y-%m-%d %H:%M UTC', created_at), strftime('%Y-%m-%d %H:%M UTC', last_used_at)
+"#;
+
+/// Only this hash of a token is stored, so a copy of the database can't be used to sign in.
+fn token_hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn read_token(row: &Row) -> rusqlite::Result<ApiToken> {
+    Ok(ApiToken {
+        token_id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+        last_used_at: row.get(3)?,
+    })
+}
+
+/// Makes a token for the user and returns it with its secret, which can't be read back later.
+pub struct CreateToken {
+    pub user_id: String,
+    pub name: String,
+}
+
+impl CreateToken {
+    pub fn apply(self, db: &Connection) -> Result<NewApiToken, StorageError> {
+        let sql = format!(
+            "INSERT INTO api_tokens (user_id, name, token_hash) VALUES (?1, ?2, ?3) RETURNING {TOKEN_COLUMNS}"
+        );
+        let secret = format!("{PREFIX}{}", hex::encode(rand::random::<[u8; 32]>()));
+        let token = db.query_row(
+            &sql,
+            params![self.user_id, self.name, token_hash(&secret)],
+            read_token,
+        )?;
+        Ok(NewApiToken { token, secret })
+    }
+}
+
+pub struct QueryTokens {
+    pub user_id: String,
+}
+
+impl QueryTokens {
+    pub fn apply(self, db: &Connection) -> Result<Vec<ApiToken>, StorageError> {
+        let sql = format!("SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE user_id = ?1 ORDER BY id");
+        let tokens = db
+            .prepare(&sql)?
+            .query_map(params![self.user_id], read_token)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tokens)
+    }
+}
+
+pub struct RevokeToken {
+    pub user_id: String,
+    pub token_id: i64,
+}
+
+impl RevokeToken {
+    pub fn apply(self, db: &Connection) -> Result<(), StorageError> {
+        const SQL: &str = "DELETE FROM api_tokens WHERE id = ?1 AND user_id = ?2";
+        if db.execute(SQL, params![self.token_id, self.user_id])? == 0 {
+            return Err(StorageError::NotFound("Token"));
+        }
+        Ok(())
+    }
+}
+
+/// The user a token's secret belongs to, noting when the token was last used.
+pub struct Authenticate {
+    pub secret: String,
+}
+
+impl Authenticate {
+    pub fn apply(self, db: &Connection) -> Result<String, StorageError> {
+        const SQL: &str = r#"
+UPDATE api_tokens
+SET last_used_at = julianday('now')
+WHERE token_hash = ?1
+RETURNING user_id
+"#;
+        db.query_row(SQL, params![token_hash(&self.secret)], |row| row.get(0))
+            .optional()?
+            .ok_or(StorageError::Unauthorized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Authenticate, CreateToken, QueryTokens, RevokeToken};
+    use crate::core::backend::config::Config;
+    use crate::core::backend::{connect, StorageError};
+
+    #[test]
+    fn revoked_tokens_stop_working() {
+        let conn = connect(&Config::in_memory()).unwrap();
+        let created = CreateToken {
+            user_id: "admin".to_string(),
+            name: "Browser".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert!(created.secret.starts_with("kk_"));
+        assert_eq!(created.token.last_used_at, None);
+
+        let user_id = Authenticate {
+            secret: created.secret.clone(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(user_id, "admin");
+        let tokens = QueryTokens {
+            user_id: "admin".to_string(),
+        }
+        .apply(&conn)
+        .unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].last_used_at.is_some());
+        assert!(tokens[0].created_at.ends_with(" UTC"));
+        let stored: String = conn
+            .query_row(
+                "SELECT typeof(created_at) || typeof(last_used_at) FROM api_tokens",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, "realreal");
+
+        let stranger = RevokeToken {
+            user_id: "someone".to_string(),
+            token_id: created.token.token_id,
+        }
+        .apply(&conn);
+        assert!(matches!(stranger, Err(StorageError::NotFound(_))));
+        RevokeToken {
+            user_id: "admin".to_string(),
+            token_id: created.token.token_id,
+        }
+        .apply(&conn)
+        .unwrap();
+        let revoked = Authenticate {
+            secret: created.secret,
+        }
+        .apply(&conn);
+        assert!(matches!(revoked, Err(StorageError::Unauthorized)));
+    }
+}
@@ -0,0 +1,171 @@
+//! Whole-account archives, for moving a user's data to another server or handing it to them
+//! before their account is deleted. The README describes the JSON format.
+
+use crate::core::data::error::ApiError;
+use crate::core::data::settings::UserSettings;
+use dioxus::fullstack::response::Response;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `format` of every account archive.
+pub const ARCHIVE_FORMAT: &str = "kanjikoto-account";
+
+/// The archive version this build writes and reads.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Everything a user has made and practiced. Times are Julian day numbers, as the database keeps
+/// them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountArchive {
+    pub format: String,
+    pub version: u32,
+    pub user_id: String,
+    /// When the archive was written, as an RFC 3339 timestamp.
+    pub exported_at: String,
+    pub settings: UserSettings,
+    pub lessons: Vec<ArchivedLesson>,
+}
+
+/// A lesson with its overrides of the user's practice settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedLesson {
+    pub title: String,
+    pub session_size: Option<usize>,
+    pub new_per_day: Option<usize>,
+    pub reviews_per_day: Option<usize>,
+    pub review_weight: Option<usize>,
+    pub phrases: Vec<ArchivedPhrase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedPhrase {
+    /// The phrase's id on the exporting server, which other phrases' `link_id` refers to.
+    pub id: i64,
+    pub prompt: String,
+    pub reading: String,
+    pub translation: String,
+    pub chapter: Option<usize>,
+    pub sentence: String,
+    pub source_url: Option<String>,
+    pub tags: Vec<String>,
+    pub content_changed_at: f64,
+    pub learned_at: Option<f64>,
+    pub lapses: usize,
+    pub is_leech: bool,
+    pub suspended: bool,
+    pub buried_until: Option<f64>,
+    /// The `id` of the phrase this one shares review state with, itself included.
+    pub link_id: Option<i64>,
+    /// Every review, oldest first.
+    pub reviews: Vec<ArchivedReview>,
+    /// When each lapse happened, oldest first.
+    pub lapsed_at: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedReview {
+    pub reviewed_at: f64,
+    pub kind: ReviewKind,
+    /// The phrase's `learned_at` before the review, which undo restores.
+    pub previous_learned_at: Option<f64>,
+}
+
+/// What a review was, as the review log records it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewKind {
+    /// The phrase's first review.
+    New,
+    /// A review of a phrase that was due.
+    Review,
+    /// Practice of a phrase before it was due.
+    Practice,
+}
+
+impl ReviewKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewKind::New => "new",
+            ReviewKind::Review => "review",
+            ReviewKind::Practice => "practice",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new" => Some(ReviewKind::New),
+            "review" => Some(ReviewKind::Review),
+            "practice" => Some(ReviewKind::Practice),
+            _ => None,
+        }
+    }
+}
+
+/// Downloads the caller's account archive, or as an admin, `user_id`'s.
+#[get("/api/account/export?user_id", headers: dioxus::fullstack::HeaderMap)]
+pub async fn export_account(user_id: Option<String>) -> Result<Response, ApiError> {
+    use crate::core::backend::account::ExportAccount;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    use dioxus::fullstack::body::Body;
+    use dioxus::fullstack::http::header;
+    let caller = caller(&headers).await?;
+    let user_id = user_id.unwrap_or_else(|| caller.clone());
+    let archive = DB
+        .read(move |db| {
+            if user_id != caller {
+                require_admin(db, &caller, "export other users' accounts")?;
+            }
+            ExportAccount {
+                user_id,
+                clock: &CLOCK,
+            }
+            .apply(db)
+        })
+        .await?;
+    let json = serde_json::to_string_pretty(&archive)?;
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"kanjikoto-{}.json\"",
+                archive.user_id
+            ),
+        )
+        .body(Body::from(json))?;
+    Ok(response)
+}
+
+/// Adds an archive's lessons to the caller's and takes its settings, returning the new lessons'
+/// ids.
+#[post("/api/account/import", headers: dioxus::fullstack::HeaderMap)]
+pub async fn import_account(archive: AccountArchive) -> Result<Vec<i64>, ApiError> {
+    use crate::core::backend::account::ImportAccount;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let user_id = caller(&headers).await?;
+    let lesson_ids = DB
+        .write(move |db| ImportAccount { user_id, archive }.apply(db))
+        .await?;
+    Ok(lesson_ids)
+}
+
+/// Deletes another user with everything they made. Only admins may.
+#[post("/api/account/delete", headers: dioxus::fullstack::HeaderMap)]
+pub async fn delete_account(user_id: String) -> Result<(), ApiError> {
+    use crate::core::backend::account::DeleteAccount;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let caller = caller(&headers).await?;
+    DB.write(move |db| {
+        require_admin(db, &caller, "delete accounts")?;
+        DeleteAccount {
+            user_id,
+            admin: config().admin.clone(),
+        }
+        .apply(db)
+    })
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A backup in the configured backup directory.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub bytes: u64,
+    pub taken_at: String,
+}
+
+/// The backups the server has taken, newest first. Only admins may list them.
+#[get("/api/backups", headers: dioxus::fullstack::HeaderMap)]
+pub async fn backups() -> Result<Vec<BackupFile>, ApiError> {
+    use crate::core::backend::backup::list_backups;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let user_id = caller(&headers).await?;
+    let backups = DB
+        .read(move |db| {
+            require_admin(db, &user_id, "list backups")?;
+            list_backups(&config().backup.directory)
+        })
+        .await?;
+    Ok(backups)
+}
+
+/// Takes a backup now, rotating out the oldest as the automatic backups do.
+#[post("/api/backups", headers: dioxus::fullstack::HeaderMap)]
+pub async fn create_backup() -> Result<BackupFile, ApiError> {
+    use crate::core::backend::backup::SnapshotDatabase;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let user_id = caller(&headers).await?;
+    let backup = DB
+        .read(move |db| {
+            require_admin(db, &user_id, "take backups")?;
+            SnapshotDatabase {
+                directory: config().backup.directory.clone(),
+                keep: config().backup.keep,
+                clock: &CLOCK,
+            }
+            .apply(db)
+        })
+        .await?;
+    Ok(backup)
+}
+
+/// Replaces all data with the backup named `name`, returning the backup taken of the data it
+/// replaced. Only admins may.
+#[post("/api/backups/restore", headers: dioxus::fullstack::HeaderMap)]
+pub async fn restore_backup(name: String) -> Result<BackupFile, ApiError> {
+    use crate::core::backend::backup::{backup_path, RestoreDatabase};
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let user_id = caller(&headers).await?;
+    let replaced = DB
+        .write(move |db| {
+            require_admin(db, &user_id, "restore backups")?;
+            let path = backup_path(&config().backup.directory, &name)?;
+            RestoreDatabase {
+                path,
+                directory: config().backup.directory.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)
+        })
+        .await?;
+    Ok(replaced)
+}
@@ -0,0 +1,80 @@
+use crate::core::data::error::ApiError;
+use crate::core::data::PhraseView;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ConfusionKind {
+    Homophone,
+    LookAlike,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConfusableSet {
+    pub kind: ConfusionKind,
+    pub key: String,
+    pub phrases: Vec<PhraseView>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Confusion {
+    pub expected: PhraseView,
+    pub chosen: PhraseView,
+    pub count: usize,
+}
+
+#[get("/api/confusables")]
+pub async fn query_confusables() -> Result<Vec<ConfusableSet>, ApiError> {
+    use crate::core::backend::confusion::QueryConfusables;
+    use crate::core::data::db::prelude::*;
+    let sets = DB
+        .read(move |db| {
+            let sets = QueryConfusables {
+                owner: config().admin.clone(),
+            }
+            .apply(db)?;
+            Ok(sets)
+        })
+        .await?;
+    Ok(sets)
+}
+
+#[get("/api/confusions")]
+pub async fn query_confusions() -> Result<Vec<Confusion>, ApiError> {
+    use crate::core::backend::confusion::QueryConfusions;
+    use crate::core::data::db::prelude::*;
+    let confusions = DB
+        .read(move |db| {
+            let confusions = QueryConfusions {
+                owner: config().admin.clone(),
+            }
+            .apply(db)?;
+            Ok(confusions)
+        })
+        .await?;
+    Ok(confusions)
+}
+
+#[server]
+pub async fn record_confusion(expected_id: i64, chosen_id: i64) -> Result<(), ApiError> {
+    use crate::core::backend::confusion::RecordConfusion;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    DB.write(move |db| {
+        let day = QueryUserDay {
+            user_id: config().admin.clone(),
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        RecordConfusion {
+            owner: config().admin.clone(),
+            expected_id,
+            chosen_id,
+            now: day.now,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
@@ -1,12 +1,93 @@
-use crate::core::backend;
+use crate::core::backend::backup::{newest_backup_age, SnapshotDatabase};
+use crate::core::backend::clock::SystemClock;
+use crate::core::backend::config::{Config, ConfigError};
+use crate::core::backend::dictionary::Dictionary;
+use crate::core::backend::pool::Pool;
+use crate::core::backend::StorageError;
 use dioxus::fullstack::Lazy;
-use dioxus::prelude::info;
-use std::sync::Mutex;
+use dioxus::prelude::{error, info};
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 pub mod prelude;
 
-pub static DB: Lazy<Mutex<rusqlite::Connection>> = Lazy::new(|| async move {
-    let conn = backend::connect(Some("data/kanjikoto.db"));
-    info!("Database path: {}", conn.path().unwrap());
-    dioxus::Ok(Mutex::new(conn))
+/// Read-only connections, enough for a few people practicing at once.
+const READERS: usize = 4;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+static DICTIONARY: OnceLock<Option<Dictionary>> = OnceLock::new();
+
+/// Loads and validates the server configuration. Call once at startup, before `config`.
+pub fn init_config() -> Result<&'static Config, ConfigError> {
+    let config = Config::load()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// The server configuration, loaded on first use if `init_config` wasn't called.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(|| Config::load().expect("Invalid configuration"))
+}
+
+/// The configured quick-add dictionary, read on first use, or `None` when there isn't one.
+pub fn dictionary() -> Result<Option<&'static Dictionary>, StorageError> {
+    if let Some(dictionary) = DICTIONARY.get() {
+        return Ok(dictionary.as_ref());
+    }
+    let path = config().quick_add.dictionary.as_deref();
+    let dictionary = path.map(Dictionary::load).transpose()?;
+    Ok(DICTIONARY.get_or_init(|| dictionary).as_ref())
+}
+
+pub static DB: Lazy<Pool> = Lazy::new(|| async move {
+    let pool = Pool::open(config(), READERS)?;
+    info!("Database path: {}", pool.path().unwrap_or_default());
+    dioxus::Ok(pool)
 });
+
+/// The clock read by server functions.
+pub static CLOCK: SystemClock = SystemClock;
+
+/// Starts a thread that backs the database up every `backup.interval_hours`. The first backup
+/// is due an interval after the newest one in the directory, so restarts don't delay or repeat it.
+pub fn spawn_backups() {
+    let backup = &config().backup;
+    if backup.interval_hours == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(backup.interval_hours * 3600);
+    // Leaves a new server time to create the database before the first backup.
+    let mut wait = newest_backup_age(&backup.directory)
+        .map_or(Duration::from_secs(60), |age| interval.saturating_sub(age));
+    let spawned = std::thread::Builder::new()
+        .name("backups".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(wait);
+            match snapshot() {
+                Ok(name) => info!("Backed up the database to {name}"),
+                Err(err) => error!("Automatic backup failed: {err}"),
+            }
+            wait = interval;
+        });
+    if let Err(err) = spawned {
+        error!("Failed to start automatic backups: {err}");
+    }
+}
+
+/// Takes a rotated backup through a read-only connection of its own, leaving the pool free.
+fn snapshot() -> Result<String, StorageError> {
+    let config = config();
+    if !Path::new(&config.database).exists() {
+        return Err(StorageError::NotFound("Database"));
+    }
+    let conn = Connection::open_with_flags(&config.database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let backup = SnapshotDatabase {
+        directory: config.backup.directory.clone(),
+        keep: config.backup.keep,
+        clock: &CLOCK,
+    }
+    .apply(&conn)?;
+    Ok(backup.name)
+}
@@ -1,3 +1,3 @@
 #![allow(unused_imports)]
 pub use crate::core::backend::*;
-pub use super::DB;
+pub use super::{config, CLOCK, DB};
@@ -0,0 +1,239 @@
+use crate::core::data::card::{Card, Goal};
+use rand::prelude::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Answers remembered for undo.
+const UNDO_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub learned: usize,
+    pub failed: usize,
+    pub repeated: usize,
+    pub passed: usize,
+}
+
+/// What was done with the top card.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Answer {
+    Learn,
+    Fail,
+    Repeat,
+    Pass,
+    Bury,
+    Suspend,
+}
+
+/// The deck as it was before an answer.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct Snapshot {
+    answer: Answer,
+    rng: ChaCha8Rng,
+    top: Card,
+    cards: Vec<Card>,
+    stats: Stats,
+}
+
+/// The deck left by `remove_top`.
+#[derive(Debug)]
+pub enum Removal {
+    /// The deck with the next card on top.
+    Next(Deck),
+    /// The deck with the answer remembered, when the removed card was its last, so that the
+    /// session can still be summed up and the answer undone.
+    Emptied(Deck),
+}
+
+/// Serializes with its RNG state so that a stored session resumes with the same card order.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Deck {
+    pub rng: ChaCha8Rng,
+    pub top: Card,
+    cards: Vec<Card>,
+    pub stats: Stats,
+    #[serde(default)]
+    undo: VecDeque<Snapshot>,
+}
+
+impl Deck {
+    pub fn from_cards(mut cards: Vec<Card>, rng: ChaCha8Rng) -> Self {
+        let top = cards.pop().unwrap();
+        Self {
+            rng,
+            top,
+            cards,
+            stats: Stats {
+                learned: 0,
+                failed: 0,
+                repeated: 0,
+                passed: 0,
+            },
+            undo: VecDeque::new(),
+        }
+    }
+    pub fn into_cards(self) -> Vec<Card> {
+        let mut cards = self.cards;
+        cards.push(self.top);
+        cards
+    }
+    pub fn is_all_passed(&self) -> bool {
+        self.stats.passed == self.cards.len() + 1 /* for the top card */
+    }
+    pub fn turns_remaining(&self) -> usize {
+        let card_turns = self
+            .cards
+            .iter()
+            .fold(0, |acc, card| acc + card.turns_remaining());
+        self.top.turns_remaining() + card_turns
+    }
+    pub fn learn(mut self) -> Self {
+        self.remember(Answer::Learn);
+        self.stats.learned += 1;
+        self.top.goal = Goal::Learn;
+        self.cycle_top()
+    }
+
+    pub fn fail(mut self) -> Self {
+        self.remember(Answer::Fail);
+        self.stats.failed += 1;
+        self.top.goal = Goal::Learn;
+        self.cycle_top()
+    }
+    pub fn repeat(mut self) -> Self {
+        self.remember(Answer::Repeat);
+        self.stats.repeated += 1;
+        self.top.goal = Goal::Review;
+        self.cycle_top()
+    }
+    pub fn pass(mut self) -> Self {
+        self.remember(Answer::Pass);
+        self.stats.passed += 1;
+        self.top.goal = Goal::Celebrate;
+        self.cycle_top()
+    }
+    /// Takes the top card out of the session.
+    pub fn remove_top(mut self, answer: Answer) -> Removal {
+        self.remember(answer);
+        if self.cards.is_empty() {
+            return Removal::Emptied(self);
+        }
+        self.cards.shuffle(&mut self.rng);
+        let position = self
+            .cards
+            .iter()
+            .position(|card| card.goal != Goal::Celebrate)
+            .unwrap_or(0);
+        self.top = self.cards.remove(position);
+        Removal::Next(self)
+    }
+    /// The answer that `undo` would take back.
+    pub fn last_answer(&self) -> Option<Answer> {
+        self.undo.back().map(|snapshot| snapshot.answer)
+    }
+    /// Restores the top card, goals and stats from before the last answer.
+    pub fn undo(mut self) -> Self {
+        if let Some(snapshot) = self.undo.pop_back() {
+            self.rng = snapshot.rng;
+            self.top = snapshot.top;
+            self.cards = snapshot.cards;
+            self.stats = snapshot.stats;
+        }
+        self
+    }
+    fn remember(&mut self, answer: Answer) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(Snapshot {
+            answer,
+            rng: self.rng.clone(),
+            top: self.top.clone(),
+            cards: self.cards.clone(),
+            stats: self.stats.clone(),
+        });
+    }
+    fn cycle_top(mut self) -> Self {
+        self.cards.shuffle(&mut self.rng);
+        if let Some(position) = self
+            .cards
+            .iter()
+            .position(|card| card.goal != Goal::Celebrate)
+        {
+            let new_top = self.cards.remove(position);
+            self.cards.push(self.top);
+            self.top = new_top;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Answer, Deck, Removal, UNDO_LIMIT};
+    use crate::core::data::card::{Card, CardBack, CardFront, Goal};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn deck() -> Deck {
+        let cards = (0..3)
+            .map(|id| Card {
+                id,
+                goal: Goal::Review,
+                front: CardFront {
+                    kanji: id.to_string(),
+                },
+                back: CardBack {
+                    yomi: String::new(),
+                    meaning: String::new(),
+                },
+            })
+            .collect();
+        Deck::from_cards(cards, ChaCha8Rng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn undo_restores_the_deck_before_the_answer() {
+        let start = deck();
+        let passed = start.clone().pass();
+        assert_eq!(passed.last_answer(), Some(Answer::Pass));
+        let undone = passed.clone().undo();
+        assert_eq!(undone.top, start.top);
+        assert_eq!(undone.stats, start.stats);
+        assert_eq!(undone.last_answer(), None);
+        // The same answer shuffles the same way again.
+        assert_eq!(undone.pass(), passed);
+    }
+
+    #[test]
+    fn removing_the_last_card_keeps_the_stats() {
+        let mut deck = deck().repeat();
+        for _ in 0..2 {
+            let Removal::Next(next) = deck.remove_top(Answer::Bury) else {
+                panic!("Cards remain");
+            };
+            deck = next;
+        }
+        let Removal::Emptied(emptied) = deck.repeat().remove_top(Answer::Suspend) else {
+            panic!("No cards remain");
+        };
+        assert_eq!(emptied.stats.repeated, 2);
+        assert_eq!(emptied.last_answer(), Some(Answer::Suspend));
+        assert_eq!(emptied.undo().last_answer(), Some(Answer::Repeat));
+    }
+
+    #[test]
+    fn undo_is_bounded() {
+        let mut deck = deck();
+        for _ in 0..UNDO_LIMIT + 5 {
+            deck = deck.repeat();
+        }
+        for _ in 0..UNDO_LIMIT {
+            assert_eq!(deck.last_answer(), Some(Answer::Repeat));
+            deck = deck.undo();
+        }
+        assert_eq!(deck.last_answer(), None);
+        assert_eq!(deck.stats.repeated, 5);
+    }
+}
@@ -0,0 +1,119 @@
+use dioxus::fullstack::{AsStatusCode, ServerFnError, StatusCode};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why a server function failed, sent to the client with a matching HTTP status.
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ApiError {
+    #[error("{0}")]
+    NotFound(String),
+    /// The request carried no API token, or one that isn't known.
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Import(String),
+    #[error("{0}")]
+    Storage(String),
+    /// The server couldn't be reached or its reply couldn't be read.
+    #[error("{0}")]
+    Server(String),
+}
+
+impl ApiError {
+    /// A short heading for the error, to show above its message.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "Not found",
+            Self::Unauthorized(_) => "Token needed",
+            Self::Forbidden(_) => "Not allowed",
+            Self::Invalid(_) => "Check your input",
+            Self::Conflict(_) => "Try again",
+            Self::Import(_) => "Import failed",
+            Self::Storage(_) => "Something went wrong",
+            Self::Server(_) => "Can't reach the server",
+        }
+    }
+}
+
+impl AsStatusCode for ApiError {
+    fn as_status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Import(_) => StatusCode::BAD_GATEWAY,
+            Self::Storage(_) | Self::Server(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<ServerFnError> for ApiError {
+    fn from(err: ServerFnError) -> Self {
+        match err {
+            ServerFnError::ServerError { message, .. } => Self::Server(message),
+            err => Self::Server(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<crate::core::backend::StorageError> for ApiError {
+    fn from(err: crate::core::backend::StorageError) -> Self {
+        use crate::core::backend::StorageError;
+        use rusqlite::ErrorCode;
+        match err {
+            StorageError::NotFound(_) => Self::NotFound(err.to_string()),
+            StorageError::Unauthorized => Self::Unauthorized(err.to_string()),
+            StorageError::AdminOnly(_) => Self::Forbidden(err.to_string()),
+            StorageError::TimeZone(_)
+            | StorageError::Date(_)
+            | StorageError::DuplicateKey(_)
+            | StorageError::TooManyRows { .. }
+            | StorageError::Backup(_)
+            | StorageError::Archive(_)
+            | StorageError::DeleteAdmin(_) => Self::Invalid(err.to_string()),
+            StorageError::Import(_) => Self::Import(err.to_string()),
+            StorageError::Dictionary(_) | StorageError::Task(_) => Self::internal(err),
+            StorageError::Sqlite(ref sqlite) => match sqlite.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) => {
+                    Self::Conflict("That conflicts with data already saved.".to_string())
+                }
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+                    Self::Conflict("The database is busy, please try again.".to_string())
+                }
+                _ => Self::internal(err),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<serde_json::Error> for ApiError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::internal(err)
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<dioxus::fullstack::http::Error> for ApiError {
+    fn from(err: dioxus::fullstack::http::Error) -> Self {
+        Self::internal(err)
+    }
+}
+
+#[cfg(feature = "server")]
+impl ApiError {
+    /// Logs a failure the user can't fix and hides its details from them.
+    fn internal(err: impl std::fmt::Display) -> Self {
+        dioxus::prelude::error!("{err}");
+        Self::Storage("Your data couldn't be read or saved.".to_string())
+    }
+}
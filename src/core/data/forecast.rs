@@ -0,0 +1,92 @@
+use crate::core::data::error::ApiError;
+use dioxus::fullstack::response::Response;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Phrases projected to come due on one practice day.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DueDay {
+    /// The practice day as YYYY-MM-DD.
+    pub date: String,
+    pub new: usize,
+    pub reviews: usize,
+}
+
+impl DueDay {
+    pub fn total(&self) -> usize {
+        self.new + self.reviews
+    }
+}
+
+#[get("/api/lessons/{lesson_id}/forecast?days")]
+pub async fn lesson_forecast(lesson_id: i64, days: usize) -> Result<Vec<DueDay>, ApiError> {
+    use crate::core::backend::lesson::forecast::QueryForecast;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::backend::settings::QueryPracticeLimits;
+    use crate::core::data::db::prelude::*;
+    let forecast = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let limits = QueryPracticeLimits {
+                user_id: config().admin.clone(),
+                lesson_id,
+            }
+            .apply(db)?;
+            let forecast = QueryForecast {
+                lesson_id,
+                day,
+                limits,
+                days: days.min(366),
+            }
+            .apply(db)?;
+            Ok(forecast)
+        })
+        .await?;
+    Ok(forecast)
+}
+
+/// The forecast summed over all the user's lessons.
+#[get("/api/forecast?days")]
+pub async fn forecast(days: usize) -> Result<Vec<DueDay>, ApiError> {
+    use crate::core::backend::lesson::forecast::QueryUserForecast;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let forecast = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let forecast = QueryUserForecast {
+                user_id: config().admin.clone(),
+                day,
+                days: days.min(366),
+            }
+            .apply(db)?;
+            Ok(forecast)
+        })
+        .await?;
+    Ok(forecast)
+}
+
+/// The overall forecast as a calendar file.
+#[get("/api/forecast.ics?days")]
+pub async fn forecast_calendar(days: usize) -> Result<Response, ApiError> {
+    use crate::core::backend::lesson::forecast::to_icalendar;
+    use dioxus::fullstack::body::Body;
+    use dioxus::fullstack::http::header;
+    let calendar = to_icalendar(&forecast(days).await?);
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"kanjikoto.ics\"",
+        )
+        .body(Body::from(calendar))?;
+    Ok(response)
+}
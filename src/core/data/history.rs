@@ -0,0 +1,85 @@
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Practice totals for one practice day.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DailyStats {
+    /// The practice day as YYYY-MM-DD.
+    pub date: String,
+    pub reviews: usize,
+    /// Phrases answered for the first time.
+    pub new_cards: usize,
+    pub sessions: usize,
+    pub learned: usize,
+    pub failed: usize,
+    pub repeated: usize,
+    pub passed: usize,
+    /// Time spent in sessions.
+    pub seconds: u64,
+}
+
+impl DailyStats {
+    /// Passes as a share of graded answers, or `None` without any.
+    pub fn pass_rate(&self) -> Option<f64> {
+        let graded = self.passed + self.failed + self.repeated;
+        (graded > 0).then(|| self.passed as f64 / graded as f64)
+    }
+}
+
+/// A finished practice session.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: i64,
+    pub lesson_title: String,
+    /// Local time the session finished, as YYYY-MM-DD HH:MM.
+    pub finished_at: String,
+    pub learned: usize,
+    pub failed: usize,
+    pub repeated: usize,
+    pub passed: usize,
+    pub seconds: u64,
+    /// Whether the session drilled phrases without scheduling them.
+    pub cram: bool,
+}
+
+#[get("/api/stats/daily?days")]
+pub async fn daily_stats(days: usize) -> Result<Vec<DailyStats>, ApiError> {
+    use crate::core::backend::history::QueryDailyStats;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let stats = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let stats = QueryDailyStats {
+                user_id: config().admin.clone(),
+                day,
+                days: days.min(366),
+            }
+            .apply(db)?;
+            Ok(stats)
+        })
+        .await?;
+    Ok(stats)
+}
+
+#[get("/api/stats/sessions?limit")]
+pub async fn session_history(limit: usize) -> Result<Vec<SessionSummary>, ApiError> {
+    use crate::core::backend::history::QuerySessionHistory;
+    use crate::core::data::db::prelude::*;
+    let sessions = DB
+        .read(move |db| {
+            let sessions = QuerySessionHistory {
+                user_id: config().admin.clone(),
+                limit,
+            }
+            .apply(db)?;
+            Ok(sessions)
+        })
+        .await?;
+    Ok(sessions)
+}
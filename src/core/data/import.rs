@@ -0,0 +1,85 @@
+use crate::core::data::error::ApiError;
+use crate::core::data::ImportDetails;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What an import does with a row whose prompt and reading match an existing phrase or an
+/// earlier row.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub enum DuplicatePolicy {
+    /// Leaves the row out.
+    #[default]
+    Skip,
+    /// Adds the row's meaning, sentence and tags to the earlier phrase.
+    Merge,
+    /// Imports the row as a phrase that shares the earlier phrase's review state.
+    Link,
+    /// Imports the row as an independent phrase.
+    Keep,
+}
+
+impl DuplicatePolicy {
+    pub const ALL: [DuplicatePolicy; 4] = [Self::Skip, Self::Merge, Self::Link, Self::Keep];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Merge => "merge",
+            Self::Link => "link",
+            Self::Keep => "keep",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Skip => "Skip duplicates",
+            Self::Merge => "Merge into the earlier phrase",
+            Self::Link => "Link to share review history",
+            Self::Keep => "Import as separate phrases",
+        }
+    }
+}
+
+/// An import row repeating an earlier phrase.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Duplicate {
+    pub prompt: String,
+    pub reading: String,
+    pub chapter: Option<usize>,
+    /// The lesson holding the earlier phrase, or `None` when an earlier row of the import repeats it.
+    pub lesson_title: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub rows: usize,
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// Reads the sheet without importing it, listing the rows that repeat earlier phrases.
+#[post("/api/import_csv/preview")]
+pub async fn preview_import(details: ImportDetails) -> Result<ImportPreview, ApiError> {
+    use crate::core::api::get_drills_url;
+    use crate::core::backend::import::QueryDuplicates;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::new_phrases;
+    let drills = get_drills_url(details.csv_url.trim()).await?;
+    let phrases = new_phrases(drills, None)?;
+    let rows = phrases.len();
+    let duplicates = DB
+        .read(move |db| {
+            QueryDuplicates {
+                user_id: config().admin.clone(),
+                phrases: &phrases,
+            }
+            .apply(db)
+        })
+        .await?;
+    Ok(ImportPreview { rows, duplicates })
+}
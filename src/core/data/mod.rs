@@ -1,21 +1,39 @@
-use crate::core::api::get_drills_url;
 use crate::core::data::card::Card;
+use crate::core::data::error::ApiError;
+use crate::core::data::import::DuplicatePolicy;
 use dioxus::prelude::*;
 use lesson_status::LessonStatus;
 use serde::{Deserialize, Serialize};
 
+pub mod account;
+pub mod backup;
 pub mod card;
+pub mod confusion;
 #[cfg(feature = "server")]
 pub mod db;
+pub mod deck;
+pub mod error;
+pub mod forecast;
+pub mod history;
+pub mod import;
 pub mod lesson_status;
+pub mod openapi;
+pub mod practice_session;
+pub mod progress;
+pub mod rest;
+pub mod search;
+pub mod settings;
+pub mod study;
+pub mod tag;
+pub mod token;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
     pub is_admin: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Lesson {
     pub lesson_id: i64,
     pub title: String,
@@ -27,7 +45,14 @@ pub struct NewPhrase {
     pub prompt: String,
     pub reading: String,
     pub translation: String,
+    /// The chapter of the source text the phrase comes from.
+    pub chapter: Option<usize>,
+    /// An example sentence using the phrase.
+    pub sentence: String,
     pub content_changed_at: Option<f64>,
+    pub tags: Vec<String>,
+    /// The page the phrase was found on, for phrases quick-added while reading.
+    pub source_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,22 +62,36 @@ pub struct Phrase {
     pub prompt: String,
     pub reading: String,
     pub translation: String,
+    pub lapses: usize,
+    pub is_leech: bool,
+    pub suspended: bool,
+    pub buried_until: Option<f64>,
+    pub tags: Vec<String>,
 }
 
 #[get("/api/users")]
-pub async fn users() -> Result<Vec<User>> {
+pub async fn users() -> Result<Vec<User>, ApiError> {
     use db::prelude::*;
-    let db = DB.lock().unwrap();
-    let users = get_users(&db);
+    let users = DB
+        .read(move |db| {
+            let users = get_users(db)?;
+            Ok(users)
+        })
+        .await?;
     Ok(users)
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Store)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Store, Default)]
 pub struct PhraseView {
     pub phrase_id: i64,
     pub prompt: String,
     pub reading: String,
     pub meaning: String,
+    pub lapses: usize,
+    pub is_leech: bool,
+    pub suspended: bool,
+    pub buried: bool,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Store)]
@@ -61,95 +100,360 @@ pub struct LessonView {
     pub title: String,
     pub phrases: Vec<PhraseView>,
 }
-#[get("/api/lesson_view")]
-pub async fn lesson_view() -> Result<Option<LessonView>> {
+/// The user's lesson with `lesson_id`, or their first lesson when `None`.
+#[get("/api/lesson_view?lesson_id")]
+pub async fn lesson_view(lesson_id: Option<i64>) -> Result<Option<LessonView>, ApiError> {
     use db::prelude::*;
-    let db = DB.lock().expect("Failed to lock the database");
-    if let Some(lesson) = read_user_lesson("admin", &db)? {
-        let phrases = read_phrases(lesson.lesson_id, &db)?
-            .into_iter()
-            .map(|p| PhraseView {
-                phrase_id: p.phrase_id,
-                prompt: p.prompt,
-                reading: p.reading,
-                meaning: p.translation,
-            })
-            .collect::<Vec<_>>();
-        let lesson_view = LessonView {
-            lesson_id: lesson.lesson_id,
-            title: lesson.title,
-            phrases,
-        };
-        Ok(Some(lesson_view))
-    } else {
-        Ok(None)
+    let lesson_view = DB
+        .read(move |db| {
+            let lesson = match lesson_id {
+                Some(lesson_id) => read_user_lessons(&config().admin, db)?
+                    .into_iter()
+                    .find(|lesson| lesson.lesson_id == lesson_id),
+                None => read_user_lesson(&config().admin, db)?,
+            };
+            let lesson = match (lesson, lesson_id) {
+                (Some(lesson), _) => lesson,
+                (None, Some(_)) => return Err(StorageError::NotFound("Lesson")),
+                (None, None) => return Ok(None),
+            };
+            let phrases = phrase_views(&config().admin, lesson.lesson_id, db)?;
+            Ok(Some(LessonView {
+                lesson_id: lesson.lesson_id,
+                title: lesson.title,
+                phrases,
+            }))
+        })
+        .await?;
+    Ok(lesson_view)
+}
+
+/// The lesson's phrases as the user sees them today.
+#[cfg(feature = "server")]
+pub(crate) fn phrase_views(
+    user_id: &str,
+    lesson_id: i64,
+    db: &rusqlite::Connection,
+) -> Result<Vec<PhraseView>, crate::core::backend::StorageError> {
+    use crate::core::backend::read_phrases;
+    use crate::core::backend::schedule::QueryUserDay;
+    let day = QueryUserDay {
+        user_id: user_id.to_string(),
+        clock: &db::CLOCK,
     }
+    .apply(db)?;
+    let phrases = read_phrases(lesson_id, db)?
+        .into_iter()
+        .map(|p| PhraseView {
+            phrase_id: p.phrase_id,
+            prompt: p.prompt,
+            reading: p.reading,
+            meaning: p.translation,
+            lapses: p.lapses,
+            is_leech: p.is_leech,
+            suspended: p.suspended,
+            buried: p.buried_until.is_some_and(|until| until > day.now),
+            tags: p.tags,
+        })
+        .collect();
+    Ok(phrases)
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ImportDetails {
     pub csv_url: String,
+    pub duplicates: DuplicatePolicy,
+}
+
+#[get("/api/lessons")]
+pub async fn user_lessons() -> Result<Vec<Lesson>, ApiError> {
+    use db::prelude::*;
+    let lessons = DB
+        .read(move |db| {
+            let lessons = read_user_lessons(&config().admin, db)?;
+            Ok(lessons)
+        })
+        .await?;
+    Ok(lessons)
 }
 
+/// Imports the sheet into a new lesson, returning `None` when every row was a duplicate skipped or
+/// merged, so no lesson was made.
 #[post("/api/import_csv")]
-pub async fn import_csv(details: ImportDetails) -> Result<i64> {
+pub async fn import_csv(details: ImportDetails) -> Result<Option<i64>, ApiError> {
+    use crate::core::api::get_drills_url;
+    use crate::core::backend::import::ImportLesson;
     use crate::core::backend::insert_lesson::InsertLesson;
+    use crate::core::backend::schedule::QueryUserDay;
     use db::prelude::*;
 
     let csv_url = details.csv_url.trim();
-    let drills = get_drills_url(csv_url).await;
-    let phrases = drills
+    let drills = get_drills_url(csv_url).await?;
+    let lesson_id = DB
+        .write(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let import_lesson = ImportLesson {
+                lesson: InsertLesson {
+                    title: "Let this Grieving Soul Retire 1".to_string(),
+                    owner: config().admin.clone(),
+                    phrases: new_phrases(drills, Some(day.now))?,
+                },
+                duplicates: details.duplicates,
+            };
+            let lesson_id = import_lesson.apply(db)?;
+            Ok(lesson_id)
+        })
+        .await?;
+    Ok(lesson_id)
+}
+
+/// The sheet's rows as phrases, refusing sheets over the configured row limit.
+#[cfg(feature = "server")]
+pub fn new_phrases(
+    drills: Vec<crate::core::api::DrillPoint>,
+    content_changed_at: Option<f64>,
+) -> Result<Vec<NewPhrase>, crate::core::backend::StorageError> {
+    db::config().import.check_rows(drills.len())?;
+    Ok(drills
         .into_iter()
         .map(|d| NewPhrase {
             prompt: d.kanji,
             reading: d.yomi,
             translation: d.meaning,
-            content_changed_at: None,
+            chapter: Some(d.chapter),
+            sentence: d.sentence,
+            content_changed_at,
+            tags: d.tags,
+            source_url: None,
         })
-        .collect::<Vec<_>>();
-    let insert_lesson = InsertLesson {
-        title: "Let this Grieving Soul Retire 1".to_string(),
-        owner: "admin".to_string(),
-        phrases,
-    };
-    let mut db = DB.lock().expect("Failed to lock the database");
-    let lesson_id = insert_lesson.apply(&mut db)?;
-    Ok(lesson_id)
+        .collect())
 }
 
+/// Counts the lesson's phrases, or only those tagged `tag`.
 #[server]
-pub async fn query_lesson_status(lesson_id: i64) -> Result<LessonStatus> {
+pub async fn query_lesson_status(
+    lesson_id: i64,
+    tag: Option<String>,
+) -> Result<LessonStatus, ApiError> {
     use crate::core::backend::lesson::QueryLessonStatus;
-    use crate::core::backend::misc::now_localtime;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::backend::settings::QueryPracticeLimits;
     use db::prelude::*;
-    let db = DB.lock().expect("Failed to lock the database");
-    let now = now_localtime(&db)?;
-    let status = QueryLessonStatus { lesson_id, now }.apply(&db)?;
+    let status = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let limits = QueryPracticeLimits {
+                user_id: config().admin.clone(),
+                lesson_id,
+            }
+            .apply(db)?;
+            let status = QueryLessonStatus {
+                lesson_id,
+                day,
+                limits,
+                tag,
+            }
+            .apply(db)?;
+            Ok(status)
+        })
+        .await?;
     Ok(status)
 }
 
 #[server]
-pub async fn query_practice_cards(lesson_id: i64) -> Result<Vec<Card>> {
+pub async fn query_practice_cards(
+    lesson_id: i64,
+    tag: Option<String>,
+) -> Result<Vec<Card>, ApiError> {
+    use db::prelude::*;
+    let cards = DB
+        .read(move |db| practice_cards(&config().admin, lesson_id, tag, db))
+        .await?;
+    Ok(cards)
+}
+
+/// The lesson's cards due today within the user's practice limits.
+#[cfg(feature = "server")]
+pub(crate) fn practice_cards(
+    user_id: &str,
+    lesson_id: i64,
+    tag: Option<String>,
+    db: &rusqlite::Connection,
+) -> Result<Vec<Card>, crate::core::backend::StorageError> {
     use crate::core::backend::lesson::QueryPracticeCards;
-    use crate::core::backend::misc::now_localtime;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::backend::settings::QueryPracticeLimits;
+    let day = QueryUserDay {
+        user_id: user_id.to_string(),
+        clock: &db::CLOCK,
+    }
+    .apply(db)?;
+    let limits = QueryPracticeLimits {
+        user_id: user_id.to_string(),
+        lesson_id,
+    }
+    .apply(db)?;
+    QueryPracticeCards {
+        lesson_id,
+        day,
+        limits,
+        tag,
+    }
+    .apply(db)
+}
+
+/// Cards due across all of the user's lessons, weighted by each lesson's review weight.
+#[server]
+pub async fn query_review_cards() -> Result<Vec<Card>, ApiError> {
+    use crate::core::backend::lesson::review::QueryReviewCards;
+    use crate::core::backend::schedule::QueryUserDay;
     use db::prelude::*;
-    let db = DB.lock().expect("Failed to lock the database");
-    let now = now_localtime(&db)?;
-    let cards = QueryPracticeCards { lesson_id, now }.apply(&db)?;
+    let cards = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let cards = QueryReviewCards {
+                user_id: config().admin.clone(),
+                day,
+            }
+            .apply(db)?;
+            Ok(cards)
+        })
+        .await?;
     Ok(cards)
 }
 
 #[server]
-pub async fn update_practice_cards(cards: Vec<Card>) -> Result<()> {
+pub async fn update_practice_cards(cards: Vec<Card>) -> Result<(), ApiError> {
     use crate::core::backend::insert_lesson::UpdateLessonTimes;
-    use crate::core::backend::misc::now_localtime;
+    use crate::core::backend::schedule::QueryUserDay;
     use db::prelude::*;
-    let mut db = DB.lock().expect("Failed to lock the database");
-    let now = now_localtime(&db)?;
-    UpdateLessonTimes {
-        phrase_ids: cards.iter().map(|c| c.id).collect(),
-        now,
-    }
-    .apply(&mut db)?;
+    DB.write(move |db| {
+        let day = QueryUserDay {
+            user_id: config().admin.clone(),
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        UpdateLessonTimes {
+            phrase_ids: cards.iter().map(|c| c.id).collect(),
+            day,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Undoes `update_practice_cards` for one card.
+#[server]
+pub async fn revert_practice_card(card: Card) -> Result<(), ApiError> {
+    use crate::core::backend::insert_lesson::RevertLessonTime;
+    use db::prelude::*;
+    DB.write(move |db| {
+        RevertLessonTime { phrase_id: card.id }.apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+#[server]
+pub async fn record_lapse(card: Card) -> Result<bool, ApiError> {
+    use crate::core::backend::phrase::RecordLapse;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::backend::settings::QueryUserSettings;
+    use db::prelude::*;
+    let is_leech = DB
+        .write(move |db| {
+            let settings = QueryUserSettings {
+                user_id: config().admin.clone(),
+            }
+            .apply(db)?;
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let is_leech = RecordLapse {
+                phrase_id: card.id,
+                leech_threshold: settings.leech_threshold,
+                now: day.now,
+            }
+            .apply(db)?;
+            Ok(is_leech)
+        })
+        .await?;
+    Ok(is_leech)
+}
+
+#[server]
+pub async fn revert_lapse(card: Card) -> Result<(), ApiError> {
+    use crate::core::backend::phrase::RevertLapse;
+    use crate::core::backend::settings::QueryUserSettings;
+    use db::prelude::*;
+    DB.write(move |db| {
+        let settings = QueryUserSettings {
+            user_id: config().admin.clone(),
+        }
+        .apply(db)?;
+        RevertLapse {
+            phrase_id: card.id,
+            leech_threshold: settings.leech_threshold,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+#[server]
+pub async fn suspend_phrase(phrase_id: i64, suspended: bool) -> Result<(), ApiError> {
+    use crate::core::backend::phrase::SuspendPhrase;
+    use db::prelude::*;
+    DB.write(move |db| {
+        SuspendPhrase {
+            phrase_id,
+            suspended,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+#[server]
+pub async fn bury_phrase(phrase_id: i64, buried: bool) -> Result<(), ApiError> {
+    use crate::core::backend::phrase::BuryPhrase;
+    use crate::core::backend::schedule::QueryUserDay;
+    use db::prelude::*;
+    DB.write(move |db| {
+        let day = QueryUserDay {
+            user_id: config().admin.clone(),
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        BuryPhrase {
+            phrase_id,
+            day,
+            buried,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
     Ok(())
 }
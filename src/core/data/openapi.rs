@@ -0,0 +1,328 @@
+//! The OpenAPI document for the REST API in `rest`, with schemas built from the types it sends.
+
+use crate::core::data::card::{Card, CardBack, CardFront, Goal};
+use crate::core::data::deck::Answer;
+use crate::core::data::rest::Review;
+use crate::core::data::{Lesson, PhraseView};
+use serde_json::{json, Map, Value};
+
+/// How a type looks in JSON.
+pub trait Schema {
+    /// The type's schema, or for named types a reference to it under `components/schemas`.
+    fn schema() -> Value;
+
+    /// Adds the named types the schema refers to, itself included, to `schemas`.
+    fn register(_schemas: &mut Map<String, Value>) {}
+}
+
+macro_rules! primitive_schemas {
+    ($($ty:ty => $schema:tt),* $(,)?) => {$(
+        impl Schema for $ty {
+            fn schema() -> Value {
+                json!($schema)
+            }
+        }
+    )*};
+}
+
+primitive_schemas! {
+    () => { "type": "null" },
+    bool => { "type": "boolean" },
+    usize => { "type": "integer", "minimum": 0 },
+    i64 => { "type": "integer", "format": "int64" },
+    f64 => { "type": "number" },
+    String => { "type": "string" },
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+
+    fn register(schemas: &mut Map<String, Value>) {
+        T::register(schemas);
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+
+    fn register(schemas: &mut Map<String, Value>) {
+        T::register(schemas);
+    }
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// Describes structs by their fields. Each gets a destructuring `check` that stops compiling
+/// when the struct's fields change, so a schema can't fall behind its type.
+macro_rules! struct_schemas {
+    ($($ty:ident { $($field:ident: $field_ty:ty),* $(,)? })*) => {$(
+        impl Schema for $ty {
+            fn schema() -> Value {
+                reference(stringify!($ty))
+            }
+
+            fn register(schemas: &mut Map<String, Value>) {
+                #[allow(dead_code)]
+                fn check(value: $ty) {
+                    let $ty { $($field),* } = value;
+                    $(let _: $field_ty = $field;)*
+                }
+                if schemas.contains_key(stringify!($ty)) {
+                    return;
+                }
+                let properties = [$((stringify!($field), <$field_ty>::schema())),*]
+                    .into_iter()
+                    .map(|(name, schema)| (name.to_string(), schema))
+                    .collect::<Map<_, _>>();
+                schemas.insert(
+                    stringify!($ty).to_string(),
+                    json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": [$(stringify!($field)),*],
+                    }),
+                );
+                $(<$field_ty>::register(schemas);)*
+            }
+        }
+    )*};
+}
+
+/// Describes enums of unit variants, which serialize as their names, with a `check` like
+/// `struct_schemas` has.
+macro_rules! enum_schemas {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {$(
+        impl Schema for $ty {
+            fn schema() -> Value {
+                reference(stringify!($ty))
+            }
+
+            fn register(schemas: &mut Map<String, Value>) {
+                #[allow(dead_code)]
+                fn check(value: $ty) {
+                    match value {
+                        $($ty::$variant => {})*
+                    }
+                }
+                schemas.insert(
+                    stringify!($ty).to_string(),
+                    json!({ "type": "string", "enum": [$(stringify!($variant)),*] }),
+                );
+            }
+        }
+    )*};
+}
+
+struct_schemas! {
+    Lesson { lesson_id: i64, title: String, creator_id: String }
+    PhraseView {
+        phrase_id: i64,
+        prompt: String,
+        reading: String,
+        meaning: String,
+        lapses: usize,
+        is_leech: bool,
+        suspended: bool,
+        buried: bool,
+        tags: Vec<String>,
+    }
+    Card { id: i64, goal: Goal, front: CardFront, back: CardBack }
+    CardFront { kanji: String }
+    CardBack { yomi: String, meaning: String }
+    Review { phrase_id: i64, answer: Answer }
+}
+
+enum_schemas! {
+    Goal { Learn, Review, Celebrate }
+    Answer { Learn, Fail, Repeat, Pass, Bury, Suspend }
+}
+
+/// Paths and the schemas they use, gathered as operations are added.
+#[derive(Default)]
+struct Document {
+    paths: Map<String, Value>,
+    schemas: Map<String, Value>,
+}
+
+impl Document {
+    fn schema<T: Schema>(&mut self) -> Value {
+        T::register(&mut self.schemas);
+        T::schema()
+    }
+
+    fn operation(
+        &mut self,
+        method: &str,
+        path: &str,
+        summary: &str,
+        parameters: Value,
+        body: Option<Value>,
+        response: Value,
+    ) {
+        let mut operation = json!({
+            "summary": summary,
+            "parameters": parameters,
+            "responses": {
+                "200": {
+                    "description": "Success",
+                    "content": { "application/json": { "schema": response } },
+                },
+                "default": {
+                    "description": "Failure, with its HTTP status repeated in `code`",
+                    "content": { "application/json": { "schema": reference("Error") } },
+                },
+            },
+        });
+        if let Some(body) = body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": body } },
+            });
+        }
+        let item = self.paths.entry(path).or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+}
+
+fn lesson_id() -> Value {
+    json!({
+        "name": "lesson_id",
+        "in": "path",
+        "required": true,
+        "schema": i64::schema(),
+    })
+}
+
+/// Describes the endpoints in `rest`.
+pub fn document() -> Value {
+    let mut document = Document::default();
+    let lessons = document.schema::<Vec<Lesson>>();
+    document.operation(
+        "get",
+        "/api/v1/lessons",
+        "Your lessons",
+        json!([]),
+        None,
+        lessons,
+    );
+    let phrases = document.schema::<Vec<PhraseView>>();
+    document.operation(
+        "get",
+        "/api/v1/lessons/{lesson_id}/phrases",
+        "A lesson's phrases",
+        json!([lesson_id()]),
+        None,
+        phrases,
+    );
+    let cards = document.schema::<Vec<Card>>();
+    document.operation(
+        "get",
+        "/api/v1/lessons/{lesson_id}/cards",
+        "A lesson's cards due today",
+        json!([
+            lesson_id(),
+            { "name": "tag", "in": "query", "required": false, "schema": String::schema() },
+        ]),
+        None,
+        cards,
+    );
+    let reviews = document.schema::<Vec<Review>>();
+    let nothing = document.schema::<()>();
+    document.operation(
+        "post",
+        "/api/v1/reviews",
+        "Record answers to cards. Learn and Repeat record nothing.",
+        json!([]),
+        Some(json!({
+            "type": "object",
+            "properties": { "reviews": reviews },
+            "required": ["reviews"],
+        })),
+        nothing,
+    );
+    let phrase = document.schema::<PhraseView>();
+    document.operation(
+        "post",
+        "/api/v1/quick_add",
+        "Add a word to your inbox lesson, filling in a missing reading and meaning from your \
+         phrases or the server's dictionary. A word already in the inbox is returned unchanged.",
+        json!([]),
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "word": String::schema(),
+                "reading": Option::<String>::schema(),
+                "sentence": Option::<String>::schema(),
+                "source_url": Option::<String>::schema(),
+            },
+            "required": ["word"],
+        })),
+        phrase,
+    );
+    document.schemas.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "message": String::schema(), "code": usize::schema() },
+            "required": ["message", "code"],
+        }),
+    );
+    json!({
+        "openapi": "3.1.0",
+        "info": { "title": "Kanjikoto", "version": "1" },
+        "paths": document.paths,
+        "components": {
+            "schemas": document.schemas,
+            "securitySchemes": {
+                "token": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "An API token from the Settings page",
+                },
+            },
+        },
+        "security": [{ "token": [] }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::document;
+    use serde_json::Value;
+
+    fn references(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    found.push(reference.clone());
+                }
+                map.values().for_each(|value| references(value, found));
+            }
+            Value::Array(values) => values.iter().for_each(|value| references(value, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn references_resolve() {
+        let document = document();
+        let mut found = Vec::new();
+        references(&document, &mut found);
+        assert!(found.contains(&"#/components/schemas/CardFront".to_string()));
+        for reference in found {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            assert!(
+                document["components"]["schemas"].get(name).is_some(),
+                "{reference} is missing"
+            );
+        }
+        let card = &document["components"]["schemas"]["Card"];
+        assert_eq!(card["required"].as_array().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::core::data::deck::Deck;
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PracticeSession {
+    pub session_id: i64,
+    pub deck: Deck,
+}
+
+/// Starts a session for a lesson, or for all lessons when `lesson_id` is `None`.
+#[post("/api/practice_sessions")]
+pub async fn start_practice_session(
+    lesson_id: Option<i64>,
+    cram: bool,
+    deck: Deck,
+) -> Result<i64, ApiError> {
+    use crate::core::backend::practice_session::StartSession;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let deck = serde_json::to_string(&deck)?;
+    let session_id = DB
+        .write(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let session_id = StartSession {
+                user_id: config().admin.clone(),
+                lesson_id,
+                cram,
+                deck,
+                now: day.now,
+            }
+            .apply(db)?;
+            Ok(session_id)
+        })
+        .await?;
+    Ok(session_id)
+}
+
+#[post("/api/practice_sessions/{session_id}")]
+pub async fn save_practice_session(session_id: i64, deck: Deck) -> Result<(), ApiError> {
+    use crate::core::backend::practice_session::SaveSession;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let deck = serde_json::to_string(&deck)?;
+    DB.write(move |db| {
+        let day = QueryUserDay {
+            user_id: config().admin.clone(),
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        SaveSession {
+            user_id: config().admin.clone(),
+            session_id,
+            deck,
+            now: day.now,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Closes a session, recording the stats of its last saved deck.
+#[post("/api/practice_sessions/{session_id}/finish")]
+pub async fn finish_practice_session(session_id: i64) -> Result<(), ApiError> {
+    use crate::core::backend::practice_session::{FinishSession, QuerySessionDeck};
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let deck = DB
+        .read(move |db| {
+            QuerySessionDeck {
+                user_id: config().admin.clone(),
+                session_id,
+            }
+            .apply(db)
+        })
+        .await?;
+    let stats = serde_json::from_str::<Deck>(&deck)?.stats;
+    DB.write(move |db| {
+        let day = QueryUserDay {
+            user_id: config().admin.clone(),
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        FinishSession {
+            user_id: config().admin.clone(),
+            session_id,
+            learned: stats.learned,
+            failed: stats.failed,
+            repeated: stats.repeated,
+            passed: stats.passed,
+            now: day.now,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// The unfinished session for a lesson, if any, for resuming on this or another device.
+#[get("/api/lessons/{lesson_id}/practice_session")]
+pub async fn open_practice_session(lesson_id: i64) -> Result<Option<PracticeSession>, ApiError> {
+    read_open_session(Some(lesson_id)).await
+}
+
+/// The unfinished session that reviews every lesson, if any.
+#[get("/api/review/practice_session")]
+pub async fn open_review_session() -> Result<Option<PracticeSession>, ApiError> {
+    read_open_session(None).await
+}
+
+#[cfg(feature = "server")]
+async fn read_open_session(lesson_id: Option<i64>) -> Result<Option<PracticeSession>, ApiError> {
+    use crate::core::backend::practice_session::QueryOpenSession;
+    use crate::core::data::db::prelude::*;
+    let session = DB
+        .read(move |db| {
+            QueryOpenSession {
+                user_id: config().admin.clone(),
+                lesson_id,
+            }
+            .apply(db)
+        })
+        .await?;
+    let session = match session {
+        Some(session) => Some(PracticeSession {
+            session_id: session.session_id,
+            deck: serde_json::from_str(&session.deck)?,
+        }),
+        None => None,
+    };
+    Ok(session)
+}
@@ -0,0 +1,60 @@
+use crate::core::data::error::ApiError;
+use crate::core::data::settings::DailyGoal;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Where the user stands on their goal, streak and milestones.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub goal: DailyGoal,
+    /// Cards or minutes, as the goal counts them, done today.
+    pub done_today: usize,
+    /// Days the goal was met in the current streak.
+    pub streak: usize,
+    pub best_streak: usize,
+    /// Earned milestones, oldest first, followed by the next ones to earn.
+    pub achievements: Vec<Achievement>,
+}
+
+impl Progress {
+    pub fn goal_met(&self) -> bool {
+        self.done_today >= self.goal.amount
+    }
+
+    pub fn goal_label(&self) -> String {
+        let unit = self.goal.kind.as_str();
+        format!("{} / {} {unit}", self.done_today, self.goal.amount)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    pub title: String,
+    /// The practice day it was earned as YYYY-MM-DD, or `None` while in progress.
+    pub earned_on: Option<String>,
+    pub progress: usize,
+    pub target: usize,
+}
+
+#[get("/api/progress")]
+pub async fn progress() -> Result<Progress, ApiError> {
+    use crate::core::backend::progress::QueryProgress;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let progress = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let progress = QueryProgress {
+                user_id: config().admin.clone(),
+                day,
+            }
+            .apply(db)?;
+            Ok(progress)
+        })
+        .await?;
+    Ok(progress)
+}
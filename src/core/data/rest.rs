@@ -0,0 +1,142 @@
+//! The versioned REST API for scripts. Requests send `Authorization: Bearer <secret>` with a
+//! token made on the Settings page and act as the token's user. `/api/v1/openapi.json`
+//! describes every endpoint.
+
+#[cfg(feature = "server")]
+use crate::core::backend::lesson::check_lesson_owner;
+use crate::core::data::card::Card;
+use crate::core::data::deck::Answer;
+use crate::core::data::error::ApiError;
+#[cfg(feature = "server")]
+use crate::core::data::token::caller;
+use crate::core::data::{Lesson, PhraseView};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An answer to a practice card, given outside the app.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    pub phrase_id: i64,
+    pub answer: Answer,
+}
+
+#[get("/api/v1/openapi.json")]
+pub async fn openapi() -> Result<serde_json::Value, ApiError> {
+    Ok(crate::core::data::openapi::document())
+}
+
+#[get("/api/v1/lessons", headers: dioxus::fullstack::HeaderMap)]
+pub async fn lessons() -> Result<Vec<Lesson>, ApiError> {
+    use crate::core::data::db::prelude::*;
+    let user_id = caller(&headers).await?;
+    let lessons = DB.read(move |db| read_user_lessons(&user_id, db)).await?;
+    Ok(lessons)
+}
+
+#[get("/api/v1/lessons/{lesson_id}/phrases", headers: dioxus::fullstack::HeaderMap)]
+pub async fn lesson_phrases(lesson_id: i64) -> Result<Vec<PhraseView>, ApiError> {
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::phrase_views;
+    let user_id = caller(&headers).await?;
+    let phrases = DB
+        .read(move |db| {
+            check_lesson_owner(db, &user_id, lesson_id)?;
+            phrase_views(&user_id, lesson_id, db)
+        })
+        .await?;
+    Ok(phrases)
+}
+
+/// The lesson's cards due today, or only those tagged `tag`.
+#[get("/api/v1/lessons/{lesson_id}/cards?tag", headers: dioxus::fullstack::HeaderMap)]
+pub async fn lesson_cards(lesson_id: i64, tag: Option<String>) -> Result<Vec<Card>, ApiError> {
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::practice_cards;
+    let user_id = caller(&headers).await?;
+    let cards = DB
+        .read(move |db| {
+            check_lesson_owner(db, &user_id, lesson_id)?;
+            practice_cards(&user_id, lesson_id, tag, db)
+        })
+        .await?;
+    Ok(cards)
+}
+
+/// Records answers in order, as practice would. Nothing is recorded if any phrase isn't the user's.
+#[post("/api/v1/reviews", headers: dioxus::fullstack::HeaderMap)]
+pub async fn submit_reviews(reviews: Vec<Review>) -> Result<(), ApiError> {
+    use crate::core::backend::phrase::{check_phrase_owner, RecordAnswer};
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::backend::settings::QueryUserSettings;
+    use crate::core::data::db::prelude::*;
+    let user_id = caller(&headers).await?;
+    DB.write(move |db| {
+        for review in &reviews {
+            check_phrase_owner(db, &user_id, review.phrase_id)?;
+        }
+        let settings = QueryUserSettings {
+            user_id: user_id.clone(),
+        }
+        .apply(db)?;
+        let day = QueryUserDay {
+            user_id,
+            clock: &CLOCK,
+        }
+        .apply(db)?;
+        for review in reviews {
+            RecordAnswer {
+                phrase_id: review.phrase_id,
+                answer: review.answer,
+                day,
+                leech_threshold: settings.leech_threshold,
+            }
+            .apply(db)?;
+        }
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Adds a word to the user's inbox lesson, as a browser extension would while the user reads.
+/// A missing reading or meaning is filled in from the user's phrases or the configured dictionary.
+#[post("/api/v1/quick_add", headers: dioxus::fullstack::HeaderMap)]
+pub async fn quick_add(
+    word: String,
+    reading: Option<String>,
+    sentence: Option<String>,
+    source_url: Option<String>,
+) -> Result<PhraseView, ApiError> {
+    use crate::core::backend::quick_add::QuickAddPhrase;
+    use crate::core::data::db::dictionary;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::{phrase_views, NewPhrase};
+    let word = word.trim().to_string();
+    if word.is_empty() {
+        return Err(ApiError::Invalid("A word is needed".to_string()));
+    }
+    let user_id = caller(&headers).await?;
+    let phrase = NewPhrase {
+        prompt: word,
+        reading: reading.unwrap_or_default().trim().to_string(),
+        sentence: sentence.unwrap_or_default().trim().to_string(),
+        source_url: source_url.filter(|url| !url.trim().is_empty()),
+        ..Default::default()
+    };
+    let phrase = DB
+        .write(move |db| {
+            let (lesson_id, phrase_id) = QuickAddPhrase {
+                user_id: user_id.clone(),
+                inbox: config().quick_add.inbox.clone(),
+                phrase,
+                dictionary: dictionary()?,
+            }
+            .apply(db)?;
+            phrase_views(&user_id, lesson_id, db)?
+                .into_iter()
+                .find(|phrase| phrase.phrase_id == phrase_id)
+                .ok_or(StorageError::NotFound("Phrase"))
+        })
+        .await?;
+    Ok(phrase)
+}
@@ -0,0 +1,36 @@
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The most phrases a search returns.
+pub const SEARCH_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub phrase_id: i64,
+    pub lesson_id: i64,
+    pub lesson_title: String,
+    pub prompt: String,
+    pub reading: String,
+    pub meaning: String,
+    pub sentence: String,
+}
+
+/// Phrases across the user's lessons matching the query, typed in kanji, kana or romaji.
+#[get("/api/search?query")]
+pub async fn search_phrases(query: String) -> Result<Vec<SearchHit>, ApiError> {
+    use crate::core::backend::search::QuerySearch;
+    use crate::core::data::db::prelude::*;
+    let hits = DB
+        .read(move |db| {
+            let hits = QuerySearch {
+                user_id: config().admin.clone(),
+                query,
+                limit: SEARCH_LIMIT,
+            }
+            .apply(db)?;
+            Ok(hits)
+        })
+        .await?;
+    Ok(hits)
+}
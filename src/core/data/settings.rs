@@ -0,0 +1,382 @@
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UserSettings {
+    /// Lapses after which a phrase is flagged as a leech.
+    pub leech_threshold: usize,
+    pub session_size: usize,
+    pub new_per_day: usize,
+    pub reviews_per_day: usize,
+    /// IANA time zone name, or `None` for the server's time zone.
+    pub time_zone: Option<String>,
+    /// Local hour at which a new day of practice begins.
+    pub day_start_hour: u8,
+    /// Days the clock runs ahead of the real date. Set only by admins, through `simulate_date`.
+    pub clock_offset_days: i64,
+    pub keys: KeyBindings,
+    pub goal: DailyGoal,
+    /// Missed days in a row that don't break a streak.
+    pub streak_freezes: usize,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            leech_threshold: 8,
+            session_size: 20,
+            new_per_day: 20,
+            reviews_per_day: 200,
+            time_zone: None,
+            day_start_hour: 3,
+            clock_offset_days: 0,
+            keys: KeyBindings::default(),
+            goal: DailyGoal::default(),
+            streak_freezes: 1,
+        }
+    }
+}
+
+impl UserSettings {
+    pub fn limits_for(&self, lesson: &LessonSettings) -> PracticeLimits {
+        PracticeLimits {
+            session_size: lesson.session_size.unwrap_or(self.session_size).max(1),
+            new_per_day: lesson.new_per_day.unwrap_or(self.new_per_day),
+            reviews_per_day: lesson.reviews_per_day.unwrap_or(self.reviews_per_day),
+        }
+    }
+}
+
+/// What the daily goal counts.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GoalKind {
+    /// Cards answered.
+    Cards,
+    /// Minutes spent in finished sessions.
+    Minutes,
+}
+
+impl GoalKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalKind::Cards => "cards",
+            GoalKind::Minutes => "minutes",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cards" => Some(GoalKind::Cards),
+            "minutes" => Some(GoalKind::Minutes),
+            _ => None,
+        }
+    }
+}
+
+/// The practice a user means to do each day.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DailyGoal {
+    pub kind: GoalKind,
+    pub amount: usize,
+}
+
+impl Default for DailyGoal {
+    fn default() -> Self {
+        Self {
+            kind: GoalKind::Cards,
+            amount: 20,
+        }
+    }
+}
+
+/// What a key does during practice.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KeyCommand {
+    /// Starts, checks the answer, or moves on, depending on the screen.
+    Flip,
+    Learn,
+    Fail,
+    Repeat,
+    Pass,
+    Bury,
+    Suspend,
+    Undo,
+    Close,
+}
+
+pub const ALL_COMMANDS: [KeyCommand; 9] = [
+    KeyCommand::Flip,
+    KeyCommand::Learn,
+    KeyCommand::Fail,
+    KeyCommand::Repeat,
+    KeyCommand::Pass,
+    KeyCommand::Bury,
+    KeyCommand::Suspend,
+    KeyCommand::Undo,
+    KeyCommand::Close,
+];
+
+impl KeyCommand {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyCommand::Flip => "Flip",
+            KeyCommand::Learn => "Learn",
+            KeyCommand::Fail => "Fail",
+            KeyCommand::Repeat => "Repeat",
+            KeyCommand::Pass => "Pass",
+            KeyCommand::Bury => "Bury",
+            KeyCommand::Suspend => "Suspend",
+            KeyCommand::Undo => "Undo",
+            KeyCommand::Close => "Close",
+        }
+    }
+}
+
+/// Practice keys, named as in `KeyboardEvent.key` except `Space` for the space bar.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub flip: String,
+    pub learn: String,
+    pub fail: String,
+    pub repeat: String,
+    pub pass: String,
+    pub bury: String,
+    pub suspend: String,
+    pub undo: String,
+    pub close: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            flip: "Space".to_string(),
+            learn: "l".to_string(),
+            fail: "1".to_string(),
+            repeat: "2".to_string(),
+            pass: "3".to_string(),
+            bury: "b".to_string(),
+            suspend: "s".to_string(),
+            undo: "u".to_string(),
+            close: "Escape".to_string(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, command: KeyCommand) -> &str {
+        match command {
+            KeyCommand::Flip => &self.flip,
+            KeyCommand::Learn => &self.learn,
+            KeyCommand::Fail => &self.fail,
+            KeyCommand::Repeat => &self.repeat,
+            KeyCommand::Pass => &self.pass,
+            KeyCommand::Bury => &self.bury,
+            KeyCommand::Suspend => &self.suspend,
+            KeyCommand::Undo => &self.undo,
+            KeyCommand::Close => &self.close,
+        }
+    }
+
+    /// A key bound to more than one command.
+    pub fn duplicate(&self) -> Option<&str> {
+        let keys = ALL_COMMANDS.map(|command| self.key(command));
+        keys.iter()
+            .enumerate()
+            .find(|(i, key)| {
+                keys[..*i]
+                    .iter()
+                    .any(|other| other.eq_ignore_ascii_case(key))
+            })
+            .map(|(_, key)| *key)
+    }
+
+    /// The command bound to a key. Letters match in either case.
+    pub fn command(&self, key: &str) -> Option<KeyCommand> {
+        ALL_COMMANDS
+            .into_iter()
+            .find(|&command| self.key(command).eq_ignore_ascii_case(key))
+    }
+}
+
+/// Per-lesson overrides of the user's practice settings.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct LessonSettings {
+    pub lesson_id: i64,
+    pub session_size: Option<usize>,
+    pub new_per_day: Option<usize>,
+    pub reviews_per_day: Option<usize>,
+    /// The lesson's share of sessions that review every lesson, relative to the others.
+    /// `None` counts as 1, and 0 leaves the lesson out.
+    pub review_weight: Option<usize>,
+}
+
+impl LessonSettings {
+    pub fn review_weight(&self) -> usize {
+        self.review_weight.unwrap_or(1)
+    }
+}
+
+/// The practice settings in effect for a lesson.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PracticeLimits {
+    pub session_size: usize,
+    pub new_per_day: usize,
+    pub reviews_per_day: usize,
+}
+
+impl Default for PracticeLimits {
+    fn default() -> Self {
+        UserSettings::default().limits_for(&LessonSettings::default())
+    }
+}
+
+#[get("/api/settings")]
+pub async fn user_settings() -> Result<UserSettings, ApiError> {
+    use crate::core::backend::settings::QueryUserSettings;
+    use crate::core::data::db::prelude::*;
+    let settings = DB
+        .read(move |db| {
+            let settings = QueryUserSettings {
+                user_id: config().admin.clone(),
+            }
+            .apply(db)?;
+            Ok(settings)
+        })
+        .await?;
+    Ok(settings)
+}
+
+#[post("/api/settings")]
+pub async fn save_user_settings(settings: UserSettings) -> Result<(), ApiError> {
+    use crate::core::backend::schedule::is_time_zone;
+    use crate::core::backend::settings::SaveUserSettings;
+    use crate::core::data::db::prelude::*;
+    if let Some(time_zone) = &settings.time_zone {
+        if !is_time_zone(time_zone) {
+            return Err(StorageError::TimeZone(time_zone.clone()).into());
+        }
+    }
+    if let Some(key) = settings.keys.duplicate() {
+        return Err(StorageError::DuplicateKey(key.to_string()).into());
+    }
+    DB.write(move |db| {
+        SaveUserSettings {
+            user_id: config().admin.clone(),
+            settings,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// The date the user's practice runs on, ahead of the real date while one is simulated.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedDate {
+    pub today: String,
+    pub offset_days: i64,
+}
+
+#[get("/api/simulated_date")]
+pub async fn simulated_date() -> Result<SimulatedDate, ApiError> {
+    use crate::core::backend::schedule::QueryUserDate;
+    use crate::core::backend::settings::QueryUserSettings;
+    use crate::core::data::db::prelude::*;
+    let simulated = DB
+        .read(move |db| {
+            let today = QueryUserDate {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let settings = QueryUserSettings {
+                user_id: config().admin.clone(),
+            }
+            .apply(db)?;
+            Ok(SimulatedDate {
+                today: today.to_string(),
+                offset_days: settings.clock_offset_days,
+            })
+        })
+        .await?;
+    Ok(simulated)
+}
+
+/// Makes `date` (YYYY-MM-DD) the admin's today, or restores the real date for `None`. Only a
+/// caller signed in as an admin may.
+#[post("/api/simulated_date", headers: dioxus::fullstack::HeaderMap)]
+pub async fn simulate_date(date: Option<String>) -> Result<(), ApiError> {
+    use crate::core::backend::schedule::SimulateDate;
+    use crate::core::data::db::prelude::*;
+    use crate::core::data::token::caller;
+    let date = match date {
+        Some(date) => Some(
+            date.trim()
+                .parse::<jiff::civil::Date>()
+                .map_err(|_| StorageError::Date(date.clone()))?,
+        ),
+        None => None,
+    };
+    let requested_by = caller(&headers).await?;
+    DB.write(move |db| {
+        SimulateDate {
+            user_id: config().admin.clone(),
+            requested_by,
+            clock: &CLOCK,
+            date,
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// The overrides of the user's practice settings for one of their lessons.
+#[get("/api/lesson_settings/{lesson_id}")]
+pub async fn lesson_settings(lesson_id: i64) -> Result<LessonSettings, ApiError> {
+    use crate::core::backend::lesson::check_lesson_owner;
+    use crate::core::backend::settings::QueryLessonSettings;
+    use crate::core::data::db::prelude::*;
+    let settings = DB
+        .read(move |db| {
+            check_lesson_owner(db, &config().admin, lesson_id)?;
+            let settings = QueryLessonSettings { lesson_id }.apply(db)?;
+            Ok(settings)
+        })
+        .await?;
+    Ok(settings)
+}
+
+#[post("/api/lesson_settings")]
+pub async fn save_lesson_settings(settings: LessonSettings) -> Result<(), ApiError> {
+    use crate::core::backend::lesson::check_lesson_owner;
+    use crate::core::backend::settings::SaveLessonSettings;
+    use crate::core::data::db::prelude::*;
+    DB.write(move |db| {
+        check_lesson_owner(db, &config().admin, settings.lesson_id)?;
+        SaveLessonSettings { settings }.apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyBindings, KeyCommand};
+
+    #[test]
+    fn keys_map_to_commands() {
+        let mut keys = KeyBindings::default();
+        assert_eq!(keys.command("Space"), Some(KeyCommand::Flip));
+        assert_eq!(keys.command("U"), Some(KeyCommand::Undo));
+        assert_eq!(keys.command("Escape"), Some(KeyCommand::Close));
+        assert_eq!(keys.command("x"), None);
+        assert_eq!(keys.duplicate(), None);
+        keys.undo = "L".to_string();
+        assert_eq!(keys.duplicate(), Some("L"));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::core::data::card::Card;
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Which phrases a custom study session drills. Empty filters match everything.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StudyFilter {
+    /// `None` for all of the user's lessons.
+    pub lesson_id: Option<i64>,
+    pub chapter: Option<usize>,
+    pub tag: Option<String>,
+    /// Only phrases failed since the day started.
+    pub failed_today: bool,
+    pub leeches_only: bool,
+    /// Only phrases added or changed in this many days, counting today.
+    pub added_within_days: Option<usize>,
+    /// The most cards to drill.
+    pub limit: usize,
+}
+
+impl Default for StudyFilter {
+    fn default() -> Self {
+        Self {
+            lesson_id: None,
+            chapter: None,
+            tag: None,
+            failed_today: false,
+            leeches_only: false,
+            added_within_days: None,
+            limit: 20,
+        }
+    }
+}
+
+/// Unsuspended phrases matching the filter, due or not, in random order.
+#[post("/api/study/cards")]
+pub async fn study_cards(filter: StudyFilter) -> Result<Vec<Card>, ApiError> {
+    use crate::core::backend::lesson::study::QueryStudyCards;
+    use crate::core::backend::schedule::QueryUserDay;
+    use crate::core::data::db::prelude::*;
+    let cards = DB
+        .read(move |db| {
+            let day = QueryUserDay {
+                user_id: config().admin.clone(),
+                clock: &CLOCK,
+            }
+            .apply(db)?;
+            let cards = QueryStudyCards {
+                user_id: config().admin.clone(),
+                day,
+                filter,
+            }
+            .apply(db)?;
+            Ok(cards)
+        })
+        .await?;
+    Ok(cards)
+}
@@ -0,0 +1,66 @@
+use crate::core::data::error::ApiError;
+use dioxus::prelude::*;
+
+/// Splits text into tag names at commas and whitespace, keeping the first of any repeats.
+pub fn split_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        if !tag.is_empty() && !tags.iter().any(|known| known == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// The tags on at least one of the user's phrases, by name.
+#[get("/api/tags")]
+pub async fn user_tags() -> Result<Vec<String>, ApiError> {
+    use crate::core::backend::tag::QueryTags;
+    use crate::core::data::db::prelude::*;
+    let tags = DB
+        .read(move |db| {
+            let tags = QueryTags {
+                user_id: config().admin.clone(),
+            }
+            .apply(db)?;
+            Ok(tags)
+        })
+        .await?;
+    Ok(tags)
+}
+
+/// Adds the tags in `tags`, separated by commas or spaces, to each phrase.
+#[post("/api/tags/add")]
+pub async fn tag_phrases(phrase_ids: Vec<i64>, tags: String) -> Result<(), ApiError> {
+    use crate::core::backend::tag::TagPhrases;
+    use crate::core::data::db::prelude::*;
+    DB.write(move |db| {
+        TagPhrases {
+            user_id: config().admin.clone(),
+            phrase_ids,
+            tags: split_tags(&tags),
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Removes the tags in `tags`, separated by commas or spaces, from each phrase.
+#[post("/api/tags/remove")]
+pub async fn untag_phrases(phrase_ids: Vec<i64>, tags: String) -> Result<(), ApiError> {
+    use crate::core::backend::tag::UntagPhrases;
+    use crate::core::data::db::prelude::*;
+    DB.write(move |db| {
+        UntagPhrases {
+            user_id: config().admin.clone(),
+            phrase_ids,
+            tags: split_tags(&tags),
+        }
+        .apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
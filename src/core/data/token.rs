@@ -0,0 +1,133 @@
+use crate::core::data::error::ApiError;
+use dioxus::fullstack::{SetCookie, SetHeader};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A token that scripts send as `Authorization: Bearer <secret>` to use the REST API.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub token_id: i64,
+    pub name: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// A token just made, with the secret that is shown this once.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NewApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+/// The caller's tokens.
+#[get("/api/tokens", headers: dioxus::fullstack::HeaderMap)]
+pub async fn api_tokens() -> Result<Vec<ApiToken>, ApiError> {
+    use crate::core::backend::token::QueryTokens;
+    use crate::core::data::db::prelude::*;
+    let user_id = caller(&headers).await?;
+    let tokens = DB
+        .read(move |db| {
+            let tokens = QueryTokens { user_id }.apply(db)?;
+            Ok(tokens)
+        })
+        .await?;
+    Ok(tokens)
+}
+
+/// Makes another token for the caller, who must already hold one. The first comes from
+/// `kanjikoto-admin token create`.
+#[post("/api/tokens", headers: dioxus::fullstack::HeaderMap)]
+pub async fn create_api_token(name: String) -> Result<NewApiToken, ApiError> {
+    use crate::core::backend::token::CreateToken;
+    use crate::core::data::db::prelude::*;
+    let user_id = caller(&headers).await?;
+    let name = match name.trim() {
+        "" => "API token".to_string(),
+        name => name.to_string(),
+    };
+    let token = DB
+        .write(move |db| {
+            let token = CreateToken { user_id, name }.apply(db)?;
+            Ok(token)
+        })
+        .await?;
+    Ok(token)
+}
+
+/// Revokes one of the caller's tokens.
+#[post("/api/tokens/revoke", headers: dioxus::fullstack::HeaderMap)]
+pub async fn revoke_api_token(token_id: i64) -> Result<(), ApiError> {
+    use crate::core::backend::token::RevokeToken;
+    use crate::core::data::db::prelude::*;
+    let user_id = caller(&headers).await?;
+    DB.write(move |db| {
+        RevokeToken { user_id, token_id }.apply(db)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// The user the browser is signed in as, if any.
+#[get("/api/session", headers: dioxus::fullstack::HeaderMap)]
+pub async fn signed_in_user() -> Result<Option<String>, ApiError> {
+    match caller(&headers).await {
+        Ok(user_id) => Ok(Some(user_id)),
+        Err(ApiError::Unauthorized(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Signs the browser in as the token's user, for pages that act on the caller's authority.
+#[post("/api/session")]
+pub async fn sign_in(secret: String) -> Result<SetHeader<SetCookie>, ApiError> {
+    use crate::core::backend::token::Authenticate;
+    use crate::core::data::db::prelude::*;
+    let secret = secret.trim().to_string();
+    let cookie = format!("{SESSION_COOKIE}={secret}; Path=/; HttpOnly; SameSite=Strict");
+    DB.write(move |db| Authenticate { secret }.apply(db))
+        .await?;
+    let cookie = SetHeader::new(cookie).map_err(|_| StorageError::Unauthorized)?;
+    Ok(cookie)
+}
+
+#[post("/api/session/end")]
+pub async fn sign_out() -> Result<SetHeader<SetCookie>, ApiError> {
+    let cookie = format!("{SESSION_COOKIE}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0");
+    SetHeader::new(cookie).map_err(|_| ApiError::Invalid("Failed to sign out".to_string()))
+}
+
+/// The cookie that `sign_in` stores a token in.
+#[cfg(feature = "server")]
+const SESSION_COOKIE: &str = "kanjikoto_token";
+
+/// The user whose token the request carries, as `Authorization: Bearer <secret>` or in the
+/// cookie `sign_in` sets.
+#[cfg(feature = "server")]
+pub(crate) async fn caller(headers: &dioxus::fullstack::HeaderMap) -> Result<String, ApiError> {
+    use crate::core::backend::token::Authenticate;
+    use crate::core::data::db::prelude::*;
+    use dioxus::fullstack::http::header::{AUTHORIZATION, COOKIE};
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let cookie = || {
+        headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .find_map(|pair| pair.trim().strip_prefix(&format!("{SESSION_COOKIE}=")))
+    };
+    let secret = bearer
+        .or_else(cookie)
+        .filter(|secret| !secret.trim().is_empty())
+        .ok_or(StorageError::Unauthorized)?
+        .trim()
+        .to_string();
+    let user_id = DB
+        .write(move |db| Authenticate { secret }.apply(db))
+        .await?;
+    Ok(user_id)
+}
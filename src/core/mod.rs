@@ -1,3 +1,4 @@
+#[cfg(feature = "server")]
 pub mod api;
 #[cfg(feature = "server")]
 pub mod backend;
@@ -0,0 +1,45 @@
+//! The kanjikoto app and the storage it shares with the admin tools.
+
+use dioxus::prelude::*;
+use views::{Backups, Home, Lesson, Navbar, Search, Start, Stats, Study};
+
+mod components;
+pub mod core;
+mod views;
+
+#[derive(Debug, Clone, Routable, PartialEq)]
+#[rustfmt::skip]
+enum Route {
+    #[layout(Navbar)]
+    #[route("/")]
+    Home {},
+    #[route("/lesson?:lesson_id&:phrase_id")]
+    Lesson { lesson_id: Option<i64>, phrase_id: Option<i64> },
+    #[route("/start")]
+    Start {},
+    #[route("/stats")]
+    Stats {},
+    #[route("/study")]
+    Study {},
+    #[route("/search?:query")]
+    Search { query: String },
+    #[route("/backups")]
+    Backups {},
+}
+
+// We can import assets in dioxus with the `asset!` macro. This macro takes a path to an asset relative to the crate root.
+// The macro returns an `Asset` type that will display as the path to the asset in the browser or a local path in desktop bundles.
+const FAVICON: Asset = asset!("/assets/favicon.ico");
+
+#[component]
+pub fn App() -> Element {
+    rsx! {
+        document::Meta { name: "viewport",
+            content: "width=device-width, initial-scale=1, maximum-scale=1, user-scalable=no"
+        }
+        document::Link { rel: "icon", href: FAVICON }
+        document::Link{ rel: "stylesheet", href: "https://cdn.jsdelivr.net/npm/bulma@1.0.4/css/bulma.min.css"}
+        document::Link{ rel: "stylesheet", href: "https://cdnjs.cloudflare.com/ajax/libs/font-awesome/6.7.2/css/all.min.css"}
+        Router::<Route> {}
+    }
+}
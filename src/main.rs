@@ -1,39 +1,15 @@
-use dioxus::prelude::*;
-use views::{Home, Lesson, Navbar, Start};
-
-mod components;
-mod core;
-mod views;
-
-#[derive(Debug, Clone, Routable, PartialEq)]
-#[rustfmt::skip]
-enum Route {
-    #[layout(Navbar)]
-    #[route("/")]
-    Home {},
-    #[route("/lesson")]
-    Lesson {},
-    #[route("/start")]
-    Start {},
-}
-
-// We can import assets in dioxus with the `asset!` macro. This macro takes a path to an asset relative to the crate root.
-// The macro returns an `Asset` type that will display as the path to the asset in the browser or a local path in desktop bundles.
-const FAVICON: Asset = asset!("/assets/favicon.ico");
-
 fn main() {
-    dioxus::launch(App);
-}
-
-#[component]
-fn App() -> Element {
-    rsx! {
-        document::Meta { name: "viewport",
-            content: "width=device-width, initial-scale=1, maximum-scale=1, user-scalable=no"
-        }
-        document::Link { rel: "icon", href: FAVICON }
-        document::Link{ rel: "stylesheet", href: "https://cdn.jsdelivr.net/npm/bulma@1.0.4/css/bulma.min.css"}
-        document::Link{ rel: "stylesheet", href: "https://cdnjs.cloudflare.com/ajax/libs/font-awesome/6.7.2/css/all.min.css"}
-        Router::<Route> {}
+    #[cfg(feature = "server")]
+    {
+        let config = match kanjikoto::core::data::db::init_config() {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Invalid configuration: {err}");
+                std::process::exit(2);
+            }
+        };
+        dioxus::logger::init(config.log_level()).expect("Failed to start logging");
+        kanjikoto::core::data::db::spawn_backups();
     }
+    dioxus::launch(kanjikoto::App);
 }
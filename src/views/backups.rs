@@ -0,0 +1,116 @@
+use crate::core::data::backup::{backups, create_backup, restore_backup};
+use crate::core::data::token::signed_in_user;
+use crate::views::error::ErrorNotice;
+use dioxus::prelude::*;
+
+/// Admin page listing the server's backups, with a backup on demand and restoring one.
+#[component]
+pub fn Backups() -> Element {
+    let user = use_loader(move || async move { signed_in_user().await })?;
+    if user().is_none() {
+        return rsx! {
+            div { class: "box",
+                h2 { class: "title is-5", "Backups" }
+                p { "Sign in with an admin's API token on the Settings page to manage backups." }
+            }
+        };
+    }
+    rsx! {
+        BackupList {}
+    }
+}
+
+#[component]
+fn BackupList() -> Element {
+    let mut list = use_loader(move || async move { backups().await })?;
+    let mut create = use_action(move || async move {
+        create_backup().await?;
+        list.restart();
+        Ok(()) as Result<()>
+    });
+    let mut restore = use_action(move |name: String| async move {
+        let replaced = restore_backup(name.clone()).await?;
+        list.restart();
+        Ok((name, replaced.name)) as Result<(String, String)>
+    });
+    // The backup whose Restore button was pressed once and now asks to be confirmed.
+    let mut confirming = use_signal(|| None::<String>);
+    rsx! {
+        div { class: "box",
+            div { class: "level",
+                div { class: "level-left",
+                    h2 { class: "title is-5", "Backups" }
+                }
+                div { class: "level-right",
+                    button { class: "button is-primary",
+                        class: if create.pending() { "is-loading" },
+                        onclick: move |_| {
+                            create.call();
+                        },
+                        "Back up now"
+                    }
+                }
+            }
+            p { class: "block",
+                "The server backs the database up on a schedule and keeps the newest few. "
+                "Restoring replaces all current data with the backup's, after backing the current data up."
+            }
+            if let Some(Err(error)) = create.value() {
+                ErrorNotice { error }
+            }
+            if let Some(Err(error)) = restore.value() {
+                ErrorNotice { error }
+            }
+            if let Some(Ok(restored)) = restore.value() {
+                div { class: "notification is-success is-light",
+                    "Restored {restored.read().0}. The data it replaced was backed up as {restored.read().1}."
+                }
+            }
+            if list().is_empty() {
+                p { "No backups yet." }
+            } else {
+                table { class: "table is-fullwidth",
+                    thead {
+                        tr {
+                            th { "Backup" }
+                            th { "Taken" }
+                            th { "Size" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for backup in list() {
+                            tr { key: "{backup.name}",
+                                td { "{backup.name}" }
+                                td { "{backup.taken_at}" }
+                                td { {format!("{:.1} MB", backup.bytes as f64 / 1_000_000.0)} }
+                                td {
+                                    if confirming() == Some(backup.name.clone()) {
+                                        div { class: "buttons",
+                                            button { class: "button is-small is-danger",
+                                                onclick: move |_| {
+                                                    confirming.set(None);
+                                                    restore.call(backup.name.clone());
+                                                },
+                                                "Replace all data"
+                                            }
+                                            button { class: "button is-small",
+                                                onclick: move |_| confirming.set(None),
+                                                "Cancel"
+                                            }
+                                        }
+                                    } else {
+                                        button { class: "button is-small is-warning is-light",
+                                            onclick: move |_| confirming.set(Some(backup.name.clone())),
+                                            "Restore"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Showing failed server calls to the user.
+
+use crate::core::data::error::ApiError;
+use dioxus::core::CapturedError;
+use dioxus::prelude::*;
+
+/// A failed call as a Bulma notification, headed by its kind when the server said why it failed.
+#[component]
+pub fn ErrorNotice(error: CapturedError) -> Element {
+    let title = error
+        .downcast_ref::<ApiError>()
+        .map_or("Something went wrong", ApiError::title);
+    rsx! {
+        div { class: "notification is-danger is-light",
+            p { class: "has-text-weight-bold", "{title}" }
+            p { "{error}" }
+        }
+    }
+}
+
+/// Renders a page that failed to load in place of the page.
+pub fn page_error(errors: ErrorContext) -> Element {
+    match errors.error() {
+        Some(error) => rsx! {
+            ErrorNotice { error }
+        },
+        None => rsx! {},
+    }
+}
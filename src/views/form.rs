@@ -0,0 +1,20 @@
+//! Reading submitted form values.
+
+use dioxus::prelude::*;
+use std::collections::HashMap;
+
+/// The trimmed text of a field, or `None` when it is missing or blank.
+pub fn form_text(data: &HashMap<String, FormValue>, name: &str) -> Option<String> {
+    match data.get(name) {
+        Some(FormValue::Text(value)) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// A field parsed as a count, or `None` when it is missing or not a number.
+pub fn form_number(data: &HashMap<String, FormValue>, name: &str) -> Option<usize> {
+    match data.get(name) {
+        Some(FormValue::Text(value)) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
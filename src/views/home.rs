@@ -1,17 +1,133 @@
+use crate::core::data::practice_session::{open_review_session, PracticeSession};
+use crate::core::data::progress::{progress, Achievement};
+use crate::views::practice::dialog::{PracticeDialog, ResumeNotification};
+use crate::views::practice::PracticeSource;
 use crate::Route;
 use dioxus::prelude::*;
 
 #[component]
 pub fn Home() -> Element {
     let nav = use_navigator();
+    let mut progress = use_loader(move || async move { progress().await })?;
+    let onpractice = move |_| progress.restart();
+    let progress = progress();
+    let (earned, upcoming): (Vec<_>, Vec<_>) = progress
+        .achievements
+        .iter()
+        .cloned()
+        .partition(|achievement| achievement.earned_on.is_some());
     rsx! {
         div { class: "container",
             h1 { class: "title", "Home"}
+            ReviewSection { onpractice }
+            div { class: "box",
+                nav { class: "level",
+                    div { class: "level-item has-text-centered",
+                        div {
+                            p { class: "heading", "Today's goal" }
+                            p { class: "title", {progress.goal_label()} }
+                        }
+                    }
+                    div { class: "level-item has-text-centered",
+                        div {
+                            p { class: "heading", "Streak" }
+                            p { class: "title", "{progress.streak} days" }
+                        }
+                    }
+                    div { class: "level-item has-text-centered",
+                        div {
+                            p { class: "heading", "Best streak" }
+                            p { class: "title", "{progress.best_streak} days" }
+                        }
+                    }
+                }
+                progress {
+                    class: if progress.goal_met() { "progress is-success" } else { "progress is-primary" },
+                    max: "{progress.goal.amount}",
+                    value: "{progress.done_today.min(progress.goal.amount)}",
+                }
+            }
+            div { class: "box",
+                h2 { class: "title is-5", "Achievements" }
+                if earned.is_empty() {
+                    p { class: "block", "None yet. Keep practicing!" }
+                } else {
+                    div { class: "tags block",
+                        for achievement in earned {
+                            span { class: "tag is-warning is-medium",
+                                title: achievement.earned_on.clone().unwrap_or_default(),
+                                span { class: "icon", i { class: "fas fa-trophy" } }
+                                span { {achievement.title} }
+                            }
+                        }
+                    }
+                }
+                for achievement in upcoming {
+                    UpcomingAchievement { achievement }
+                }
+            }
             button {
                 class: "button is-primary",
-                onclick: move |_| { nav.push(Route::Lesson {}); },
+                onclick: move |_| { nav.push(Route::Lesson { lesson_id: None, phrase_id: None }); },
                 "Lesson"
             }
         }
     }
 }
+
+#[component]
+fn UpcomingAchievement(achievement: Achievement) -> Element {
+    rsx! {
+        div { class: "block",
+            p { "{achievement.title}: {achievement.progress} / {achievement.target}" }
+            progress { class: "progress is-small is-warning",
+                max: "{achievement.target}",
+                value: "{achievement.progress}",
+            }
+        }
+    }
+}
+
+/// Starts or resumes a session that reviews every lesson.
+#[component]
+fn ReviewSection(onpractice: EventHandler<()>) -> Element {
+    let mut practicing = use_signal(|| false);
+    let mut resuming = use_signal(|| None::<PracticeSession>);
+    let mut open_session = use_loader(move || async move { open_review_session().await })?;
+    let close = use_callback(move |_: ()| {
+        *practicing.write() = false;
+        open_session.restart();
+        onpractice.call(());
+    });
+    rsx! {
+        if let Some(session) = open_session() {
+            ResumeNotification {
+                turns: session.deck.turns_remaining(),
+                onresume: move |_| {
+                    *resuming.write() = open_session();
+                    *practicing.write() = true;
+                },
+            }
+        }
+        div { class: "block",
+            button { class: "button is-primary is-medium",
+                onclick: move |_| {
+                    *resuming.write() = None;
+                    *practicing.write() = true;
+                },
+                span { class: "icon", i { class: "fas fa-layer-group" } }
+                span { "Review everything" }
+            }
+        }
+        if practicing() {
+            PracticeDialog {
+                source: PracticeSource::Review,
+                title: "Review",
+                subtitle: "Due phrases from all lessons",
+                resume: resuming(),
+                onsave: close,
+                onclose: close,
+            }
+        }
+    }
+}
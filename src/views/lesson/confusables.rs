@@ -0,0 +1,193 @@
+use crate::core::data::confusion::{
+    query_confusables, query_confusions, record_confusion, ConfusableSet, ConfusionKind,
+};
+use dioxus::prelude::*;
+
+#[component]
+pub fn ConfusablesSection() -> Element {
+    let sets = use_loader(move || async move { query_confusables().await })?;
+    let mut confusions = use_loader(move || async move { query_confusions().await })?;
+    let mut drilling = use_signal(|| None::<ConfusableSet>);
+
+    let mut record = use_action(move |(expected_id, chosen_id): (i64, i64)| async move {
+        record_confusion(expected_id, chosen_id).await
+    });
+
+    rsx! {
+        div { class: "block",
+            h2 { class: "title is-5", "Mixed up" }
+            if confusions().is_empty() {
+                p { "Nothing mixed up yet" }
+            } else {
+                table { class: "table is-striped is-fullwidth",
+                    thead {
+                        tr {
+                            th { "Expected" }
+                            th { "Chosen" }
+                            th { "Times" }
+                        }
+                    }
+                    tbody {
+                        for confusion in confusions() {
+                            tr {
+                                td { "{confusion.expected.prompt} ({confusion.expected.reading})" }
+                                td { "{confusion.chosen.prompt} ({confusion.chosen.reading})" }
+                                td { "{confusion.count}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        div { class: "block",
+            h2 { class: "title is-5", "Confusable sets" }
+            if sets().is_empty() {
+                p { "No confusable phrases found" }
+            }
+            for set in sets() {
+                ConfusableSetItem { set: set.clone(), ondrill: move |_| *drilling.write() = Some(set.clone()) }
+            }
+        }
+        if let Some(set) = drilling() {
+            div { class: "modal is-active",
+                div { class: "modal-background" }
+                div { class: "modal-card",
+                    header { class: "modal-card-head",
+                        div { class: "modal-card-title",
+                            p { class: "title", "Confusion Drill"}
+                            p { class: "subtitle", "{set.key}" }
+                        }
+                        button { class: "delete", aria_label: "close",
+                            onclick: move |_| {
+                                *drilling.write() = None;
+                                confusions.restart();
+                            }
+                        }
+                    }
+                    footer { class: "modal-card-foot",
+                        div { class: "container",
+                            ConfusionDrill {
+                                set,
+                                onconfuse: move |ids| record.call(ids),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfusableSetItem(set: ConfusableSet, ondrill: EventHandler<MouseEvent>) -> Element {
+    let kind = match set.kind {
+        ConfusionKind::Homophone => "Same reading",
+        ConfusionKind::LookAlike => "Look-alike kanji",
+    };
+    rsx! {
+        div { class: "box",
+            nav { class: "level is-mobile",
+                div { class: "level-left",
+                    div { class: "level-item",
+                        span { class: "tag is-info is-medium", "{set.key}" }
+                    }
+                    div { class: "level-item",
+                        span { class: "heading", {kind} }
+                    }
+                }
+                div { class: "level-right",
+                    div { class: "level-item",
+                        button { class: "button is-primary is-small", onclick: ondrill, "Drill" }
+                    }
+                }
+            }
+            div { class: "tags are-medium",
+                for phrase in set.phrases.iter() {
+                    span { class: "tag is-light", "{phrase.prompt}（{phrase.reading}）" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfusionDrill(set: ConfusableSet, onconfuse: EventHandler<(i64, i64)>) -> Element {
+    let count = set.phrases.len();
+    let mut target = use_signal(|| rand::random_range(0..count));
+    let mut chosen = use_signal(|| None::<usize>);
+    let mut score = use_signal(|| (0usize, 0usize));
+
+    let expected = set.phrases[target()].clone();
+    let meanings = expected
+        .meaning
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let (correct, total) = score();
+    rsx! {
+        div { class: "card",
+            div { class: "card-content",
+                nav { class: "level is-mobile",
+                    div { class: "level-left",
+                        div { class: "level-item",
+                            span { class: "heading", "Choose the phrase"}
+                        }
+                    }
+                    div { class: "level-right",
+                        div { class: "level-item",
+                            span { class: "tag is-light", "{correct} / {total}"}
+                        }
+                    }
+                }
+                section { class: "section has-text-centered",
+                    h1 { class: "title", "{expected.reading}" }
+                    div { class: "tags are-medium is-centered",
+                        for meaning in meanings {
+                            span { class: "tag is-warning is-light", "{meaning}"}
+                        }
+                    }
+                }
+                div { class: "buttons is-centered",
+                    for (index, phrase) in set.phrases.iter().enumerate() {
+                        button {
+                            class: match chosen() {
+                                Some(_) if index == target() => "button is-large is-success",
+                                Some(choice) if index == choice => "button is-large is-danger",
+                                _ => "button is-large",
+                            },
+                            disabled: chosen().is_some(),
+                            onclick: {
+                                let chosen_id = phrase.phrase_id;
+                                let expected_id = expected.phrase_id;
+                                move |_| {
+                                    *chosen.write() = Some(index);
+                                    let (correct, total) = score();
+                                    if index == target() {
+                                        *score.write() = (correct + 1, total + 1);
+                                    } else {
+                                        *score.write() = (correct, total + 1);
+                                        onconfuse.call((expected_id, chosen_id));
+                                    }
+                                }
+                            },
+                            "{phrase.prompt}"
+                        }
+                    }
+                }
+            }
+            if chosen().is_some() {
+                footer { class: "card-footer",
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| {
+                            *target.write() = rand::random_range(0..count);
+                            *chosen.write() = None;
+                        },
+                        "Next"
+                    }
+                }
+            }
+        }
+    }
+}
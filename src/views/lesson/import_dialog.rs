@@ -1,4 +1,6 @@
+use crate::core::data::import::{preview_import, DuplicatePolicy, ImportPreview};
 use crate::core::data::ImportDetails;
+use crate::views::error::ErrorNotice;
 use dioxus::prelude::*;
 use std::collections::HashMap;
 
@@ -8,14 +10,25 @@ pub fn ImportDialog(
     onimport: EventHandler<ImportDetails>,
 ) -> Element {
     const SAMPLE_CSV_URL: &str = "https://docs.google.com/spreadsheets/d/e/2PACX-1vQjXD1Z1nrpTS60VhvlyI3Gha7bS-XP1r_nv3ITYbw4JBL-FA8SB6irRsVHhlEje5ZZT_H8uwFuRGgw/pub?gid=0&single=true&output=csv";
+    let mut csv_url = use_signal(|| SAMPLE_CSV_URL.to_string());
+    let mut preview = use_action(move |csv_url: String| async move {
+        preview_import(ImportDetails {
+            csv_url,
+            duplicates: DuplicatePolicy::default(),
+        })
+        .await
+    });
     rsx! {
         div { id: "import-dialog", class: "modal is-active",
             div { class: "modal-background" }
             form { class: "modal-card",
                 onsubmit: move |evt| async move {
                     let data = evt.values().into_iter().collect::<HashMap<_, _>>();
-                    let FormValue::Text(csv_url) = data.get("csv_url").unwrap() else { unreachable!() };
-                    let details = ImportDetails { csv_url: csv_url.to_string() };
+                    let duplicates = match data.get("duplicates") {
+                        Some(FormValue::Text(value)) => DuplicatePolicy::parse(value).unwrap_or_default(),
+                        _ => DuplicatePolicy::default(),
+                    };
+                    let details = ImportDetails { csv_url: csv_url(), duplicates };
                     onimport.call(details);
                     evt.prevent_default();
                     *importing.write() = false;
@@ -27,10 +40,47 @@ pub fn ImportDialog(
                 section { class: "modal-card-body",
                     div { class: "field",
                         label { class: "label", "URL" }
-                        div { class: "control is-expanded",
-                            input {class: "input",type: "url",name: "csv_url",value: SAMPLE_CSV_URL}
+                        div { class: "field has-addons",
+                            div { class: "control is-expanded",
+                                input {class: "input",type: "url",name: "csv_url",
+                                    value: "{csv_url}",
+                                    oninput: move |evt| csv_url.set(evt.value()),
+                                }
+                            }
+                            div { class: "control",
+                                button { class: "button is-info",
+                                    r#type: "button",
+                                    class: if preview.pending() { "is-loading" },
+                                    onclick: move |_| preview.call(csv_url()),
+                                    "Preview"
+                                }
+                            }
                         }
                     }
+                    div { class: "field",
+                        label { class: "label", "Duplicates" }
+                        div { class: "control",
+                            for policy in DuplicatePolicy::ALL {
+                                label { class: "radio mr-4",
+                                    input {
+                                        r#type: "radio",
+                                        name: "duplicates",
+                                        value: policy.as_str(),
+                                        checked: policy == DuplicatePolicy::default(),
+                                    }
+                                    " {policy.label()}"
+                                }
+                            }
+                        }
+                        p { class: "help", "A duplicate has the same prompt and reading as a phrase in one of your lessons or an earlier row." }
+                    }
+                    match preview.value() {
+                        Some(Ok(result)) => rsx! { PreviewTable { preview: result() } },
+                        Some(Err(error)) => rsx! {
+                            ErrorNotice { error }
+                        },
+                        None => rsx! {},
+                    }
                 }
                 footer { class: "modal-card-foot",
                     div { class: "buttons",
@@ -46,3 +96,35 @@ pub fn ImportDialog(
         }
     }
 }
+
+#[component]
+fn PreviewTable(preview: ImportPreview) -> Element {
+    let ImportPreview { rows, duplicates } = preview;
+    rsx! {
+        p { class: "block",
+            "{rows} rows, {duplicates.len()} duplicates."
+        }
+        if !duplicates.is_empty() {
+            table { class: "table is-narrow is-fullwidth",
+                thead {
+                    tr {
+                        th { "Prompt" }
+                        th { "Reading" }
+                        th { "Chapter" }
+                        th { "Already in" }
+                    }
+                }
+                tbody {
+                    for duplicate in duplicates {
+                        tr {
+                            td { "{duplicate.prompt}" }
+                            td { "{duplicate.reading}" }
+                            td { {duplicate.chapter.map(|chapter| chapter.to_string())} }
+                            td { {duplicate.lesson_title.unwrap_or_else(|| "An earlier row".to_string())} }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
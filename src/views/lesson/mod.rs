@@ -1,4 +1,6 @@
 use crate::core::data::{import_csv, lesson_view};
+use crate::views::error::ErrorNotice;
+use crate::views::lesson::confusables::ConfusablesSection;
 use crate::views::lesson::import_dialog::ImportDialog;
 use crate::views::lesson::phrases::PhraseTable;
 use crate::views::lesson::tabs::LessonTabs;
@@ -6,21 +8,35 @@ use crate::views::lesson::today::TodaySection;
 use dioxus::prelude::*;
 use tabs::LessonTab;
 
+pub mod confusables;
 pub mod import_dialog;
 pub mod phrases;
 pub mod tabs;
 pub mod today;
+/// Shows the user's lesson with `lesson_id`, or their first, opening the Phrases tab at `phrase_id`.
 #[component]
-pub fn Lesson() -> Element {
-    let current_tab = use_signal(|| LessonTab::Today);
-    let mut current_lesson = use_loader(move || async move { lesson_view().await })?;
+pub fn Lesson(lesson_id: Option<i64>, phrase_id: Option<i64>) -> Element {
+    let mut current_tab = use_signal(|| LessonTab::Today);
+    use_effect(use_reactive!(|phrase_id| {
+        if phrase_id.is_some() {
+            current_tab.set(LessonTab::Phrases);
+        }
+    }));
+    let mut current_lesson = use_loader(use_reactive!(|lesson_id| async move {
+        lesson_view(lesson_id).await
+    }))?;
     let mut show_import_dialog = use_signal(|| false);
     let mut import_csv = use_action(move |details| async move {
-        import_csv(details).await.expect("Failed to import CSV");
+        import_csv(details).await?;
         current_lesson.restart();
         Ok(()) as Result<()>
     });
 
+    let import_error = match import_csv.value() {
+        Some(Err(error)) => Some(error),
+        _ => None,
+    };
+
     match current_lesson() {
         None => rsx! {
             div { class: "block",
@@ -32,7 +48,10 @@ pub fn Lesson() -> Element {
                     }
                 }
             }
-            if show_import_dialog.read().clone() {
+            if let Some(error) = import_error {
+                ErrorNotice { error }
+            }
+            if *show_import_dialog.read() {
                 ImportDialog{
                     importing: show_import_dialog,
                     onimport: move |details| async move {
@@ -54,9 +73,12 @@ pub fn Lesson() -> Element {
                     if lesson.phrases.is_empty() {
                         "No phrases yet"
                     } else {
-                        PhraseTable{ phrases: lesson.phrases }
+                        PhraseTable{ phrases: lesson.phrases, highlight: phrase_id, onchange: move |_| current_lesson.restart() }
                     }
                 },
+                LessonTab::Confusables => rsx! {
+                    ConfusablesSection {}
+                },
             }
         },
     }
@@ -1,14 +1,34 @@
+use crate::core::data::tag::{split_tags, tag_phrases, untag_phrases};
+use crate::core::data::{bury_phrase, suspend_phrase, PhraseView};
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
-use crate::core::data::PhraseView;
+use std::collections::HashSet;
+
+/// Whether the phrase's text or one of its tags contains the search text.
+fn matches_search(phrase: &PhraseView, search: &str) -> bool {
+    let search = search.trim().to_lowercase();
+    search.is_empty()
+        || [&phrase.prompt, &phrase.reading, &phrase.meaning]
+            .into_iter()
+            .chain(phrase.tags.iter())
+            .any(|text| text.to_lowercase().contains(&search))
+}
 
 #[component]
-pub fn PhraseTable(phrases: ReadSignal<Vec<PhraseView>>) -> Element {
+pub fn PhraseTable(
+    phrases: ReadSignal<Vec<PhraseView>>,
+    /// A phrase to pick out, such as a search result.
+    highlight: Option<i64>,
+    onchange: EventHandler<()>,
+) -> Element {
     #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
     struct PhraseRow {
         number: String,
         phrase: PhraseView,
     }
+    let mut search = use_signal(String::new);
+    let mut selected = use_signal(HashSet::<i64>::new);
+    let mut tag_text = use_signal(String::new);
     let rows = phrases
         .iter()
         .enumerate()
@@ -16,27 +36,163 @@ pub fn PhraseTable(phrases: ReadSignal<Vec<PhraseView>>) -> Element {
             number: (index + 1).to_string(),
             phrase: phrase.cloned(),
         })
+        .filter(|row| matches_search(&row.phrase, &search.read()))
+        .collect::<Vec<_>>();
+    let shown = rows
+        .iter()
+        .map(|row| row.phrase.phrase_id)
         .collect::<Vec<_>>();
+    let all_selected = !shown.is_empty() && shown.iter().all(|id| selected.read().contains(id));
+    let mut suspend = use_action(move |(phrase_id, suspended): (i64, bool)| async move {
+        suspend_phrase(phrase_id, suspended).await?;
+        onchange.call(());
+        Ok(()) as Result<()>
+    });
+    let mut bury = use_action(move |(phrase_id, buried): (i64, bool)| async move {
+        bury_phrase(phrase_id, buried).await?;
+        onchange.call(());
+        Ok(()) as Result<()>
+    });
+    let mut add_tags = use_action(move |(phrase_ids, tags): (Vec<i64>, String)| async move {
+        tag_phrases(phrase_ids, tags).await?;
+        tag_text.set(String::new());
+        onchange.call(());
+        Ok(()) as Result<()>
+    });
+    let mut remove_tags = use_action(move |(phrase_ids, tags): (Vec<i64>, String)| async move {
+        untag_phrases(phrase_ids, tags).await?;
+        tag_text.set(String::new());
+        onchange.call(());
+        Ok(()) as Result<()>
+    });
+    let selection = move || selected.read().iter().copied().collect::<Vec<_>>();
     rsx! {
+        div { class: "level",
+            div { class: "level-left",
+                div { class: "level-item",
+                    input { class: "input",
+                        r#type: "search",
+                        placeholder: "Search phrases or tags",
+                        value: "{search}",
+                        oninput: move |evt| search.set(evt.value()),
+                    }
+                }
+            }
+            div { class: "level-right",
+                div { class: "level-item",
+                    "{selected.read().len()} selected"
+                }
+                div { class: "level-item",
+                    div { class: "field has-addons",
+                        div { class: "control",
+                            input { class: "input",
+                                placeholder: "Tags",
+                                value: "{tag_text}",
+                                oninput: move |evt| tag_text.set(evt.value()),
+                            }
+                        }
+                        div { class: "control",
+                            button { class: "button is-primary",
+                                disabled: selected.read().is_empty() || split_tags(&tag_text.read()).is_empty(),
+                                onclick: move |_| add_tags.call((selection(), tag_text())),
+                                "Add tags"
+                            }
+                        }
+                        div { class: "control",
+                            button { class: "button",
+                                disabled: selected.read().is_empty() || split_tags(&tag_text.read()).is_empty(),
+                                onclick: move |_| remove_tags.call((selection(), tag_text())),
+                                "Remove tags"
+                            }
+                        }
+                    }
+                }
+            }
+        }
         table { class: "table is-striped is-hoverable is-fullwidth",
             thead {
                 tr {
+                    th {
+                        input {
+                            r#type: "checkbox",
+                            checked: all_selected,
+                            onchange: move |_| {
+                                let mut selected = selected.write();
+                                if all_selected {
+                                    shown.iter().for_each(|id| { selected.remove(id); });
+                                } else {
+                                    selected.extend(shown.iter().copied());
+                                }
+                            },
+                        }
+                    }
                     th { "#"}
                     th { "Prompt" }
                     th { "Reading" }
                     th { "Meaning" }
+                    th { "Tags" }
+                    th { "Lapses" }
+                    th { "" }
                 }
             }
             tbody {
                 for row in rows.iter() {
-                    tr {
+                    tr { class: if highlight == Some(row.phrase.phrase_id) { "is-selected" },
+                        td {
+                            input {
+                                r#type: "checkbox",
+                                checked: selected.read().contains(&row.phrase.phrase_id),
+                                onchange: {
+                                    let phrase_id = row.phrase.phrase_id;
+                                    move |_| {
+                                        let mut selected = selected.write();
+                                        if !selected.remove(&phrase_id) {
+                                            selected.insert(phrase_id);
+                                        }
+                                    }
+                                },
+                            }
+                        }
                         td { { row.number.clone() } }
                         td { { row.phrase.prompt.clone()} }
                         td { { row.phrase.reading.clone()} }
                         td { { row.phrase.meaning.clone()} }
+                        td {
+                            div { class: "tags",
+                                for tag in row.phrase.tags.iter() {
+                                    span { key: "{tag}", class: "tag is-link is-light", "{tag}" }
+                                }
+                            }
+                        }
+                        td {
+                            "{row.phrase.lapses}"
+                            if row.phrase.is_leech {
+                                span { class: "tag is-danger is-light ml-2", "Leech" }
+                            }
+                        }
+                        td {
+                            div { class: "buttons are-small",
+                                button {
+                                    class: if row.phrase.buried { "button is-info" } else { "button is-light" },
+                                    onclick: {
+                                        let (phrase_id, buried) = (row.phrase.phrase_id, row.phrase.buried);
+                                        move |_| bury.call((phrase_id, !buried))
+                                    },
+                                    if row.phrase.buried { "Unbury" } else { "Bury" }
+                                }
+                                button {
+                                    class: if row.phrase.suspended { "button is-warning" } else { "button is-light" },
+                                    onclick: {
+                                        let (phrase_id, suspended) = (row.phrase.phrase_id, row.phrase.suspended);
+                                        move |_| suspend.call((phrase_id, !suspended))
+                                    },
+                                    if row.phrase.suspended { "Unsuspend" } else { "Suspend" }
+                                }
+                            }
+                        }
                     }
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
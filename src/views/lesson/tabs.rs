@@ -5,16 +5,18 @@ use serde::{Deserialize, Serialize};
 pub enum LessonTab {
     Today,
     Phrases,
+    Confusables,
 }
 
 #[component]
 pub fn LessonTabs(current_tab: Signal<LessonTab>) -> Element {
     #[component]
     fn TabItem(tab: LessonTab, current_tab: Signal<LessonTab>) -> Element {
-        let is_active = tab == current_tab.read().clone();
+        let is_active = tab == *current_tab.read();
         let title = match tab {
             LessonTab::Today => "Today",
             LessonTab::Phrases => "Phrases",
+            LessonTab::Confusables => "Confusables",
         };
         rsx! {
             li { class: if is_active {"is-active"},
@@ -28,9 +30,10 @@ pub fn LessonTabs(current_tab: Signal<LessonTab>) -> Element {
     rsx! {
         div { class: "tabs is-centered is-medium",
             ul {
-                TabItem { tab: LessonTab::Today, current_tab: current_tab.clone() }
-                TabItem { tab: LessonTab::Phrases, current_tab: current_tab.clone() }
+                TabItem { tab: LessonTab::Today, current_tab }
+                TabItem { tab: LessonTab::Phrases, current_tab }
+                TabItem { tab: LessonTab::Confusables, current_tab }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -1,51 +1,98 @@
-use crate::core::data::card::Card;
+use crate::core::data::forecast::lesson_forecast;
 use crate::core::data::lesson_status::LessonStatus;
-use crate::core::data::{query_lesson_status, update_practice_cards};
-use crate::views::practice::PracticeSessionSection;
+use crate::core::data::practice_session::{open_practice_session, PracticeSession};
+use crate::core::data::query_lesson_status;
+use crate::core::data::tag::user_tags;
+use crate::views::practice::dialog::{PracticeDialog, ResumeNotification};
+use crate::views::practice::PracticeSource;
+use crate::views::stats::{BarChart, ChartBox};
 use dioxus::core::Element;
 use dioxus::core_macro::component;
 use dioxus::fullstack::{use_loader, Loader};
 use dioxus::prelude::*;
 
+/// Days shown in the forecast.
+const FORECAST_DAYS: usize = 14;
+
 #[component]
 pub fn TodaySection(lesson_id: i64) -> Element {
     let mut practicing = use_signal(|| false);
+    let mut resuming = use_signal(|| None::<PracticeSession>);
+    let mut tag = use_signal(|| None::<String>);
+    let tags = use_loader(move || async move { user_tags().await })?;
     let mut lesson_status =
-        use_loader(move || async move { query_lesson_status(lesson_id).await })?;
+        use_loader(move || async move { query_lesson_status(lesson_id, tag()).await })?;
+    let mut open_session =
+        use_loader(move || async move { open_practice_session(lesson_id).await })?;
+    let mut forecast =
+        use_loader(move || async move { lesson_forecast(lesson_id, FORECAST_DAYS).await })?;
 
     let mut record_practice = use_action(move |_| async move {
         *practicing.write() = false;
         lesson_status.restart();
+        open_session.restart();
+        forecast.restart();
         Ok(()) as Result<()>
     });
 
-    let mut record_pass =
-        use_action(move |card: Card| async move { update_practice_cards(vec![card]).await });
+    let close = use_callback(move |_: ()| {
+        *practicing.write() = false;
+        open_session.restart();
+    });
 
     rsx! {
-        TodayLessonStatus{ lesson_status, practicing: practicing.clone() }
-        if practicing() {
-            div { class: "modal is-active",
-                div { class: "modal-background" }
-                div { class: "modal-card",
-                    header { class: "modal-card-head",
-                        div { class: "modal-card-title",
-                            p { class: "title", "Reading Practice"}
-                            p { class: "subtitle", "Read today's cards" }
-                        }
-                        button { class: "delete", aria_label: "close", onclick: move |_| *practicing.write() = false }
-                    }
-                    footer { class: "modal-card-foot",
-                        div { class: "container",
-                            PracticeSessionSection {
-                                onsave: move |cards| record_practice.call(cards),
-                                onpass: move |card| record_pass.call(card),
+        if let Some(session) = open_session() {
+            ResumeNotification {
+                turns: session.deck.turns_remaining(),
+                onresume: move |_| {
+                    *resuming.write() = open_session();
+                    *practicing.write() = true;
+                },
+            }
+        }
+        if !tags().is_empty() {
+            div { class: "field m-3",
+                label { class: "label", "Practice tag" }
+                div { class: "control",
+                    div { class: "select",
+                        select {
+                            onchange: move |evt| tag.set(Some(evt.value()).filter(|name| !name.is_empty())),
+                            option { value: "", "All phrases" }
+                            for name in tags() {
+                                option { key: "{name}", value: "{name}", selected: tag().as_ref() == Some(&name),
+                                    "{name}"
+                                }
                             }
                         }
                     }
                 }
             }
         }
+        TodayLessonStatus{ lesson_status, practicing, resuming }
+        div { class: "m-3",
+            ChartBox { title: "Due in the next {FORECAST_DAYS} days",
+                BarChart {
+                    bars: forecast().iter().map(|day| (day.date.clone(), day.total() as f64)).collect::<Vec<_>>(),
+                    color: "has-background-primary",
+                }
+                p { class: "help mt-2",
+                    "Assumes each day's practice gets done. "
+                    a { href: "/api/forecast.ics?days=60", download: "kanjikoto.ics",
+                        "Add all lessons to your calendar"
+                    }
+                }
+            }
+        }
+        if practicing() {
+            PracticeDialog {
+                source: PracticeSource::Lesson { lesson_id, tag: tag() },
+                title: "Reading Practice",
+                subtitle: "Read today's cards",
+                resume: resuming(),
+                onsave: move |_| record_practice.call(()),
+                onclose: close,
+            }
+        }
     }
 }
 
@@ -53,6 +100,7 @@ pub fn TodaySection(lesson_id: i64) -> Element {
 fn TodayLessonStatus(
     lesson_status: Loader<LessonStatus>,
     practicing: WriteSignal<bool>,
+    resuming: WriteSignal<Option<PracticeSession>>,
 ) -> Element {
     let (ready, learned) = lesson_status().to_ready_learned();
     rsx! {
@@ -61,7 +109,12 @@ fn TodayLessonStatus(
                 StatusCard { title: "Ready", style: "is-primary",
                     div { class: "buttons",
                         for _ in 0..ready {
-                            ReadyButton{ onclick: move |_| *practicing.write() = true}
+                            ReadyButton{
+                                onclick: move |_| {
+                                    *resuming.write() = None;
+                                    *practicing.write() = true;
+                                }
+                            }
                         }
                     }
                 }
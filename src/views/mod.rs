@@ -10,6 +10,22 @@ pub use start::Start;
 mod home;
 pub use home::Home;
 
+mod stats;
+pub use stats::Stats;
+
+mod study;
+pub use study::Study;
+
+mod search;
+pub use search::Search;
+
+mod backups;
+pub use backups::Backups;
+
+mod error;
+
+mod form;
+
 pub mod practice;
 
 mod lesson;
@@ -1,3 +1,5 @@
+use crate::core::data::progress::progress;
+use crate::views::error::page_error;
 use crate::Route;
 use dioxus::prelude::*;
 
@@ -5,21 +7,74 @@ const LOGO_IMG: Asset = asset!("/assets/PlaygroundImage4.jpg");
 
 #[component]
 pub fn Navbar() -> Element {
+    let route = use_route::<Route>();
     rsx! {
         nav { class: "navbar", role: "navigation", aria_label: "main navigation",
             div { class: "navbar-brand",
                 Link{ class: "navbar-item", to: Route::Home{}, img { src: LOGO_IMG, }}
-                Link{ class: "navbar-item", to: Route::Lesson{}, "Lesson"}
+                Link{ class: "navbar-item", to: Route::Lesson{ lesson_id: None, phrase_id: None }, "Lesson"}
+                Link{ class: "navbar-item", to: Route::Study{}, "Study"}
+                Link{ class: "navbar-item", to: Route::Stats{}, "Stats"}
             }
             div { class: "navbar-menu",
-                div { class: "navbar-start" }
+                div { class: "navbar-start",
+                    SearchBox {}
+                }
                 div { class: "navbar-end",
+                    // Keyed by route so the badge refreshes after each page change.
+                    StreakBadge { key: "{route:?}" }
+                    Link{ class: "navbar-item", to: Route::Backups {}, "Backups"}
                     Link{ class: "navbar-item", to: Route::Start {}, "Settings"}
                 }
             }
         }
         section { class: "section is-fullheight-with-navbar",
-            Outlet::<Route> {}
+            // Keyed by route so leaving a page that failed to load clears its error.
+            ErrorBoundary { key: "{route:?}", handle_error: page_error,
+                Outlet::<Route> {}
+            }
+        }
+    }
+}
+
+/// Searches every lesson, opening the results on the Search page.
+#[component]
+fn SearchBox() -> Element {
+    let mut query = use_signal(String::new);
+    let nav = navigator();
+    rsx! {
+        form { class: "navbar-item",
+            onsubmit: move |evt| {
+                evt.prevent_default();
+                nav.push(Route::Search { query: query() });
+            },
+            div { class: "control has-icons-left",
+                input { class: "input",
+                    r#type: "search",
+                    placeholder: "Search kanji, kana or romaji",
+                    value: "{query}",
+                    oninput: move |evt| query.set(evt.value()),
+                }
+                span { class: "icon is-left", i { class: "fas fa-search" } }
+            }
+        }
+    }
+}
+
+/// The current streak, with a check once today's goal is met.
+#[component]
+fn StreakBadge() -> Element {
+    let progress = use_resource(move || async move { progress().await });
+    let Some(Ok(progress)) = progress.cloned() else {
+        return rsx! {};
+    };
+    rsx! {
+        div { class: "navbar-item", title: progress.goal_label(),
+            span { class: "icon has-text-danger", i { class: "fas fa-fire" } }
+            span { "{progress.streak}" }
+            if progress.goal_met() {
+                span { class: "icon has-text-success", i { class: "fas fa-check" } }
+            }
         }
     }
 }
@@ -0,0 +1,111 @@
+use crate::core::data::card::Card;
+use crate::core::data::deck::Answer;
+use crate::core::data::practice_session::PracticeSession;
+use crate::core::data::{
+    bury_phrase, record_lapse, revert_lapse, revert_practice_card, suspend_phrase,
+    update_practice_cards,
+};
+use crate::views::error::ErrorNotice;
+use crate::views::practice::{PracticeSessionSection, PracticeSource};
+use dioxus::core::CapturedError;
+use dioxus::prelude::*;
+use futures_util::StreamExt;
+
+/// A write to a card's phrase, for an answer or for taking one back.
+enum Record {
+    Answer(Answer, Card),
+    Undo(Answer, Card),
+}
+
+async fn write_record(record: Record) -> Result<()> {
+    match record {
+        Record::Answer(Answer::Pass, card) => update_practice_cards(vec![card]).await?,
+        Record::Answer(Answer::Fail, card) => {
+            record_lapse(card).await?;
+        }
+        Record::Answer(Answer::Suspend, card) => suspend_phrase(card.id, true).await?,
+        Record::Answer(Answer::Bury, card) => bury_phrase(card.id, true).await?,
+        Record::Undo(Answer::Pass, card) => revert_practice_card(card).await?,
+        Record::Undo(Answer::Fail, card) => revert_lapse(card).await?,
+        Record::Undo(Answer::Suspend, card) => suspend_phrase(card.id, false).await?,
+        Record::Undo(Answer::Bury, card) => bury_phrase(card.id, false).await?,
+        Record::Answer(Answer::Learn | Answer::Repeat, _)
+        | Record::Undo(Answer::Learn | Answer::Repeat, _) => {}
+    }
+    Ok(())
+}
+
+/// A practice session in a modal, recording each answer against its phrase
+/// unless the session is a cram session.
+#[component]
+pub fn PracticeDialog(
+    source: PracticeSource,
+    title: String,
+    subtitle: String,
+    resume: Option<PracticeSession>,
+    onsave: EventHandler<()>,
+    onclose: EventHandler<()>,
+) -> Element {
+    let mut record_error = use_signal(|| None::<CapturedError>);
+    // Writes run one at a time in order, so undoing an answer waits for the answer to be written.
+    let recorder = use_coroutine(move |mut queue: UnboundedReceiver<Record>| async move {
+        while let Some(record) = queue.next().await {
+            if let Err(error) = write_record(record).await {
+                record_error.set(Some(error));
+            }
+        }
+    });
+    let records = !source.is_cram();
+    rsx! {
+        div { class: "modal is-active",
+            div { class: "modal-background" }
+            div { class: "modal-card",
+                header { class: "modal-card-head",
+                    div { class: "modal-card-title",
+                        p { class: "title", {title} }
+                        p { class: "subtitle", {subtitle} }
+                    }
+                    button { class: "delete", aria_label: "close", onclick: move |_| onclose.call(()) }
+                }
+                footer { class: "modal-card-foot",
+                    div { class: "container",
+                        if let Some(error) = record_error() {
+                            ErrorNotice { error }
+                        }
+                        PracticeSessionSection {
+                            source,
+                            resume,
+                            onsave: move |_| onsave.call(()),
+                            onclose,
+                            onundo: move |(answer, card)| if records { recorder.send(Record::Undo(answer, card)); },
+                            onpass: move |card| if records { recorder.send(Record::Answer(Answer::Pass, card)); },
+                            onfail: move |card| if records { recorder.send(Record::Answer(Answer::Fail, card)); },
+                            onsuspend: move |card| if records { recorder.send(Record::Answer(Answer::Suspend, card)); },
+                            onbury: move |card| if records { recorder.send(Record::Answer(Answer::Bury, card)); },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ResumeNotification(turns: usize, onresume: EventHandler<MouseEvent>) -> Element {
+    rsx! {
+        div { class: "notification is-info is-light m-3",
+            nav { class: "level is-mobile",
+                div { class: "level-left",
+                    div { class: "level-item",
+                        p { "You have an unfinished session with {turns} turns left." }
+                    }
+                }
+                div { class: "level-right",
+                    div { class: "level-item",
+                        button { class: "button is-info", onclick: onresume, "Resume session" }
+                    }
+                }
+            }
+        }
+    }
+}
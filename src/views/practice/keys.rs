@@ -0,0 +1,49 @@
+use crate::core::data::settings::{KeyBindings, KeyCommand};
+use dioxus::prelude::*;
+
+/// Takes focus when mounted and turns bound key presses into commands.
+#[component]
+pub fn KeyScope(
+    keys: KeyBindings,
+    commands: Vec<KeyCommand>,
+    oncommand: EventHandler<KeyCommand>,
+    children: Element,
+) -> Element {
+    let legend = commands
+        .iter()
+        .map(|&command| (keys.key(command).to_string(), command.label()))
+        .collect::<Vec<_>>();
+    rsx! {
+        div { tabindex: 0, style: "outline: none",
+            onmounted: move |evt| async move {
+                // Focus is best effort; clicks still work without it.
+                let _ = evt.set_focus(true).await;
+            },
+            onkeydown: move |evt| {
+                let name = key_name(&evt.key());
+                if let Some(command) = keys.command(&name).filter(|c| commands.contains(c)) {
+                    evt.prevent_default();
+                    evt.stop_propagation();
+                    oncommand.call(command);
+                }
+            },
+            {children}
+            p { class: "help has-text-centered mt-3",
+                for (key, label) in legend {
+                    span { class: "mx-2",
+                        span { class: "tag is-light", {key} }
+                        " {label}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The key's name as used in `KeyBindings`.
+fn key_name(key: &Key) -> String {
+    match key {
+        Key::Character(c) if c == " " => "Space".to_string(),
+        key => key.to_string(),
+    }
+}
@@ -1,11 +1,43 @@
 use crate::core::data::card::Card;
-use crate::core::data::{lesson_view, query_practice_cards};
-use deck::Deck;
+use crate::core::data::deck::{Answer, Deck, Removal};
+use crate::core::data::practice_session::{
+    finish_practice_session, save_practice_session, start_practice_session, PracticeSession,
+};
+use crate::core::data::settings::{user_settings, KeyBindings, KeyCommand};
+use crate::core::data::study::{study_cards, StudyFilter};
+use crate::core::data::{query_practice_cards, query_review_cards};
 use dioxus::prelude::*;
-use rand::rngs::StdRng;
+use keys::KeyScope;
 use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
-pub mod deck;
+pub mod dialog;
+pub mod keys;
+
+/// Where a session's cards come from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PracticeSource {
+    /// A lesson's due phrases, or only those with the tag.
+    Lesson { lesson_id: i64, tag: Option<String> },
+    /// Due phrases from every lesson.
+    Review,
+    /// Phrases matching a filter, drilled without changing their schedule.
+    Cram(StudyFilter),
+}
+
+impl PracticeSource {
+    pub fn lesson_id(&self) -> Option<i64> {
+        match self {
+            PracticeSource::Lesson { lesson_id, .. } => Some(*lesson_id),
+            PracticeSource::Review => None,
+            PracticeSource::Cram(filter) => filter.lesson_id,
+        }
+    }
+
+    pub fn is_cram(&self) -> bool {
+        matches!(self, PracticeSource::Cram(_))
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum SessionState {
@@ -16,34 +48,174 @@ enum SessionState {
     Done { deck: Deck },
 }
 
+/// Runs a practice session with cards from `source`, saving the deck on the server after
+/// each answer so that it can be resumed from `resume` later.
 #[component]
 pub fn PracticeSessionSection(
+    source: PracticeSource,
+    resume: Option<PracticeSession>,
     onsave: EventHandler<Vec<Card>>,
+    onclose: EventHandler<()>,
+    onundo: EventHandler<(Answer, Card)>,
     onpass: EventHandler<Card>,
+    onfail: EventHandler<Card>,
+    onsuspend: EventHandler<Card>,
+    onbury: EventHandler<Card>,
 ) -> Element {
-    let mut session = use_signal(|| SessionState::Start);
-
-    let mut start_action = use_action(move |_input: ()| async move {
-        let lesson = lesson_view().await.unwrap().unwrap();
-        let cards = query_practice_cards(lesson.lesson_id).await.unwrap();
-        assert!(cards.len() > 0);
-        let rng = StdRng::seed_from_u64(rand::random());
-        let deck = Deck::from_cards(cards, rng);
-        *session.write() = SessionState::Prompt { deck };
+    let settings = use_loader(move || async move { user_settings().await })?;
+    let keys = settings().keys;
+    let mut session_id = use_signal(|| resume.as_ref().map(|resume| resume.session_id));
+    let mut session = use_signal(|| match &resume {
+        Some(resume) => SessionState::Prompt {
+            deck: resume.deck.clone(),
+        },
+        None => SessionState::Start,
+    });
+
+    let start_source = source.clone();
+    let mut start_action = use_action(move |_input: ()| {
+        let source = start_source.clone();
+        async move {
+            let cards = match &source {
+                PracticeSource::Lesson { lesson_id, tag } => {
+                    query_practice_cards(*lesson_id, tag.clone()).await?
+                }
+                PracticeSource::Review => query_review_cards().await?,
+                PracticeSource::Cram(filter) => study_cards(filter.clone()).await?,
+            };
+            if cards.is_empty() {
+                return Ok(());
+            }
+            let rng = ChaCha8Rng::seed_from_u64(rand::random());
+            let deck = Deck::from_cards(cards, rng);
+            let id =
+                start_practice_session(source.lesson_id(), source.is_cram(), deck.clone()).await?;
+            *session_id.write() = Some(id);
+            *session.write() = SessionState::Prompt { deck };
+            Ok(()) as Result<()>
+        }
+    });
+
+    let mut persist_action = use_action(move |state: SessionState| async move {
+        let Some(id) = *session_id.peek() else {
+            return Ok(());
+        };
+        match state {
+            SessionState::Prompt { deck } => save_practice_session(id, deck).await?,
+            // Saving again reopens the session if an answer is undone.
+            SessionState::Done { deck } => {
+                save_practice_session(id, deck).await?;
+                finish_practice_session(id).await?;
+            }
+            // Start follows Done.
+            SessionState::Start => {
+                finish_practice_session(id).await?;
+                *session_id.write() = None;
+            }
+            SessionState::Learn { .. } | SessionState::Check { .. } => {}
+        }
         Ok(()) as Result<()>
     });
+    use_effect(move || {
+        persist_action.call(session());
+    });
 
     match session() {
         SessionState::Start => rsx! {
-            button {
-                class: "button is-primary",
-                onclick: move |_| {
-                    start_action.call(());
+            KeyScope { keys,
+                commands: vec![KeyCommand::Flip, KeyCommand::Close],
+                oncommand: move |command| match command {
+                    KeyCommand::Close => onclose.call(()),
+                    _ => {
+                        start_action.call(());
+                    }
                 },
-                "Start"
+                button {
+                    class: "button is-primary",
+                    onclick: move |_| {
+                        start_action.call(());
+                    },
+                    "Start"
+                }
             }
         },
         SessionState::Done { deck } => rsx! {
+            DoneSection { deck, keys, session, onsave, onclose, onundo }
+        },
+        SessionState::Prompt { deck } => rsx! {
+            PromptSection { deck, keys, session, onclose, onundo }
+        },
+        SessionState::Learn { deck } => rsx! {
+            LearnSection { deck, keys, session, onclose }
+        },
+        SessionState::Check { deck } => rsx! {
+            CheckSection { deck, keys, session, onclose, onpass, onfail, onsuspend, onbury }
+        },
+    }
+}
+
+/// Takes back the deck's last answer, returning to the card it was given for.
+fn take_back(
+    deck: Deck,
+    mut session: WriteSignal<SessionState>,
+    onundo: EventHandler<(Answer, Card)>,
+) {
+    let Some(answer) = deck.last_answer() else {
+        return;
+    };
+    let deck = deck.undo();
+    onundo.call((answer, deck.top.clone()));
+    *session.write() = match answer {
+        Answer::Learn => SessionState::Learn { deck },
+        _ => SessionState::Check { deck },
+    };
+}
+
+#[component]
+fn UndoButton(
+    deck: Deck,
+    session: WriteSignal<SessionState>,
+    onundo: EventHandler<(Answer, Card)>,
+) -> Element {
+    if deck.last_answer().is_none() {
+        return rsx! {};
+    }
+    rsx! {
+        button { class: "button is-small is-light",
+            title: "Take back the last answer",
+            onclick: move |_| take_back(deck.clone(), session, onundo),
+            "Undo"
+        }
+    }
+}
+
+#[component]
+fn DoneSection(
+    deck: Deck,
+    keys: KeyBindings,
+    session: WriteSignal<SessionState>,
+    onsave: EventHandler<Vec<Card>>,
+    onclose: EventHandler<()>,
+    onundo: EventHandler<(Answer, Card)>,
+) -> Element {
+    let stats = deck.stats.clone();
+    let command = use_callback({
+        let deck = deck.clone();
+        move |command: KeyCommand| match command {
+            KeyCommand::Flip => {
+                *session.write() = SessionState::Start;
+                onsave.call(deck.clone().into_cards());
+            }
+            KeyCommand::Undo => take_back(deck.clone(), session, onundo),
+            _ => onclose.call(()),
+        }
+    });
+    let mut commands = vec![KeyCommand::Flip, KeyCommand::Close];
+    if deck.last_answer().is_some() {
+        commands.insert(1, KeyCommand::Undo);
+    }
+    rsx! {
+        KeyScope { keys, commands, oncommand: command,
             div { class: "block",
                 div { class: "title is-5", "Stats" }
                 div { class: "container",
@@ -51,95 +223,98 @@ pub fn PracticeSessionSection(
                         div { class: "control",
                             div { class: "tags has-addons",
                                 span { class: "tag is-dark", "Passes"}
-                                span { class: "tag is-success", "{deck.stats.passed}"}
+                                span { class: "tag is-success", "{stats.passed}"}
                             }
                         }
                         div { class: "control",
                             div { class: "tags has-addons",
                                 span { class: "tag is-dark", "Fails"}
-                                span { class: "tag is-warning", "{deck.stats.failed}"}
+                                span { class: "tag is-warning", "{stats.failed}"}
                             }
                         }
                         div { class: "control",
                             div { class: "tags has-addons",
                                 span { class: "tag is-dark", "Repeats"}
-                                span { class: "tag is-link", "{deck.stats.repeated}"}
+                                span { class: "tag is-link", "{stats.repeated}"}
                             }
                         }
                         div { class: "control",
                             div { class: "tags has-addons",
                                 span { class: "tag is-dark", "Learns"}
-                                span { class: "tag is-info", "{deck.stats.learned}"}
+                                span { class: "tag is-info", "{stats.learned}"}
                             }
                         }
                     }
                 }
             }
-            div { class: "block",
+            div { class: "block buttons",
                 button {
                     class: "button is-primary",
-                    onclick: move |_| {
-                        *session.write() = SessionState::Start;
-                        onsave.call(deck.clone().into_cards());
-                    },
+                    onclick: move |_| command(KeyCommand::Flip),
                     "Done"
                 }
+                UndoButton { deck: deck.clone(), session, onundo }
             }
-        },
-        SessionState::Prompt { deck } => rsx! {
-            PromptSection { deck, session }
-        },
-        SessionState::Learn { deck } => rsx! {
-            LearnSection { deck, session }
-        },
-        SessionState::Check { deck } => rsx! {
-            CheckSection { deck, session, onpass }
-        },
+        }
     }
 }
 
 #[component]
-fn PromptSection(deck: Deck, session: WriteSignal<SessionState>) -> Element {
+fn PromptSection(
+    deck: Deck,
+    keys: KeyBindings,
+    session: WriteSignal<SessionState>,
+    onclose: EventHandler<()>,
+    onundo: EventHandler<(Answer, Card)>,
+) -> Element {
     let title = deck.top.front.kanji.clone();
     let turns = deck.turns_remaining();
+    let command = use_callback({
+        let deck = deck.clone();
+        move |command: KeyCommand| match command {
+            KeyCommand::Flip => *session.write() = SessionState::Check { deck: deck.clone() },
+            KeyCommand::Learn => *session.write() = SessionState::Learn { deck: deck.clone() },
+            KeyCommand::Undo => take_back(deck.clone(), session, onundo),
+            _ => onclose.call(()),
+        }
+    });
+    let mut commands = vec![KeyCommand::Flip, KeyCommand::Learn, KeyCommand::Close];
+    if deck.last_answer().is_some() {
+        commands.insert(2, KeyCommand::Undo);
+    }
     rsx! {
-        div { class: "card",
-            div { class: "card-content",
-                nav { class: "level is-mobile",
-                    div { class: "level-left",
-                        div { class: "level-item",
-                            span { class: "heading", "Read and translate"}
+        KeyScope { keys, commands, oncommand: command,
+            div { class: "card",
+                div { class: "card-content",
+                    nav { class: "level is-mobile",
+                        div { class: "level-left",
+                            div { class: "level-item",
+                                span { class: "heading", "Read and translate"}
+                            }
                         }
-                    }
-                    div { class: "level-right",
-                        div { class: "level-item",
-                            span { class: "tag is-light", "{turns}"}
+                        div { class: "level-right",
+                            div { class: "level-item",
+                                UndoButton { deck: deck.clone(), session, onundo }
+                            }
+                            div { class: "level-item",
+                                span { class: "tag is-light", "{turns}"}
+                            }
                         }
                     }
+                    section { class: "section has-text-centered",
+                        h1 { class: "title", {title} }
+                    }
                 }
-                section { class: "section has-text-centered",
-                    h1 { class: "title", {title} }
-                }
-            }
-            footer { class: "card-footer",
-                a { class: "card-footer-item",
-                    href: "#",
-                    onclick: {
-                        let deck = deck.clone();
-                        move |_| {
-                            *session.write() = SessionState::Learn { deck: deck.clone() };
-                        }
-                    },
-                    "Learn"
-                }
-                a { class: "card-footer-item", href: "#",
-                    onclick: {
-                        let deck = deck.clone();
-                        move |_| {
-                            *session.write() = SessionState::Check { deck: deck.clone() };
-                        }
-                    },
-                    "Check"
+                footer { class: "card-footer",
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| command(KeyCommand::Learn),
+                        "Learn"
+                    }
+                    a { class: "card-footer-item", href: "#",
+                        onclick: move |_| command(KeyCommand::Flip),
+                        "Check"
+                    }
                 }
             }
         }
@@ -147,75 +322,135 @@ fn PromptSection(deck: Deck, session: WriteSignal<SessionState>) -> Element {
 }
 
 #[component]
-fn LearnSection(deck: Deck, session: WriteSignal<SessionState>) -> Element {
+fn LearnSection(
+    deck: Deck,
+    keys: KeyBindings,
+    session: WriteSignal<SessionState>,
+    onclose: EventHandler<()>,
+) -> Element {
     let card = deck.top.clone();
     let turns = deck.turns_remaining();
+    let command = use_callback(move |command: KeyCommand| match command {
+        KeyCommand::Flip => {
+            let deck = deck.clone().learn();
+            *session.write() = SessionState::Prompt { deck };
+        }
+        _ => onclose.call(()),
+    });
     rsx! {
-        div { class: "card",
-            div { class: "card-content", BackContent{ card, turns } }
-            footer { class: "card-footer",
-                a { class: "card-footer-item",
-                    href: "#",
-                    onclick: move |_| {
-                        let deck = deck.clone().learn();
-                        *session.write() = SessionState::Prompt { deck };
-                    },
-                    "Next" }
+        KeyScope { keys,
+            commands: vec![KeyCommand::Flip, KeyCommand::Close],
+            oncommand: command,
+            div { class: "card",
+                div { class: "card-content", BackContent{ card, turns } }
+                footer { class: "card-footer",
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| command(KeyCommand::Flip),
+                        "Next" }
+                }
             }
         }
     }
 }
 
 #[component]
+#[allow(clippy::too_many_arguments)]
 fn CheckSection(
     deck: Deck,
+    keys: KeyBindings,
     session: WriteSignal<SessionState>,
+    onclose: EventHandler<()>,
     onpass: EventHandler<Card>,
+    onfail: EventHandler<Card>,
+    onsuspend: EventHandler<Card>,
+    onbury: EventHandler<Card>,
 ) -> Element {
     let card = deck.top.clone();
     let turns = deck.turns_remaining();
+    let command = use_callback(move |command: KeyCommand| {
+        let deck = deck.clone();
+        let top = deck.top.clone();
+        *session.write() = match command {
+            KeyCommand::Fail => {
+                onfail.call(top);
+                SessionState::Prompt { deck: deck.fail() }
+            }
+            KeyCommand::Repeat => SessionState::Prompt {
+                deck: deck.repeat(),
+            },
+            KeyCommand::Pass => {
+                onpass.call(top);
+                let deck = deck.pass();
+                if deck.is_all_passed() {
+                    SessionState::Done { deck }
+                } else {
+                    SessionState::Prompt { deck }
+                }
+            }
+            KeyCommand::Bury | KeyCommand::Suspend => {
+                let answer = if command == KeyCommand::Bury {
+                    onbury.call(top);
+                    Answer::Bury
+                } else {
+                    onsuspend.call(top);
+                    Answer::Suspend
+                };
+                match deck.remove_top(answer) {
+                    Removal::Next(deck) if !deck.is_all_passed() => SessionState::Prompt { deck },
+                    // Nothing left to practice, so sum up the session as after the last pass.
+                    Removal::Next(deck) | Removal::Emptied(deck) => SessionState::Done { deck },
+                }
+            }
+            _ => {
+                onclose.call(());
+                return;
+            }
+        };
+    });
     rsx! {
-        div { class: "card",
-            div { class: "card-content", BackContent{ card, turns } }
-            footer { class: "card-footer",
-                a { class: "card-footer-item",
-                    href: "#",
-                    onclick: {
-                        let deck = deck.clone();
-                        move |_| {
-                            let deck = deck.clone().fail();
-                            *session.write() = SessionState::Prompt { deck };
+        KeyScope { keys,
+            commands: vec![
+                KeyCommand::Fail,
+                KeyCommand::Repeat,
+                KeyCommand::Pass,
+                KeyCommand::Bury,
+                KeyCommand::Suspend,
+                KeyCommand::Close,
+            ],
+            oncommand: command,
+            div { class: "card",
+                div { class: "card-content",
+                    BackContent{ card, turns }
+                    div { class: "buttons are-small is-right",
+                        button { class: "button is-light",
+                            title: "Skip until tomorrow",
+                            onclick: move |_| command(KeyCommand::Bury),
+                            "Bury"
                         }
-                    },
-                    "Fail"
-                }
-                a { class: "card-footer-item",
-                    href: "#",
-                    onclick: {
-                        let deck = deck.clone();
-                        move |_| {
-                            let deck = deck.clone().repeat();
-                            *session.write() = SessionState::Prompt { deck};
-
+                        button { class: "button is-light",
+                            title: "Hide until unsuspended",
+                            onclick: move |_| command(KeyCommand::Suspend),
+                            "Suspend"
                         }
-                    },
-                    "Repeat"
+                    }
                 }
-                a { class: "card-footer-item",
-                    href: "#",
-                    onclick: {
-                        let deck = deck.clone();
-                        move |_| {
-                            onpass.call(deck.top.clone());
-                            let deck = deck.clone().pass();
-                            *session.write() = if deck.is_all_passed() {
-                                SessionState::Done { deck }
-                            } else {
-                                SessionState::Prompt { deck}
-                            };
-                        }
-                    },
-                    "Pass"
+                footer { class: "card-footer",
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| command(KeyCommand::Fail),
+                        "Fail"
+                    }
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| command(KeyCommand::Repeat),
+                        "Repeat"
+                    }
+                    a { class: "card-footer-item",
+                        href: "#",
+                        onclick: move |_| command(KeyCommand::Pass),
+                        "Pass"
+                    }
                 }
             }
         }
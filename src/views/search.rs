@@ -0,0 +1,63 @@
+use crate::core::data::search::{search_phrases, SEARCH_LIMIT};
+use crate::Route;
+use dioxus::prelude::*;
+
+/// The Search page component that will be rendered when the current route is `[Route::Search]`
+#[component]
+pub fn Search(query: String) -> Element {
+    let hits = use_loader(use_reactive!(
+        |query| async move { search_phrases(query).await }
+    ))?;
+    let hits = hits();
+    rsx! {
+        div { class: "container",
+            h1 { class: "title", "Search" }
+            if query.trim().is_empty() {
+                p { "Type kanji, kana or romaji into the search box." }
+            } else if hits.is_empty() {
+                p { "No phrases match “{query}”." }
+            } else {
+                p { class: "help block",
+                    if hits.len() == SEARCH_LIMIT {
+                        "Showing the first {SEARCH_LIMIT} phrases matching “{query}”."
+                    } else {
+                        "{hits.len()} phrases match “{query}”."
+                    }
+                }
+                table { class: "table is-striped is-hoverable is-fullwidth",
+                    thead {
+                        tr {
+                            th { "Prompt" }
+                            th { "Reading" }
+                            th { "Meaning" }
+                            th { "Lesson" }
+                        }
+                    }
+                    tbody {
+                        for hit in hits {
+                            tr { key: "{hit.phrase_id}",
+                                td {
+                                    Link {
+                                        to: Route::Lesson { lesson_id: Some(hit.lesson_id), phrase_id: Some(hit.phrase_id) },
+                                        "{hit.prompt}"
+                                    }
+                                    if !hit.sentence.is_empty() {
+                                        p { class: "help", "{hit.sentence}" }
+                                    }
+                                }
+                                td { "{hit.reading}" }
+                                td { "{hit.meaning}" }
+                                td {
+                                    Link {
+                                        to: Route::Lesson { lesson_id: Some(hit.lesson_id), phrase_id: None },
+                                        "{hit.lesson_title}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,26 @@
 use crate::core::data;
+use crate::core::data::account::{delete_account, import_account, AccountArchive};
+use crate::core::data::lesson_view;
+use crate::core::data::settings::{
+    lesson_settings, save_lesson_settings, save_user_settings, simulate_date, simulated_date,
+    user_settings, DailyGoal, GoalKind, KeyBindings, KeyCommand, LessonSettings, UserSettings,
+    ALL_COMMANDS,
+};
+use crate::core::data::token::{
+    api_tokens, create_api_token, revoke_api_token, sign_in, sign_out, signed_in_user, NewApiToken,
+};
+use crate::views::error::ErrorNotice;
+use crate::views::form::{form_number, form_text};
+use dioxus::html::FileData;
 use dioxus::prelude::*;
+use std::collections::HashMap;
 
 /// The Start page component that will be rendered when the current route is `[Route::Start]`
 #[component]
 pub fn Start() -> Element {
     let users = use_resource(move || async move {
-        let users = data::users().await.unwrap();
-        users.into_iter().map(|user| user.id).collect::<Vec<_>>()
+        let users = data::users().await?;
+        Ok(users.into_iter().map(|user| user.id).collect::<Vec<_>>()) as Result<Vec<_>>
     });
     rsx! {
         div {
@@ -17,11 +31,14 @@ pub fn Start() -> Element {
                     None => rsx! {
                         "Loading..."
                     },
-                    Some(users) => rsx! {
+                    Some(Ok(users)) => rsx! {
                         for user in users.iter() {
                             p {"{user}"}
                         }
-                    }
+                    },
+                    Some(Err(error)) => rsx! {
+                        ErrorNotice { error }
+                    },
                 }
             }
             form {
@@ -29,6 +46,537 @@ pub fn Start() -> Element {
                 input { type: "text", placeholder: "Url" }
                 input { type: "submit", value: "Submit" }
             }
+            SettingsForm {}
+            SignInForm {}
+            AccountForm {}
+            SimulateDateForm {}
+        }
+    }
+}
+
+#[component]
+fn SettingsForm() -> Element {
+    let mut settings = use_loader(move || async move { user_settings().await })?;
+    let lesson = use_loader(move || async move { lesson_view(None).await })?;
+    let mut save = use_action(move |new_settings: UserSettings| async move {
+        save_user_settings(new_settings).await?;
+        settings.restart();
+        Ok(()) as Result<()>
+    });
+    let current = settings();
+    rsx! {
+        form { class: "box mt-5",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                let current = settings();
+                let number = |name: &str, fallback: usize| form_number(&data, name).unwrap_or(fallback);
+                save.call(UserSettings {
+                    leech_threshold: number("leech_threshold", current.leech_threshold),
+                    session_size: number("session_size", current.session_size),
+                    new_per_day: number("new_per_day", current.new_per_day),
+                    reviews_per_day: number("reviews_per_day", current.reviews_per_day),
+                    time_zone: form_text(&data, "time_zone"),
+                    day_start_hour: number("day_start_hour", current.day_start_hour.into())
+                        .min(23) as u8,
+                    clock_offset_days: current.clock_offset_days,
+                    keys: {
+                        let key = |command: KeyCommand| {
+                            let name = format!("key_{}", command.label().to_lowercase());
+                            form_text(&data, &name)
+                                .unwrap_or_else(|| current.keys.key(command).to_string())
+                        };
+                        KeyBindings {
+                            flip: key(KeyCommand::Flip),
+                            learn: key(KeyCommand::Learn),
+                            fail: key(KeyCommand::Fail),
+                            repeat: key(KeyCommand::Repeat),
+                            pass: key(KeyCommand::Pass),
+                            bury: key(KeyCommand::Bury),
+                            suspend: key(KeyCommand::Suspend),
+                            undo: key(KeyCommand::Undo),
+                            close: key(KeyCommand::Close),
+                        }
+                    },
+                    goal: DailyGoal {
+                        kind: form_text(&data, "goal_kind")
+                            .and_then(|kind| GoalKind::parse(&kind))
+                            .unwrap_or(current.goal.kind),
+                        amount: number("goal_amount", current.goal.amount).max(1),
+                    },
+                    streak_freezes: number("streak_freezes", current.streak_freezes),
+                });
+            },
+            h2 { class: "title is-5", "Practice" }
+            NumberField { label: "Session size", name: "session_size", min: 1,
+                value: Some(current.session_size),
+                help: "Cards in each practice session",
+            }
+            NumberField { label: "New cards per day", name: "new_per_day", min: 0,
+                value: Some(current.new_per_day),
+                help: "Phrases seen for the first time each day",
+            }
+            NumberField { label: "Reviews per day", name: "reviews_per_day", min: 0,
+                value: Some(current.reviews_per_day),
+                help: "Phrases brought back for review each day",
+            }
+            NumberField { label: "Leech threshold", name: "leech_threshold", min: 1,
+                value: Some(current.leech_threshold),
+                help: "Lapses before a phrase is flagged as a leech",
+            }
+            h2 { class: "title is-5", "Day" }
+            div { class: "field",
+                label { class: "label", "Time zone" }
+                div { class: "control",
+                    input { class: "input", type: "text", name: "time_zone",
+                        placeholder: "Server time zone",
+                        value: current.time_zone.clone().unwrap_or_default(),
+                    }
+                }
+                p { class: "help", "A time zone name such as Asia/Tokyo or America/New_York" }
+            }
+            NumberField { label: "Day starts at", name: "day_start_hour", min: 0,
+                value: Some(current.day_start_hour.into()),
+                help: "Hour of the day (0-23) when practiced phrases become ready again",
+            }
+            h2 { class: "title is-5", "Goal" }
+            div { class: "field",
+                label { class: "label", "Daily goal counts" }
+                div { class: "control",
+                    div { class: "select",
+                        select { name: "goal_kind",
+                            for kind in [GoalKind::Cards, GoalKind::Minutes] {
+                                option { value: kind.as_str(), selected: kind == current.goal.kind,
+                                    {kind.as_str()}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            NumberField { label: "Daily goal", name: "goal_amount", min: 1,
+                value: Some(current.goal.amount),
+                help: "Cards or minutes to practice each day",
+            }
+            NumberField { label: "Streak freezes", name: "streak_freezes", min: 0,
+                value: Some(current.streak_freezes),
+                help: "Missed days in a row that don't break a streak",
+            }
+            h2 { class: "title is-5", "Practice keys" }
+            p { class: "block",
+                "Key names such as Space, Enter or Escape, or a single letter or digit."
+            }
+            div { class: "columns is-multiline",
+                for command in ALL_COMMANDS {
+                    div { class: "column is-one-third",
+                        div { class: "field",
+                            label { class: "label", {command.label()} }
+                            div { class: "control",
+                                input { class: "input", type: "text",
+                                    name: format!("key_{}", command.label().to_lowercase()),
+                                    value: current.keys.key(command).to_string(),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(Err(error)) = save.value() {
+                p { class: "help is-danger", "{error}" }
+            }
+            button { class: "button is-primary", type: "submit", "Save" }
+        }
+        if let Some(lesson) = lesson() {
+            LessonSettingsForm { lesson_id: lesson.lesson_id, title: lesson.title }
+        }
+    }
+}
+
+/// Tokens for scripts using the REST API. A new token's secret is shown once, right after it's made.
+#[component]
+fn ApiTokensForm() -> Element {
+    let mut tokens = use_loader(move || async move { api_tokens().await })?;
+    let mut create = use_action(move |name: String| async move {
+        let token = create_api_token(name).await?;
+        tokens.restart();
+        Ok(token) as Result<NewApiToken>
+    });
+    let mut revoke = use_action(move |token_id: i64| async move {
+        revoke_api_token(token_id).await?;
+        tokens.restart();
+        Ok(()) as Result<()>
+    });
+    rsx! {
+        form { class: "box mt-5",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                create.call(form_text(&data, "name").unwrap_or_default());
+            },
+            h2 { class: "title is-5", "API tokens" }
+            p { class: "block",
+                "Scripts send a token as "
+                code { "Authorization: Bearer <token>" }
+                " to use the API described at "
+                a { href: "/api/v1/openapi.json", "/api/v1/openapi.json" }
+                "."
+            }
+            if let Some(Ok(created)) = create.value() {
+                div { class: "notification is-success is-light",
+                    p { "Copy the token for {created.read().token.name} now, it won't be shown again:" }
+                    pre { "{created.read().secret}" }
+                }
+            }
+            if !tokens().is_empty() {
+                table { class: "table is-fullwidth",
+                    thead {
+                        tr {
+                            th { "Name" }
+                            th { "Created" }
+                            th { "Last used" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for token in tokens() {
+                            tr { key: "{token.token_id}",
+                                td { "{token.name}" }
+                                td { "{token.created_at}" }
+                                td { {token.last_used_at.clone().unwrap_or_else(|| "Never".to_string())} }
+                                td {
+                                    button { class: "button is-small is-danger is-light", type: "button",
+                                        onclick: move |_| {
+                                            revoke.call(token.token_id);
+                                        },
+                                        "Revoke"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "field has-addons",
+                div { class: "control is-expanded",
+                    input { class: "input", type: "text", name: "name", placeholder: "Token name" }
+                }
+                div { class: "control",
+                    button { class: "button is-primary", type: "submit", "Create token" }
+                }
+            }
+            if let Some(Err(error)) = create.value() {
+                p { class: "help is-danger", "{error}" }
+            }
+            if let Some(Err(error)) = revoke.value() {
+                p { class: "help is-danger", "{error}" }
+            }
+        }
+    }
+}
+
+/// Signs the browser in with an API token, which admin tools check to know who is asking, and
+/// once signed in, manages the user's tokens.
+#[component]
+fn SignInForm() -> Element {
+    let mut user = use_loader(move || async move { signed_in_user().await })?;
+    let mut enter = use_action(move |secret: String| async move {
+        sign_in(secret).await?;
+        user.restart();
+        Ok(()) as Result<()>
+    });
+    let mut leave = use_action(move || async move {
+        sign_out().await?;
+        user.restart();
+        Ok(()) as Result<()>
+    });
+    rsx! {
+        form { class: "box mt-5",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                enter.call(form_text(&data, "secret").unwrap_or_default());
+            },
+            h2 { class: "title is-5", "Sign in" }
+            p { class: "block",
+                "Backups, simulated dates and deleting accounts act only for a browser signed in "
+                "with an admin's API token. The first token comes from "
+                code { "kanjikoto-admin token create <user>" }
+                "."
+            }
+            if let Some(user_id) = user() {
+                div { class: "field is-grouped",
+                    p { class: "control", "Signed in as {user_id}." }
+                    div { class: "control",
+                        button { class: "button is-small", type: "button",
+                            onclick: move |_| {
+                                leave.call();
+                            },
+                            "Sign out"
+                        }
+                    }
+                }
+            } else {
+                div { class: "field has-addons",
+                    div { class: "control is-expanded",
+                        input { class: "input", type: "password", name: "secret", placeholder: "API token" }
+                    }
+                    div { class: "control",
+                        button { class: "button is-primary", type: "submit", "Sign in" }
+                    }
+                }
+            }
+            if let Some(Err(error)) = enter.value() {
+                ErrorNotice { error }
+            }
+            if let Some(Err(error)) = leave.value() {
+                ErrorNotice { error }
+            }
+        }
+        if user().is_some() {
+            ApiTokensForm {}
+        }
+    }
+}
+
+/// Downloading and importing account archives, and for admins, deleting other users' accounts.
+#[component]
+fn AccountForm() -> Element {
+    let mut users = use_loader(move || async move { data::users().await })?;
+    let mut import = use_action(move |file: FileData| async move {
+        let archive = serde_json::from_str::<AccountArchive>(&file.read_string().await?)?;
+        let lesson_ids = import_account(archive).await?;
+        Ok(lesson_ids.len()) as Result<usize>
+    });
+    let mut delete = use_action(move |user_id: String| async move {
+        delete_account(user_id.clone()).await?;
+        users.restart();
+        Ok(user_id) as Result<String>
+    });
+    // The user whose Delete button was pressed once and now asks to be confirmed.
+    let mut confirming = use_signal(|| None::<String>);
+    let others = users()
+        .into_iter()
+        .filter(|user| !user.is_admin)
+        .collect::<Vec<_>>();
+    rsx! {
+        div { class: "box mt-5",
+            h2 { class: "title is-5", "Account data" }
+            p { class: "block",
+                "An archive holds your lessons, phrases, tags, review history and settings as JSON. "
+                "Importing one adds its lessons beside yours and replaces your settings with its. "
+                "Both need a browser signed in as you."
+            }
+            div { class: "field is-grouped",
+                div { class: "control",
+                    a { class: "button", href: "/api/account/export", download: "kanjikoto.json",
+                        "Download archive"
+                    }
+                }
+                div { class: "control",
+                    div { class: "file",
+                        label { class: "file-label",
+                            input { class: "file-input", type: "file", accept: ".json,application/json",
+                                onchange: move |evt| {
+                                    if let Some(file) = evt.files().into_iter().next() {
+                                        import.call(file);
+                                    }
+                                },
+                            }
+                            span { class: "file-cta",
+                                span { class: "file-label",
+                                    if import.pending() { "Importing..." } else { "Import archive" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(Ok(lessons)) = import.value() {
+                div { class: "notification is-success is-light", "Imported {lessons} lessons." }
+            }
+            if let Some(Err(error)) = import.value() {
+                ErrorNotice { error }
+            }
+            if !others.is_empty() {
+                h3 { class: "title is-6 mt-5", "Other accounts" }
+                p { class: "block",
+                    "Downloading another user's archive or deleting their account needs a browser "
+                    "signed in as an admin. Deleting removes everything they made, so download "
+                    "their archive first if they want to keep it."
+                }
+                if let Some(Ok(user_id)) = delete.value() {
+                    div { class: "notification is-success is-light", "Deleted {user_id}." }
+                }
+                if let Some(Err(error)) = delete.value() {
+                    ErrorNotice { error }
+                }
+                table { class: "table is-fullwidth",
+                    tbody {
+                        for user in others {
+                            tr { key: "{user.id}",
+                                td { "{user.id}" }
+                                td {
+                                    ArchiveLink { user_id: user.id.clone() }
+                                }
+                                td {
+                                    if confirming() == Some(user.id.clone()) {
+                                        div { class: "buttons",
+                                            button { class: "button is-small is-danger",
+                                                onclick: move |_| {
+                                                    confirming.set(None);
+                                                    delete.call(user.id.clone());
+                                                },
+                                                "Delete everything"
+                                            }
+                                            button { class: "button is-small",
+                                                onclick: move |_| confirming.set(None),
+                                                "Cancel"
+                                            }
+                                        }
+                                    } else {
+                                        button { class: "button is-small is-danger is-light",
+                                            onclick: move |_| confirming.set(Some(user.id.clone())),
+                                            "Delete"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Downloads another user's account archive, which the server allows only for admins.
+#[component]
+fn ArchiveLink(user_id: String) -> Element {
+    rsx! {
+        a { class: "button is-small",
+            href: "/api/account/export?user_id={user_id}",
+            download: "kanjikoto-{user_id}.json",
+            "Download archive"
+        }
+    }
+}
+
+/// Admin-only tool for checking scheduling days ahead without waiting.
+#[component]
+fn SimulateDateForm() -> Element {
+    let mut simulated = use_loader(move || async move { simulated_date().await })?;
+    let mut simulate = use_action(move |date: Option<String>| async move {
+        simulate_date(date).await?;
+        simulated.restart();
+        Ok(()) as Result<()>
+    });
+    let current = simulated();
+    rsx! {
+        form { class: "box mt-5",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                if let Some(FormValue::Text(date)) = data.get("date") {
+                    if !date.trim().is_empty() {
+                        simulate.call(Some(date.trim().to_string()));
+                    }
+                }
+            },
+            h2 { class: "title is-5", "Simulate date" }
+            p { class: "block",
+                if current.offset_days == 0 {
+                    "Today is {current.today}."
+                } else {
+                    "Simulating {current.today}, {current.offset_days} days from the real date."
+                }
+            }
+            div { class: "field",
+                label { class: "label", "Date" }
+                div { class: "control",
+                    input { class: "input", type: "date", name: "date", value: current.today.clone() }
+                }
+                p { class: "help",
+                    "Practice and scheduling act as if this were today. Only a browser signed in "
+                    "as an admin may change it."
+                }
+            }
+            if let Some(Err(error)) = simulate.value() {
+                p { class: "help is-danger", "{error}" }
+            }
+            div { class: "buttons",
+                button { class: "button is-primary", type: "submit", "Simulate" }
+                button { class: "button", type: "button",
+                    disabled: current.offset_days == 0,
+                    onclick: move |_| {
+                        simulate.call(None);
+                    },
+                    "Use real date"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LessonSettingsForm(lesson_id: i64, title: String) -> Element {
+    let mut settings = use_loader(move || async move { lesson_settings(lesson_id).await })?;
+    let mut save = use_action(move |new_settings: LessonSettings| async move {
+        save_lesson_settings(new_settings).await?;
+        settings.restart();
+        Ok(()) as Result<()>
+    });
+    let current = settings();
+    rsx! {
+        form { class: "box mt-5",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                save.call(LessonSettings {
+                    lesson_id,
+                    session_size: form_number(&data, "session_size"),
+                    new_per_day: form_number(&data, "new_per_day"),
+                    reviews_per_day: form_number(&data, "reviews_per_day"),
+                    review_weight: form_number(&data, "review_weight"),
+                });
+            },
+            h2 { class: "title is-5", "{title}" }
+            p { class: "block", "Leave a field empty to use the practice settings above." }
+            NumberField { label: "Session size", name: "session_size", min: 1,
+                value: current.session_size,
+            }
+            NumberField { label: "New cards per day", name: "new_per_day", min: 0,
+                value: current.new_per_day,
+            }
+            NumberField { label: "Reviews per day", name: "reviews_per_day", min: 0,
+                value: current.reviews_per_day,
+            }
+            NumberField { label: "Review weight", name: "review_weight", min: 0,
+                value: current.review_weight,
+                help: "Share of review-everything sessions, relative to other lessons (default 1, 0 to leave out)",
+            }
+            button { class: "button is-primary", type: "submit", "Save" }
+        }
+    }
+}
+
+#[component]
+fn NumberField(
+    label: String,
+    name: String,
+    min: usize,
+    value: Option<usize>,
+    help: Option<String>,
+) -> Element {
+    let value = value.map(|value| value.to_string()).unwrap_or_default();
+    rsx! {
+        div { class: "field",
+            label { class: "label", {label} }
+            div { class: "control",
+                input { class: "input", type: "number", min: "{min}", name, value }
+            }
+            if let Some(help) = help {
+                p { class: "help", {help} }
+            }
         }
     }
 }
@@ -0,0 +1,190 @@
+use crate::core::data::history::{daily_stats, session_history, DailyStats};
+use dioxus::prelude::*;
+
+/// Days shown in the charts.
+const CHART_DAYS: usize = 30;
+/// Weeks shown in the heatmap.
+const HEATMAP_WEEKS: usize = 26;
+
+/// The Stats page component that will be rendered when the current route is `[Route::Stats]`
+#[component]
+pub fn Stats() -> Element {
+    let days = use_loader(move || async move { daily_stats(HEATMAP_WEEKS * 7).await })?;
+    let sessions = use_loader(move || async move { session_history(20).await })?;
+    let days = days();
+    let recent = days[days.len().saturating_sub(CHART_DAYS)..].to_vec();
+    let total_reviews = recent.iter().map(|day| day.reviews).sum::<usize>();
+    let total_new = recent.iter().map(|day| day.new_cards).sum::<usize>();
+    let total_minutes = recent.iter().map(|day| day.seconds).sum::<u64>() / 60;
+    let pass_rate = {
+        let passed = recent.iter().map(|day| day.passed).sum::<usize>();
+        let graded = recent
+            .iter()
+            .map(|day| day.passed + day.failed + day.repeated)
+            .sum::<usize>();
+        (passed * 100)
+            .checked_div(graded)
+            .map_or("-".to_string(), |rate| format!("{rate}%"))
+    };
+    rsx! {
+        div { class: "container",
+            h1 { class: "title", "Statistics" }
+            nav { class: "level box",
+                LevelItem { heading: "Reviews", value: "{total_reviews}" }
+                LevelItem { heading: "New phrases", value: "{total_new}" }
+                LevelItem { heading: "Pass rate", value: pass_rate }
+                LevelItem { heading: "Minutes", value: "{total_minutes}" }
+            }
+            p { class: "help block", "Totals for the last {CHART_DAYS} days." }
+            div { class: "columns is-multiline",
+                div { class: "column is-half",
+                    ChartBox { title: "Reviews per day",
+                        BarChart {
+                            bars: recent.iter().map(|day| (day.date.clone(), day.reviews as f64)).collect::<Vec<_>>(),
+                            color: "has-background-primary",
+                        }
+                    }
+                }
+                div { class: "column is-half",
+                    ChartBox { title: "Pass rate",
+                        BarChart {
+                            bars: recent.iter().map(|day| (day.date.clone(), day.pass_rate().unwrap_or(0.0) * 100.0)).collect::<Vec<_>>(),
+                            color: "has-background-success",
+                            max: 100.0,
+                            unit: "%",
+                        }
+                    }
+                }
+                div { class: "column is-half",
+                    ChartBox { title: "New phrases per day",
+                        BarChart {
+                            bars: recent.iter().map(|day| (day.date.clone(), day.new_cards as f64)).collect::<Vec<_>>(),
+                            color: "has-background-info",
+                        }
+                    }
+                }
+                div { class: "column is-half",
+                    ChartBox { title: "Minutes per day",
+                        BarChart {
+                            bars: recent.iter().map(|day| (day.date.clone(), day.seconds as f64 / 60.0)).collect::<Vec<_>>(),
+                            color: "has-background-warning",
+                        }
+                    }
+                }
+            }
+            ChartBox { title: "Reviews over {HEATMAP_WEEKS} weeks",
+                Heatmap { days: days.clone() }
+            }
+            div { class: "box",
+                h2 { class: "title is-5", "Recent sessions" }
+                if sessions().is_empty() {
+                    p { "No finished sessions yet." }
+                } else {
+                    table { class: "table is-fullwidth is-striped",
+                        thead {
+                            tr {
+                                th { "Finished" }
+                                th { "Lesson" }
+                                th { "Passes" }
+                                th { "Fails" }
+                                th { "Repeats" }
+                                th { "Learns" }
+                                th { "Minutes" }
+                            }
+                        }
+                        tbody {
+                            for session in sessions() {
+                                tr { key: "{session.session_id}",
+                                    td { "{session.finished_at}" }
+                                    td {
+                                        "{session.lesson_title}"
+                                        if session.cram {
+                                            span { class: "tag is-light ml-2", "Cram" }
+                                        }
+                                    }
+                                    td { "{session.passed}" }
+                                    td { "{session.failed}" }
+                                    td { "{session.repeated}" }
+                                    td { "{session.learned}" }
+                                    td { "{session.seconds.div_ceil(60)}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LevelItem(heading: String, value: String) -> Element {
+    rsx! {
+        div { class: "level-item has-text-centered",
+            div {
+                p { class: "heading", {heading} }
+                p { class: "title", {value} }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ChartBox(title: String, children: Element) -> Element {
+    rsx! {
+        div { class: "box",
+            h2 { class: "title is-6", {title} }
+            {children}
+        }
+    }
+}
+
+/// Vertical bars scaled to `max`, or to the largest value when `max` is `None`.
+#[component]
+pub fn BarChart(
+    bars: Vec<(String, f64)>,
+    color: String,
+    max: Option<f64>,
+    #[props(default)] unit: String,
+) -> Element {
+    let max = max
+        .unwrap_or_else(|| bars.iter().map(|(_, value)| *value).fold(0.0, f64::max))
+        .max(1.0);
+    rsx! {
+        div { style: "display: flex; align-items: flex-end; gap: 2px; height: 8rem;",
+            for (label, value) in bars {
+                div {
+                    key: "{label}",
+                    class: "{color}",
+                    title: "{label}: {value:.0}{unit}",
+                    style: "flex: 1; min-height: 1px; height: {value / max * 100.0}%;",
+                }
+            }
+        }
+    }
+}
+
+/// A calendar grid of review counts, one column per week.
+#[component]
+fn Heatmap(days: Vec<DailyStats>) -> Element {
+    let max = days.iter().map(|day| day.reviews).max().unwrap_or(0).max(1);
+    rsx! {
+        div { style: "display: grid; grid-template-rows: repeat(7, 0.8rem); grid-auto-flow: column; grid-auto-columns: 0.8rem; gap: 2px; overflow-x: auto;",
+            for day in days {
+                div {
+                    key: "{day.date}",
+                    title: "{day.date}: {day.reviews} reviews",
+                    style: "border-radius: 2px; background-color: {heat_color(day.reviews, max)};",
+                }
+            }
+        }
+    }
+}
+
+fn heat_color(reviews: usize, max: usize) -> String {
+    if reviews == 0 {
+        return "hsl(0, 0%, 90%)".to_string();
+    }
+    let lightness = 75.0 - 50.0 * reviews as f64 / max as f64;
+    format!("hsl(153, 53%, {lightness:.0}%)")
+}
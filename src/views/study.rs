@@ -0,0 +1,129 @@
+use crate::core::data::study::{study_cards, StudyFilter};
+use crate::core::data::tag::user_tags;
+use crate::core::data::user_lessons;
+use crate::views::form::{form_number, form_text};
+use crate::views::practice::dialog::PracticeDialog;
+use crate::views::practice::PracticeSource;
+use dioxus::prelude::*;
+use std::collections::HashMap;
+
+/// The Study page component that will be rendered when the current route is `[Route::Study]`
+#[component]
+pub fn Study() -> Element {
+    let lessons = use_loader(move || async move { user_lessons().await })?;
+    let tags = use_loader(move || async move { user_tags().await })?;
+    let mut cramming = use_signal(|| None::<StudyFilter>);
+    // Checks the filter matches something before opening the session.
+    let mut find = use_action(move |filter: StudyFilter| async move {
+        let found = study_cards(filter.clone()).await?.len();
+        if found > 0 {
+            *cramming.write() = Some(filter);
+        }
+        Ok(found) as Result<usize>
+    });
+    let defaults = StudyFilter::default();
+    rsx! {
+        div { class: "container",
+            h1 { class: "title", "Custom study" }
+            p { class: "block",
+                "Drill any phrases, due or not. Answers in a custom session don't change when phrases come up for practice."
+            }
+            form { class: "box",
+                onsubmit: move |evt| async move {
+                    evt.prevent_default();
+                    let data = evt.values().into_iter().collect::<HashMap<_, _>>();
+                    find.call(StudyFilter {
+                        lesson_id: form_text(&data, "lesson_id").and_then(|id| id.parse().ok()),
+                        chapter: form_number(&data, "chapter"),
+                        tag: form_text(&data, "tag"),
+                        failed_today: form_text(&data, "failed_today").is_some(),
+                        leeches_only: form_text(&data, "leeches_only").is_some(),
+                        added_within_days: form_number(&data, "added_within_days"),
+                        limit: form_number(&data, "limit").unwrap_or(defaults.limit).max(1),
+                    });
+                },
+                div { class: "field",
+                    label { class: "label", "Lesson" }
+                    div { class: "control",
+                        div { class: "select",
+                            select { name: "lesson_id",
+                                option { value: "", "All lessons" }
+                                for lesson in lessons() {
+                                    option { key: "{lesson.lesson_id}", value: "{lesson.lesson_id}", {lesson.title} }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "field",
+                    label { class: "label", "Chapter" }
+                    div { class: "control",
+                        input { class: "input", type: "number", min: "1", name: "chapter", placeholder: "Any" }
+                    }
+                }
+                div { class: "field",
+                    label { class: "label", "Tag" }
+                    div { class: "control",
+                        div { class: "select",
+                            select { name: "tag",
+                                option { value: "", "Any" }
+                                for name in tags() {
+                                    option { key: "{name}", value: "{name}", "{name}" }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "field",
+                    label { class: "label", "Added in the last" }
+                    div { class: "field has-addons",
+                        div { class: "control",
+                            input { class: "input", type: "number", min: "1", name: "added_within_days", placeholder: "Any" }
+                        }
+                        div { class: "control",
+                            a { class: "button is-static", "days" }
+                        }
+                    }
+                }
+                div { class: "field",
+                    label { class: "checkbox",
+                        input { type: "checkbox", name: "failed_today", value: "true" }
+                        " Failed today"
+                    }
+                }
+                div { class: "field",
+                    label { class: "checkbox",
+                        input { type: "checkbox", name: "leeches_only", value: "true" }
+                        " Leeches only"
+                    }
+                }
+                div { class: "field",
+                    label { class: "label", "Cards" }
+                    div { class: "control",
+                        input { class: "input", type: "number", min: "1", name: "limit", value: "{defaults.limit}" }
+                    }
+                }
+                match find.value() {
+                    Some(Ok(found)) if found() == 0 => rsx! {
+                        p { class: "help is-warning", "No phrases match these filters." }
+                    },
+                    Some(Err(error)) => rsx! {
+                        p { class: "help is-danger", "{error}" }
+                    },
+                    _ => rsx! {},
+                }
+                button { class: "button is-primary", type: "submit", "Start studying" }
+            }
+        }
+        if let Some(filter) = cramming() {
+            PracticeDialog {
+                source: PracticeSource::Cram(filter),
+                title: "Custom Study",
+                subtitle: "Answers here don't change the schedule",
+                resume: None,
+                onsave: move |_| *cramming.write() = None,
+                onclose: move |_| *cramming.write() = None,
+            }
+        }
+    }
+}